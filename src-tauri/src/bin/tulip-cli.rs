@@ -0,0 +1,446 @@
+// tulip-cli: スレッド一覧・スレッドの表示と追跡・ログ検索・書き出しを行うコマンドライン版
+// アプリと同じライブラリと設定ディレクトリ (ログDB、NGルール) を使う。
+// 結果は標準出力に、進捗やエラーは標準エラー出力に出すので、パイプでつないで使える
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use std::collections::HashMap;
+use std::io::{self, Write};
+use std::path::Path;
+use std::process::ExitCode;
+use std::str::FromStr;
+use std::time::Duration;
+
+use tulip_browser_lib::ng::filter::load_ng_filter;
+use tulip_browser_lib::post::submit::PostSession;
+use tulip_browser_lib::settings::load_settings;
+use tulip_browser_lib::store::log_store::LogStore;
+use tulip_browser_lib::store::search::SearchQuery;
+use tulip_browser_lib::thread::board::BOARD_ID;
+use tulip_browser_lib::thread::body::plain_text;
+use tulip_browser_lib::thread::cache::ThreadCache;
+use tulip_browser_lib::thread::client::BoardClient;
+use tulip_browser_lib::thread::dat::{decode_dat_bytes, parse_dat_title, ResponseItem};
+use tulip_browser_lib::thread::export::{export_thread_to_file, ExportFormat};
+use tulip_browser_lib::thread::responses::load_thread_through_store;
+use tulip_browser_lib::thread::threads::{apply_thread_query, build_thread_list, ThreadQuery};
+
+const USAGE: &str = "使い方: tulip-cli <コマンド> [オプション]
+
+コマンド:
+  threads [--sort 基準] [--desc] [--title 文字列] [--regex] [--json]
+      スレッド一覧を表示する (スレタイNGを適用、ピン留めが先頭)
+      基準: server, created_at, last_update, response_count, unread_count, momentum
+  dump <スレッドID> [--from レス番号] [--no-ng] [--json]
+      スレッドを取得して表示する (ログDBにも保存される)
+  follow <スレッドID> [--interval 秒] [--last 件数] [--no-ng] [--json]
+      tail -f のように新着レスを表示し続ける (--json では1行1レスの JSON)
+  search <検索語...> [--board 板] [--since YYYY-MM-DD] [--until YYYY-MM-DD] [--limit 件数] [--json]
+      保存済みのログを全文検索する
+  export <スレッドID> [--format html|markdown|json|dat] [--output パス] [--no-ng]
+      スレッドをファイルに書き出す (パスを省略するとカレントディレクトリに書き出す)";
+
+// 値を取るオプション (それ以外の --xxx は値を取らないフラグとして扱う)
+const VALUE_OPTIONS: &[&str] = &[
+    "--sort",
+    "--title",
+    "--from",
+    "--interval",
+    "--last",
+    "--board",
+    "--since",
+    "--until",
+    "--limit",
+    "--format",
+    "--output",
+];
+
+// follow でサーバーに負荷をかけすぎないよう、取得間隔はこれより短くしない
+const MIN_FOLLOW_INTERVAL_SECS: u64 = 5;
+
+// コマンドの失敗。標準出力への書き込みの失敗は、出力先が閉じられた (head につないだ) 場合を見分けられるよう別にする
+enum CliError {
+    Message(String),
+    Io(io::Error),
+}
+
+impl From<String> for CliError {
+    fn from(message: String) -> Self {
+        CliError::Message(message)
+    }
+}
+
+impl From<io::Error> for CliError {
+    fn from(error: io::Error) -> Self {
+        CliError::Io(error)
+    }
+}
+
+struct Args {
+    positional: Vec<String>,
+    options: HashMap<String, String>,
+    flags: Vec<String>,
+}
+
+impl Args {
+    fn parse(mut args: impl Iterator<Item = String>) -> Result<Self, String> {
+        let mut parsed = Args {
+            positional: Vec::new(),
+            options: HashMap::new(),
+            flags: Vec::new(),
+        };
+        while let Some(arg) = args.next() {
+            if !arg.starts_with("--") {
+                parsed.positional.push(arg);
+                continue;
+            }
+            // --name=value の形も受け付ける
+            if let Some((name, value)) = arg.split_once('=') {
+                parsed.options.insert(name.to_string(), value.to_string());
+            } else if VALUE_OPTIONS.contains(&arg.as_str()) {
+                let value = args
+                    .next()
+                    .ok_or_else(|| format!("{} には値が必要です", arg))?;
+                parsed.options.insert(arg, value);
+            } else {
+                parsed.flags.push(arg);
+            }
+        }
+        Ok(parsed)
+    }
+
+    // コマンドが受け付けないオプションが指定されていればエラーにする (打ち間違いに気付けるように)
+    fn check_options(&self, allowed: &[&str]) -> Result<(), String> {
+        match self
+            .options
+            .keys()
+            .chain(self.flags.iter())
+            .find(|name| !allowed.contains(&name.as_str()))
+        {
+            Some(name) => Err(format!("このコマンドでは {} は使えません", name)),
+            None => Ok(()),
+        }
+    }
+
+    fn flag(&self, name: &str) -> bool {
+        self.flags.iter().any(|flag| flag == name)
+    }
+
+    fn option(&self, name: &str) -> Option<&str> {
+        self.options.get(name).map(String::as_str)
+    }
+
+    fn number<T: FromStr>(&self, name: &str, default: T) -> Result<T, String> {
+        match self.option(name) {
+            Some(value) => value
+                .parse()
+                .map_err(|_| format!("{} の値が不正です: {}", name, value)),
+            None => Ok(default),
+        }
+    }
+
+    fn thread_id(&self) -> Result<&str, String> {
+        match self.positional.as_slice() {
+            [thread_id] => Ok(thread_id),
+            [] => Err("スレッドIDを指定してください".to_string()),
+            _ => Err("スレッドIDは1つだけ指定してください".to_string()),
+        }
+    }
+}
+
+#[tokio::main(flavor = "current_thread")]
+async fn main() -> ExitCode {
+    let mut args = std::env::args().skip(1);
+    let command = match args.next() {
+        Some(command) if !matches!(command.as_str(), "-h" | "--help" | "help") => command,
+        Some(_) => {
+            // 出力先が閉じられていても、ヘルプの表示は成功とする
+            let _ = writeln!(io::stdout().lock(), "{}", USAGE);
+            return ExitCode::SUCCESS;
+        }
+        None => {
+            eprintln!("{}", USAGE);
+            return ExitCode::from(2);
+        }
+    };
+    let args = match Args::parse(args) {
+        Ok(args) => args,
+        Err(e) => {
+            eprintln!("エラー: {}", e);
+            return ExitCode::from(2);
+        }
+    };
+
+    let result = match command.as_str() {
+        "threads" => threads(&args).await,
+        "dump" => dump(&args).await,
+        "follow" => follow(&args).await,
+        "search" => search(&args),
+        "export" => export(&args).await,
+        other => {
+            eprintln!("不明なコマンドです: {}\n\n{}", other, USAGE);
+            return ExitCode::from(2);
+        }
+    };
+    match result {
+        Ok(()) => ExitCode::SUCCESS,
+        // head などで出力を途中まで読んで閉じられた場合は正常終了とする
+        Err(CliError::Io(e)) if e.kind() == io::ErrorKind::BrokenPipe => ExitCode::SUCCESS,
+        Err(CliError::Io(e)) => {
+            eprintln!("エラー: 標準出力に書き込めません: {}", e);
+            ExitCode::FAILURE
+        }
+        Err(CliError::Message(e)) => {
+            eprintln!("エラー: {}", e);
+            ExitCode::FAILURE
+        }
+    }
+}
+
+async fn threads(args: &Args) -> Result<(), CliError> {
+    args.check_options(&["--sort", "--desc", "--title", "--regex", "--json"])?;
+    let store = LogStore::open_default()?;
+    let session = open_session()?;
+    let entries = BoardClient::default().fetch_subject(session.http()).await?;
+    // アプリの「前回の更新からの変化」を変えないよう、前回の一覧は置き換えない
+    let mut list = build_thread_list(entries, &store, false);
+    let query = ThreadQuery {
+        sort: parse_name(args.option("--sort").unwrap_or("server"), "並び順")?,
+        descending: args.flag("--desc"),
+        title: args.option("--title").map(str::to_string),
+        title_is_regex: args.flag("--regex"),
+        refresh: false,
+    };
+    apply_thread_query(&mut list, &query)?;
+
+    let mut out = io::stdout().lock();
+    if args.flag("--json") {
+        return print_json(&mut out, &list);
+    }
+    // スレッドID, レス数, 未読数 (開いたことがなければ -), 勢い, タイトル のタブ区切り
+    for thread in &list.threads {
+        let unread = thread
+            .unread_count
+            .map_or_else(|| "-".to_string(), |count| count.to_string());
+        writeln!(
+            out,
+            "{}\t{}\t{}\t{:.1}\t{}",
+            thread.id, thread.response_count, unread, thread.momentum, thread.title
+        )?;
+    }
+    Ok(())
+}
+
+async fn dump(args: &Args) -> Result<(), CliError> {
+    args.check_options(&["--from", "--no-ng", "--json"])?;
+    let thread_id = args.thread_id()?;
+    let from = args.number("--from", 1usize)?;
+    let store = LogStore::open_default()?;
+    let session = open_session()?;
+    let responses = load_thread(&session, thread_id, &store, !args.flag("--no-ng")).await?;
+    let title = thread_title(&store, thread_id);
+    let shown = &responses[from.saturating_sub(1).min(responses.len())..];
+
+    let mut out = io::stdout().lock();
+    if args.flag("--json") {
+        return print_json(
+            &mut out,
+            &serde_json::json!({
+            "board": BOARD_ID,
+            "thread_id": thread_id,
+            "title": title,
+            "response_count": responses.len(),
+            "responses": shown,
+            }),
+        );
+    }
+    writeln!(out, "{}\n", title)?;
+    for response in shown {
+        print_response(&mut out, response)?;
+    }
+    Ok(())
+}
+
+async fn follow(args: &Args) -> Result<(), CliError> {
+    args.check_options(&["--interval", "--last", "--no-ng", "--json"])?;
+    let thread_id = args.thread_id()?;
+    let interval = args
+        .number("--interval", 30u64)?
+        .max(MIN_FOLLOW_INTERVAL_SECS);
+    let last = args.number("--last", 10usize)?;
+    let apply_ng = !args.flag("--no-ng");
+    let json = args.flag("--json");
+    let store = LogStore::open_default()?;
+    let session = open_session()?;
+
+    let mut responses = load_thread(&session, thread_id, &store, apply_ng).await?;
+    if !json {
+        writeln!(io::stdout().lock(), "{}\n", thread_title(&store, thread_id))?;
+    }
+    eprintln!("{} 秒ごとに新着レスを確認します (Ctrl+C で終了)", interval);
+    let mut printed = responses.len().saturating_sub(last);
+    loop {
+        let mut out = io::stdout().lock();
+        for response in &responses[printed..] {
+            if json {
+                let line = serde_json::to_string(response).map_err(|e| e.to_string())?;
+                writeln!(out, "{}", line)?;
+            } else {
+                print_response(&mut out, response)?;
+            }
+        }
+        printed = responses.len();
+        out.flush()?;
+        drop(out);
+
+        tokio::time::sleep(Duration::from_secs(interval)).await;
+        match load_thread(&session, thread_id, &store, apply_ng).await {
+            // 削除などでレスが減った場合は、その位置から表示し直す
+            Ok(latest) => {
+                printed = printed.min(latest.len());
+                responses = latest;
+            }
+            Err(e) => eprintln!("取得に失敗しました (次の確認で再試行します): {}", e),
+        }
+    }
+}
+
+fn search(args: &Args) -> Result<(), CliError> {
+    args.check_options(&["--board", "--since", "--until", "--limit", "--json"])?;
+    let query = args.positional.join(" ");
+    if query.trim().is_empty() {
+        return Err("検索語を指定してください".to_string().into());
+    }
+    let limit = match args.option("--limit") {
+        Some(_) => Some(args.number("--limit", 0usize)?),
+        None => None,
+    };
+    let store = LogStore::open_default()?;
+    let hits = store.search(&SearchQuery {
+        query,
+        board: args.option("--board").map(str::to_string),
+        from_date: args.option("--since").map(str::to_string),
+        to_date: args.option("--until").map(str::to_string),
+        limit,
+    })?;
+
+    let mut out = io::stdout().lock();
+    if args.flag("--json") {
+        return print_json(&mut out, &hits);
+    }
+    for hit in &hits {
+        // 一致箇所の <mark> は [ ] にして、残りのタグとエスケープを外す
+        let snippet = plain_text(&hit.snippet.replace("<mark>", "[").replace("</mark>", "]"));
+        writeln!(
+            out,
+            "{}/{} >>{} {}",
+            hit.board, hit.thread_id, hit.number, hit.thread_title
+        )?;
+        writeln!(
+            out,
+            "    {} {} {}",
+            plain_text(&hit.author),
+            hit.created_at,
+            hit.user_id.as_deref().unwrap_or_default()
+        )?;
+        writeln!(out, "    {}\n", snippet.replace('\n', " "))?;
+    }
+    eprintln!("{} 件見つかりました", hits.len());
+    Ok(())
+}
+
+async fn export(args: &Args) -> Result<(), CliError> {
+    args.check_options(&["--format", "--output", "--no-ng"])?;
+    let thread_id = args.thread_id()?;
+    let format_name = args.option("--format").unwrap_or("html");
+    let format: ExportFormat = parse_name(format_name, "形式")?;
+    let output = match args.option("--output") {
+        Some(path) => path.to_string(),
+        None => {
+            let extension = if format_name == "markdown" {
+                "md"
+            } else {
+                format_name
+            };
+            format!("{}.{}", thread_id, extension)
+        }
+    };
+
+    let store = LogStore::open_default()?;
+    let session = open_session()?;
+    let written = export_thread_to_file(
+        session.http(),
+        thread_id,
+        format,
+        Path::new(&output),
+        !args.flag("--no-ng"),
+        &ThreadCache::default(),
+        &store,
+    )
+    .await?;
+    eprintln!("{} バイトを書き出しました: {}", written, output);
+    Ok(())
+}
+
+// アプリと同じプロファイルの Cookie を使って取得する
+fn open_session() -> Result<PostSession, String> {
+    PostSession::open_profile(&load_settings().unwrap_or_default().profile)
+}
+
+// アプリと同じく、差分取得してログDBに保存したレスに、表示時の NG を適用する
+async fn load_thread(
+    session: &PostSession,
+    thread_id: &str,
+    store: &LogStore,
+    apply_ng: bool,
+) -> Result<Vec<ResponseItem>, String> {
+    let mut responses = load_thread_through_store(session.http(), thread_id, store).await?;
+    if apply_ng {
+        load_ng_filter(store, BOARD_ID, thread_id).apply(&mut responses);
+    }
+    Ok(responses)
+}
+
+fn thread_title(store: &LogStore, thread_id: &str) -> String {
+    store
+        .load_dat(BOARD_ID, thread_id)
+        .ok()
+        .flatten()
+        .and_then(|dat| parse_dat_title(&decode_dat_bytes(&dat).0))
+        .unwrap_or_default()
+}
+
+fn print_response(out: &mut impl Write, response: &ResponseItem) -> io::Result<()> {
+    if response.ng.is_some() {
+        return writeln!(out, "{} あぼーん\n", response.id);
+    }
+    let mail = if response.mail.is_empty() {
+        String::new()
+    } else {
+        format!(" [{}]", response.mail)
+    };
+    writeln!(
+        out,
+        "{} {}{} {} {}",
+        response.id,
+        plain_text(&response.author),
+        mail,
+        response.created_at,
+        response.user_id_info
+    )?;
+    for line in plain_text(&response.content).lines() {
+        writeln!(out, "    {}", line.trim())?;
+    }
+    writeln!(out)
+}
+
+// "momentum" や "markdown" などの名前を、フロントエンドと同じ serde の名前で列挙型に変換する
+fn parse_name<T: DeserializeOwned>(name: &str, what: &str) -> Result<T, String> {
+    serde_json::from_value(serde_json::Value::String(name.to_string()))
+        .map_err(|_| format!("{}の指定が不正です: {}", what, name))
+}
+
+fn print_json<T: Serialize>(out: &mut impl Write, value: &T) -> Result<(), CliError> {
+    let json = serde_json::to_string_pretty(value).map_err(|e| e.to_string())?;
+    writeln!(out, "{}", json)?;
+    Ok(())
+}
@@ -0,0 +1,174 @@
+// tulip_browser_lib: 掲示板の取得・パース、NG、ログDB などの本体
+// Tauri に依存する部分 (コマンド、メニュー、設定ウィンドウ) は `tauri` feature (デフォルトで有効) に含まれる。
+// ツールやテストから使う場合は default-features = false にすれば Tauri なしでビルドできる
+#[cfg(feature = "tauri")]
+pub mod menu {
+    pub mod menubar;
+    pub mod settings;
+}
+
+pub mod ng {
+    pub mod filter;
+    pub mod rules;
+    pub mod title;
+}
+
+pub mod post {
+    pub mod cookies;
+    pub mod create;
+    pub mod limits;
+    pub mod preview;
+    pub mod submit;
+    pub mod upload;
+}
+
+pub mod store {
+    pub mod drafts;
+    pub mod favorites;
+    pub mod import;
+    pub mod log_store;
+    pub mod my_posts;
+    pub mod pinned;
+    pub mod read_state;
+    pub mod replies;
+    pub mod search;
+    pub mod uploads;
+}
+
+pub mod thread {
+    pub mod board;
+    pub mod body;
+    pub mod cache;
+    pub mod client;
+    pub mod dat;
+    pub mod export;
+    pub mod fetch;
+    pub mod local;
+    pub mod normalize;
+    pub mod responses;
+    pub mod setting;
+    pub mod subject;
+    pub mod threads;
+}
+
+pub mod settings;
+
+#[cfg(test)]
+mod test_server;
+
+// アプリ本体を起動する (main.rs から呼ぶ)
+#[cfg(feature = "tauri")]
+pub fn run() {
+    use tauri::Manager;
+
+    tauri::Builder::default()
+        .plugin(tauri_plugin_store::Builder::default().build())
+        .plugin(tauri_plugin_dialog::init())
+        .plugin(tauri_plugin_notification::init())
+        .manage(thread::cache::ThreadCache::default())
+        .manage(thread::threads::ThreadListCache::default())
+        .manage(thread::local::StartupDat::from_args(std::env::args()))
+        .manage(
+            post::submit::PostSession::open_profile(
+                &settings::load_settings().unwrap_or_default().profile,
+            )
+            .expect("Failed to create HTTP client."),
+        )
+        .manage(store::log_store::LogStore::open_default().expect("Failed to open log store."))
+        .manage(store::drafts::DraftSaver::default())
+        .invoke_handler(tauri::generate_handler![
+            menu::settings::get_settings,
+            menu::settings::save_settings,
+            thread::threads::fetch_threads,
+            thread::threads::query_threads,
+            thread::responses::fetch_thread_content,
+            thread::responses::fetch_thread_content_stream,
+            thread::responses::get_responses,
+            thread::responses::get_last_responses,
+            thread::responses::get_responses_since,
+            thread::responses::fetch_image_as_base64,
+            thread::export::export_thread,
+            thread::local::open_local_dat,
+            thread::local::take_startup_dat,
+            post::submit::post_response,
+            post::preview::preview_post,
+            post::create::create_thread,
+            post::cookies::list_cookies,
+            post::cookies::clear_cookies,
+            post::cookies::import_cookie_file,
+            post::upload::upload_image,
+            post::upload::list_image_uploads,
+            post::upload::delete_image_upload,
+            store::read_state::mark_thread_read,
+            store::read_state::get_read_state,
+            store::favorites::list_favorites,
+            store::favorites::add_favorite,
+            store::favorites::remove_favorite,
+            store::favorites::move_favorite,
+            store::favorites::create_favorite_folder,
+            store::favorites::rename_favorite_folder,
+            store::favorites::delete_favorite_folder,
+            store::favorites::move_favorite_folder,
+            store::favorites::check_favorites,
+            ng::rules::list_ng_rules,
+            ng::rules::add_ng_rule,
+            ng::rules::remove_ng_rule,
+            ng::rules::get_ng_options,
+            ng::rules::set_ng_options,
+            ng::title::list_title_ng_rules,
+            ng::title::add_title_ng_rule,
+            ng::title::remove_title_ng_rule,
+            store::pinned::pin_thread,
+            store::pinned::unpin_thread,
+            store::search::search_logs,
+            store::my_posts::get_my_posts,
+            store::replies::get_reply_inbox,
+            store::replies::get_unread_reply_count,
+            store::replies::mark_replies_read,
+            store::drafts::save_draft,
+            store::drafts::get_draft,
+            store::drafts::list_drafts,
+            store::drafts::delete_draft,
+            store::import::import_logs
+        ])
+        .setup(|app| {
+            let app_handle = app.handle(); // AppHandle を取得
+
+            // 非同期タスク用に AppHandle をクローンしてムーブする
+            let async_task_app_handle = app_handle.clone();
+            tauri::async_runtime::spawn(async move {
+                println!("[Rust] Attempting to ensure settings are initialized on app setup...");
+                match menu::settings::get_settings(async_task_app_handle).await {
+                    // クローンしたハンドルを使用
+                    Ok(s) => println!("[Rust] Initial settings check OK on setup: {:?}", s),
+                    Err(e) => {
+                        eprintln!("[Rust] Error during initial settings check on setup: {}", e)
+                    }
+                }
+            });
+
+            // メニュー作成と設定には元の app_handle (またはそのクローン) を使用
+            let menu =
+                menu::menubar::create_app_menu(&app_handle).expect("Failed to create app menu."); // menuモジュールにあると仮定
+            app.set_menu(menu)?; // app を直接使うか、app_handle.set_menu(menu)? でも可
+
+            Ok(())
+        })
+        .on_menu_event(|app_handle, event| {
+            menu::menubar::handle_menu_event(app_handle, event);
+        })
+        .build(tauri::generate_context!())
+        .expect("error while building tauri application")
+        .run(|app_handle, event| {
+            // 終了する前に、まだログDBへ書いていない下書きを保存する
+            if let tauri::RunEvent::Exit = event {
+                let store = app_handle.state::<store::log_store::LogStore>();
+                if let Err(e) = app_handle
+                    .state::<store::drafts::DraftSaver>()
+                    .flush(&store)
+                {
+                    eprintln!("[Rust] 終了時の下書きの保存に失敗しました: {}", e);
+                }
+            }
+        });
+}
@@ -1,6 +1,6 @@
 #[allow(unused_imports)]
 use tauri::menu::{Menu, MenuEvent, MenuItemBuilder, PredefinedMenuItem, SubmenuBuilder};
-use tauri::{AppHandle, Manager, Runtime}; // AppHandle, Runtime を追加
+use tauri::{AppHandle, Emitter, Manager, Runtime}; // AppHandle, Runtime を追加
 
 use tauri::WebviewWindowBuilder; // WebviewWindowBuilder を追加
 
@@ -11,15 +11,23 @@ pub fn create_app_menu<R: Runtime>(app_handle: &AppHandle<R>) -> tauri::Result<M
         // .accelerator("CmdOrCtrl+,") // 必要であればアクセラレータを設定
         .build(app_handle)?;
 
-    // メインメニューの作成 (トップレベルに「設定...」アイテムのみ)
+    // 「ファイル」メニュー。ファイル選択ダイアログはフロントエンド側で開く
+    let open_dat_item = MenuItemBuilder::new("datファイルを開く...")
+        .id("open_dat")
+        .accelerator("CmdOrCtrl+O")
+        .build(app_handle)?;
+    let file_menu = SubmenuBuilder::new(app_handle, "ファイル")
+        .item(&open_dat_item)
+        .build()?;
+
+    // メインメニューの作成 (「ファイル」と「設定...」)
     // macOSの場合、これはアプリケーションメニュー（アプリ名）の下に配置されることが期待されます。
-    // Windows/Linuxでは、トップレベルにこのアイテムのみが表示されます。
-    let menu = Menu::with_items(app_handle, &[&settings_item])?;
+    let menu = Menu::with_items(app_handle, &[&file_menu, &settings_item])?;
 
     Ok(menu)
 }
 
-// メニューイベントを処理する関数 (Tauri v2) - 「設定」イベントのみ処理
+// メニューイベントを処理する関数 (Tauri v2)
 
 #[allow(unused)]
 pub fn handle_menu_event<R: Runtime>(app_handle: &AppHandle<R>, event: MenuEvent) {
@@ -66,6 +74,12 @@ pub fn handle_menu_event<R: Runtime>(app_handle: &AppHandle<R>, event: MenuEvent
                 }
             }
         }
+        "open_dat" => {
+            println!("Menu: 'open_dat' clicked.");
+            if let Err(e) = app_handle.emit_to("main", "menu-open-dat", ()) {
+                eprintln!("Failed to emit menu-open-dat: {}", e);
+            }
+        }
         // ... (他のメニューアイテムの処理) ...
         other_id => {
             // ...
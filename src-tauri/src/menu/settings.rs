@@ -1,47 +1,12 @@
 // src/setting.rs
-use dirs;
-use serde::{Deserialize, Serialize};
 use std::path::PathBuf;
 use std::sync::Arc;
 use tauri::Wry;
-use tauri::{AppHandle, Emitter};
+use tauri::{AppHandle, Emitter, Manager};
 use tauri_plugin_store::StoreBuilder; // Manager と Runtime を削除
 
-// AppSettings 構造体 (変更なし)
-#[derive(Serialize, Deserialize, Debug, Clone)]
-pub struct AppSettings {
-    theme: String,
-    font_size: u32,
-}
-// AppSettings のデフォルト値 (変更なし)
-impl Default for AppSettings {
-    fn default() -> Self {
-        Self {
-            theme: "light".to_string(),
-            font_size: 14,
-        }
-    }
-}
-
-const SETTINGS_STORE_PATH_FILENAME: &str = "setting.json";
-const SETTINGS_KEY: &str = "app_settings";
-
-// 設定ファイルのフルパスを取得し、ディレクトリがなければ作成する関数 (ログ強化版のまま)
-fn get_store_path() -> Result<PathBuf, String> {
-    let config_dir = dirs::config_dir()
-        .ok_or_else(|| "ユーザーの設定ディレクトリが見つかりませんでした。".to_string())?;
-    let app_config_subdir = config_dir.join("tulip-browser");
-    if !app_config_subdir.exists() {
-        std::fs::create_dir_all(&app_config_subdir).map_err(|e| {
-            format!(
-                "ディレクトリ '{}' の作成に失敗しました: {}",
-                app_config_subdir.display(),
-                e
-            )
-        })?;
-    }
-    Ok(app_config_subdir.join(SETTINGS_STORE_PATH_FILENAME))
-}
+use crate::post::submit::PostSession;
+use crate::settings::{get_store_path, AppSettings, SETTINGS_KEY};
 
 fn create_and_save_default_settings(
     store: &Arc<tauri_plugin_store::Store<Wry>>,
@@ -163,6 +128,12 @@ pub async fn save_settings(
     let path = get_store_path()?;
     println!("[Rust save_settings] ストアパス: '{}'", path.display());
 
+    // プロファイルが変わった場合は Cookie を読み替える (名前が使えなければ保存しない)
+    app_handle
+        .state::<PostSession>()
+        .cookies()
+        .switch_profile(&settings.profile)?;
+
     let store = StoreBuilder::new(&app_handle, path.clone())
         .build()
         .map_err(|e| format!("ストアの構築に失敗しました: {}", e))?;
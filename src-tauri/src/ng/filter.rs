@@ -0,0 +1,447 @@
+use regex::Regex;
+use serde::Serialize;
+use std::collections::{HashMap, HashSet};
+
+use super::rules::{NgOptions, NgRule, NgTarget};
+use super::title::compile_regex;
+use crate::store::log_store::LogStore;
+use crate::thread::body::{parse_anchors, plain_text};
+use crate::thread::dat::{parse_trip, ResponseItem};
+use crate::thread::normalize::{normalize, NormalizeOptions};
+
+// レスが一致したNGルール (ResponseItem.ng としてフロントエンドに渡す)
+#[derive(Debug, Serialize, Clone)]
+pub struct NgMatch {
+    pub rule_id: i64,
+    pub target: NgTarget,
+    pub pattern: String,
+    // 連鎖NGの段数 (0 はルールに直接一致したレス)
+    pub chain_depth: u32,
+    // 連鎖NGの場合、NGになったレスのうちアンカー先のレス番号
+    pub chain_from: Option<u32>,
+}
+
+enum Matcher {
+    Contains(String), // 本文・名前欄の部分一致
+    Exact(String),    // ID・トリップの完全一致
+    Regex(Regex),
+}
+
+struct CompiledRule {
+    rule: NgRule,
+    matcher: Matcher,
+}
+
+// NGルールをまとめて適用するフィルタ
+// 連鎖NGのため、NGにしたレスを覚えておく。同じスレッドのレスを番号順に渡すこと
+#[derive(Default)]
+pub struct NgFilter {
+    rules: Vec<CompiledRule>,
+    chain_depth: u32,             // 連鎖NGの最大段数 (0 なら連鎖NGしない)
+    muted: HashMap<u32, NgMatch>, // レス番号 -> 一致したNG
+}
+
+impl NgFilter {
+    pub fn new(rules: Vec<NgRule>, options: &NgOptions) -> Self {
+        let mut compiled = Vec::with_capacity(rules.len());
+        for rule in rules {
+            let matcher = match rule.target {
+                NgTarget::Word | NgTarget::Name => {
+                    Matcher::Contains(normalize(&rule.pattern, &rule.folds))
+                }
+                NgTarget::Id | NgTarget::Trip => {
+                    Matcher::Exact(normalize(rule.pattern.trim(), &rule.folds))
+                }
+                NgTarget::Regex => match compile_regex(&rule.pattern, &rule.folds) {
+                    Ok(regex) => Matcher::Regex(regex),
+                    Err(e) => {
+                        eprintln!(
+                            "[Rust NgFilter] 正規表現をコンパイルできないためルール {} を無視します: {}",
+                            rule.id, e
+                        );
+                        continue;
+                    }
+                },
+            };
+            // 正規化すると空になる語句 (ゼロ幅文字だけなど) は、すべてのレスに一致してしまうので使わない
+            if let Matcher::Contains(pattern) | Matcher::Exact(pattern) = &matcher {
+                if pattern.trim().is_empty() {
+                    eprintln!(
+                        "[Rust NgFilter] 正規化すると空になるためルール {} を無視します",
+                        rule.id
+                    );
+                    continue;
+                }
+            }
+            compiled.push(CompiledRule { rule, matcher });
+        }
+        Self {
+            rules: compiled,
+            chain_depth: if options.chain_enabled {
+                options.chain_depth
+            } else {
+                0
+            },
+            muted: HashMap::new(),
+        }
+    }
+
+    // 最初に一致したルールを返す
+    pub fn find_match(&self, response: &ResponseItem) -> Option<NgMatch> {
+        if self.rules.is_empty() {
+            return None;
+        }
+        let body = plain_text(&response.content);
+        let name = plain_text(&response.author);
+        let trip = parse_trip(&response.author);
+        // ルールごとに正規化の種類が違うため、正規化済みの文字列を種類ごとに使い回す
+        let mut normalized: HashMap<(NgTarget, NormalizeOptions), String> = HashMap::new();
+
+        self.rules
+            .iter()
+            .find(|compiled| {
+                let target = compiled.rule.target;
+                let subject = match target {
+                    NgTarget::Word | NgTarget::Regex => Some(body.as_str()),
+                    NgTarget::Name => Some(name.as_str()),
+                    NgTarget::Id => response.parsed_user_id.as_deref(),
+                    NgTarget::Trip => trip.as_deref(),
+                };
+                let Some(subject) = subject else {
+                    return false;
+                };
+                let folds = compiled.rule.folds;
+                let text = normalized
+                    .entry((target, folds))
+                    .or_insert_with(|| normalize(subject, &folds));
+                match &compiled.matcher {
+                    Matcher::Contains(pattern) => text.contains(pattern.as_str()),
+                    Matcher::Exact(pattern) => text == pattern,
+                    Matcher::Regex(regex) => regex.is_match(text),
+                }
+            })
+            .map(|compiled| NgMatch {
+                rule_id: compiled.rule.id,
+                target: compiled.rule.target,
+                pattern: compiled.rule.pattern.clone(),
+                chain_depth: 0,
+                chain_from: None,
+            })
+    }
+
+    // アンカーがすべてNGのレスを指している場合、連鎖NGとして一致を返す
+    // アンカー先のうち最も段数の浅いNGから1段深いものとして扱う
+    fn find_chain_match(&self, response: &ResponseItem) -> Option<NgMatch> {
+        if self.chain_depth == 0 || self.muted.is_empty() {
+            return None;
+        }
+        let number: u32 = response.id.parse().ok()?;
+        let anchors: Vec<u32> = parse_anchors(&response.content)
+            .into_iter()
+            .filter(|&anchor| anchor < number)
+            .collect();
+        if anchors.is_empty() {
+            return None;
+        }
+        let mut closest: Option<(u32, &NgMatch)> = None;
+        for anchor in anchors {
+            let muted = self.muted.get(&anchor)?; // NGでないレスへのアンカーがあれば連鎖しない
+            if closest.is_none_or(|(_, c)| muted.chain_depth < c.chain_depth) {
+                closest = Some((anchor, muted));
+            }
+        }
+        let (anchor, root) = closest?;
+        if root.chain_depth >= self.chain_depth {
+            return None;
+        }
+        Some(NgMatch {
+            chain_depth: root.chain_depth + 1,
+            chain_from: Some(anchor),
+            ..root.clone()
+        })
+    }
+
+    // レスを判定し、連鎖NGのために一致したレスを覚えておく
+    fn judge(&mut self, response: &ResponseItem) -> Option<NgMatch> {
+        let ng = self
+            .find_match(response)
+            .or_else(|| self.find_chain_match(response));
+        if self.chain_depth > 0 {
+            if let (Some(ng), Ok(number)) = (&ng, response.id.parse::<u32>()) {
+                self.muted.insert(number, ng.clone());
+            }
+        }
+        ng
+    }
+
+    // 一致したレスに NG の印を付ける
+    pub fn apply(&mut self, responses: &mut [ResponseItem]) {
+        if self.rules.is_empty() {
+            return;
+        }
+        for response in responses.iter_mut() {
+            response.ng = self.judge(response);
+        }
+    }
+
+    // レスに印を付けずに、NGになるレスの番号だけを返す
+    pub fn find_muted(&mut self, responses: &[ResponseItem]) -> HashSet<u32> {
+        if self.rules.is_empty() {
+            return HashSet::new();
+        }
+        responses
+            .iter()
+            .filter_map(|response| {
+                self.judge(response)?;
+                response.id.parse().ok()
+            })
+            .collect()
+    }
+}
+
+// NG を適用したレスのうち、NGになったレスの番号
+pub fn muted_numbers(responses: &[ResponseItem]) -> HashSet<u32> {
+    responses
+        .iter()
+        .filter(|response| response.ng.is_some())
+        .filter_map(|response| response.id.parse().ok())
+        .collect()
+}
+
+// スレッドに適用されるNGルールを読み込んでフィルタを作る
+// 読み込みに失敗した場合は何もNGにしないフィルタを返す (表示を妨げないため)
+pub fn load_ng_filter(store: &LogStore, board: &str, thread_id: &str) -> NgFilter {
+    let options = store.ng_options().unwrap_or_else(|e| {
+        eprintln!(
+            "[Rust load_ng_filter] NG設定の読み込みに失敗しました: {}",
+            e
+        );
+        NgOptions::default()
+    });
+    match store.active_ng_rules(board, thread_id) {
+        Ok(rules) => NgFilter::new(rules, &options),
+        Err(e) => {
+            eprintln!(
+                "[Rust load_ng_filter] NGルールの読み込みに失敗しました: {}",
+                e
+            );
+            NgFilter::default()
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ng::rules::{NgRuleInput, NgScope};
+    use crate::thread::dat::parse_dat;
+    use std::path::Path;
+
+    fn rule(id: i64, target: NgTarget, pattern: &str, folds: NormalizeOptions) -> NgRule {
+        NgRule {
+            id,
+            target,
+            pattern: pattern.to_string(),
+            scope: NgScope::Global,
+            expires_at: None,
+            created_at: 0,
+            folds,
+        }
+    }
+
+    // (名前欄, ID, 本文) の並びを dat にしてパースする
+    fn responses(lines: &[(&str, &str, &str)]) -> Vec<ResponseItem> {
+        let dat: String = lines
+            .iter()
+            .map(|(name, id, body)| {
+                format!(
+                    "{}<>sage<>2024/01/01(月) 00:00:00.00 ID:{}<> {} <>\n",
+                    name, id, body
+                )
+            })
+            .collect();
+        parse_dat(&dat)
+    }
+
+    fn muted(filter: &mut NgFilter, responses: &[ResponseItem]) -> Vec<u32> {
+        let mut numbers: Vec<u32> = filter.find_muted(responses).into_iter().collect();
+        numbers.sort();
+        numbers
+    }
+
+    fn no_chain() -> NgOptions {
+        NgOptions {
+            chain_enabled: false,
+            chain_depth: 0,
+        }
+    }
+
+    #[test]
+    fn matches_each_target() {
+        let thread = responses(&[
+            ("名無し", "AAA", "普通のレス"),
+            ("コテ ◆Trip123", "BBB", "宣伝です"),
+            ("名無し", "CCC", "ＵＲＬはこちら"),
+            ("名無し", "AAAB", "センデン"),
+        ]);
+        let kana_and_width = NormalizeOptions {
+            nfkc: true,
+            kana: true,
+            case: true,
+            zero_width: false,
+        };
+        let plain = NormalizeOptions::default();
+        for (rule, expected) in [
+            (rule(1, NgTarget::Word, "宣伝", plain), vec![2]),
+            (rule(2, NgTarget::Word, "せんでん", kana_and_width), vec![4]),
+            (rule(3, NgTarget::Id, " AAA ", plain), vec![1]),
+            (rule(4, NgTarget::Name, "コテ", plain), vec![2]),
+            (rule(5, NgTarget::Name, "宣伝", plain), vec![]),
+            (rule(6, NgTarget::Trip, "Trip123", plain), vec![2]),
+            (rule(7, NgTarget::Trip, "Trip12", plain), vec![]),
+            (rule(8, NgTarget::Regex, "^url", kana_and_width), vec![3]),
+            (rule(9, NgTarget::Regex, "^url", plain), vec![]),
+            (rule(10, NgTarget::Regex, "(", plain), vec![]),
+        ] {
+            let id = rule.id;
+            let mut filter = NgFilter::new(vec![rule], &no_chain());
+            assert_eq!(muted(&mut filter, &thread), expected, "ルール {}", id);
+        }
+
+        let mut thread = thread;
+        NgFilter::new(vec![rule(6, NgTarget::Trip, "Trip123", plain)], &no_chain())
+            .apply(&mut thread);
+        let ng = thread[1].ng.as_ref().unwrap();
+        assert_eq!((ng.rule_id, ng.target), (6, NgTarget::Trip));
+        assert_eq!((ng.chain_depth, ng.chain_from), (0, None));
+        assert!(thread[0].ng.is_none());
+    }
+
+    #[test]
+    fn patterns_that_normalize_to_nothing_are_ignored() {
+        let thread = responses(&[("名無し", "AAA", "レス")]);
+        let zero_width = NormalizeOptions {
+            zero_width: true,
+            ..Default::default()
+        };
+        let mut filter = NgFilter::new(
+            vec![
+                rule(1, NgTarget::Word, "\u{200B}\u{200D}", zero_width),
+                rule(2, NgTarget::Name, " ", NormalizeOptions::default()),
+                rule(3, NgTarget::Id, "\u{FEFF}", zero_width),
+            ],
+            &no_chain(),
+        );
+        assert!(filter.rules.is_empty());
+        assert!(muted(&mut filter, &thread).is_empty());
+    }
+
+    #[test]
+    fn only_active_rules_in_scope_are_loaded() {
+        let store = LogStore::open(Path::new(":memory:")).unwrap();
+        let add = |pattern: &str, scope: NgScope, duration_secs: Option<i64>| {
+            store
+                .add_ng_rule(&NgRuleInput {
+                    target: NgTarget::Word,
+                    pattern: pattern.to_string(),
+                    scope,
+                    duration_secs,
+                    folds: NormalizeOptions::default(),
+                })
+                .unwrap();
+        };
+        let board = |board: &str| NgScope::Board {
+            board: board.to_string(),
+        };
+        let thread = |thread_id: &str| NgScope::Thread {
+            board: "test".to_string(),
+            thread_id: thread_id.to_string(),
+        };
+        add("期限切れ", NgScope::Global, Some(-10));
+        add("期限内", NgScope::Global, Some(3600));
+        add("別の板", board("other"), None);
+        add("この板", board("test"), None);
+        add("別のスレ", thread("1700000001"), None);
+        add("このスレ", thread("1700000000"), None);
+
+        let lines = [
+            "期限切れ",
+            "期限内",
+            "別の板",
+            "この板",
+            "別のスレ",
+            "このスレ",
+        ];
+        let thread_responses = responses(
+            &lines
+                .iter()
+                .map(|body| ("名無し", "AAA", *body))
+                .collect::<Vec<_>>(),
+        );
+        let mut filter = load_ng_filter(&store, "test", "1700000000");
+        assert_eq!(muted(&mut filter, &thread_responses), [2, 4, 6]);
+
+        // 期限切れのルールは一覧を読むときに削除される
+        let patterns: Vec<String> = store
+            .list_ng_rules()
+            .unwrap()
+            .into_iter()
+            .map(|rule| rule.pattern)
+            .collect();
+        assert!(!patterns.contains(&"期限切れ".to_string()));
+        assert_eq!(patterns.len(), 5);
+    }
+
+    fn chain_thread() -> Vec<ResponseItem> {
+        responses(&[
+            ("名無し", "A", "NGワード"),
+            ("名無し", "B", "&gt;&gt;1"),
+            ("名無し", "C", "&gt;&gt;2"),
+            ("名無し", "D", "&gt;&gt;3"),
+            ("名無し", "E", "普通のレス"),
+            ("名無し", "F", "&gt;&gt;1 &gt;&gt;5"),
+            ("名無し", "G", "&gt;&gt;3 &gt;&gt;2"),
+            ("名無し", "H", "&gt;&gt;1 &gt;&gt;99"),
+        ])
+    }
+
+    fn chain_filter(chain_enabled: bool, chain_depth: u32) -> NgFilter {
+        NgFilter::new(
+            vec![rule(
+                1,
+                NgTarget::Word,
+                "NGワード",
+                NormalizeOptions::default(),
+            )],
+            &NgOptions {
+                chain_enabled,
+                chain_depth,
+            },
+        )
+    }
+
+    #[test]
+    fn chain_ng_follows_anchors_to_muted_responses() {
+        let thread = chain_thread();
+        assert_eq!(muted(&mut chain_filter(false, 3), &thread), [1]);
+        // 1段なら直接NGになったレスへのアンカーだけ。後ろのレスへのアンカーは数えない
+        assert_eq!(muted(&mut chain_filter(true, 1), &thread), [1, 2, 8]);
+        // NGでないレス (5) へのアンカーがあれば連鎖しない
+        assert_eq!(
+            muted(&mut chain_filter(true, 3), &thread),
+            [1, 2, 3, 4, 7, 8]
+        );
+
+        let mut thread = thread;
+        chain_filter(true, 3).apply(&mut thread);
+        let chain = |index: usize| {
+            let ng = thread[index].ng.as_ref().unwrap();
+            (ng.rule_id, ng.chain_depth, ng.chain_from)
+        };
+        assert_eq!(chain(1), (1, 1, Some(1)));
+        assert_eq!(chain(3), (1, 3, Some(3)));
+        // アンカー先のうち段数の浅い方 (2) から数える
+        assert_eq!(chain(6), (1, 2, Some(2)));
+        assert!(thread[5].ng.is_none());
+    }
+}
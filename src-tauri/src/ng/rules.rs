@@ -0,0 +1,282 @@
+use rusqlite::{params, OptionalExtension, Row};
+use serde::{Deserialize, Serialize};
+#[cfg(feature = "tauri")]
+use tauri::State;
+
+use crate::store::log_store::LogStore;
+#[cfg(feature = "tauri")]
+use crate::thread::normalize::normalize;
+use crate::thread::normalize::NormalizeOptions;
+
+// NGルールが何を対象にするか
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Hash)]
+#[serde(rename_all = "snake_case")]
+pub enum NgTarget {
+    Word,  // 本文に含まれる語句
+    Id,    // ID (parsed_user_id と完全一致)
+    Name,  // 名前欄に含まれる語句
+    Trip,  // トリップ (完全一致)
+    Regex, // 本文に対する正規表現
+}
+
+impl NgTarget {
+    fn as_str(&self) -> &'static str {
+        match self {
+            NgTarget::Word => "word",
+            NgTarget::Id => "id",
+            NgTarget::Name => "name",
+            NgTarget::Trip => "trip",
+            NgTarget::Regex => "regex",
+        }
+    }
+
+    fn from_db(value: &str) -> Option<Self> {
+        match value {
+            "word" => Some(NgTarget::Word),
+            "id" => Some(NgTarget::Id),
+            "name" => Some(NgTarget::Name),
+            "trip" => Some(NgTarget::Trip),
+            "regex" => Some(NgTarget::Regex),
+            _ => None,
+        }
+    }
+}
+
+// NGルールの適用範囲
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq)]
+#[serde(rename_all = "snake_case", tag = "type")]
+pub enum NgScope {
+    Global,
+    Board { board: String },
+    Thread { board: String, thread_id: String },
+}
+
+#[derive(Debug, Serialize, Clone)]
+pub struct NgRule {
+    pub id: i64,
+    pub target: NgTarget,
+    pub pattern: String,
+    pub scope: NgScope,
+    pub expires_at: Option<i64>, // 期限 (Unixタイムスタンプ)。None は無期限
+    pub created_at: i64,
+    pub folds: NormalizeOptions, // 照合の前に行う正規化
+}
+
+// フロントエンドから受け取る新しいNGルール
+#[derive(Debug, Deserialize, Clone)]
+pub struct NgRuleInput {
+    pub target: NgTarget,
+    pub pattern: String,
+    pub scope: NgScope,
+    pub duration_secs: Option<i64>, // 例: 24時間NGなら 86400。None は無期限
+    #[serde(default)]
+    pub folds: NormalizeOptions,
+}
+
+// NG全体の設定
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct NgOptions {
+    pub chain_enabled: bool, // 連鎖NG (NGレスにだけアンカーしているレスもNGにする)
+    pub chain_depth: u32,    // 連鎖NGをたどる段数
+}
+
+impl Default for NgOptions {
+    fn default() -> Self {
+        Self {
+            chain_enabled: false,
+            chain_depth: 3,
+        }
+    }
+}
+
+const NG_RULE_COLUMNS: &str =
+    "id, target, pattern, scope_board, scope_thread, expires_at, created_at, folds";
+
+fn ng_rule_from_row(row: &Row<'_>) -> rusqlite::Result<NgRule> {
+    let target: String = row.get(1)?;
+    let scope_board: Option<String> = row.get(3)?;
+    let scope_thread: Option<String> = row.get(4)?;
+    let scope = match (scope_board, scope_thread) {
+        (Some(board), Some(thread_id)) => NgScope::Thread { board, thread_id },
+        (Some(board), None) => NgScope::Board { board },
+        _ => NgScope::Global,
+    };
+    Ok(NgRule {
+        id: row.get(0)?,
+        target: NgTarget::from_db(&target).ok_or_else(|| {
+            rusqlite::Error::FromSqlConversionFailure(
+                1,
+                rusqlite::types::Type::Text,
+                format!("不明なNG対象です: {}", target).into(),
+            )
+        })?,
+        pattern: row.get(2)?,
+        scope,
+        expires_at: row.get(5)?,
+        created_at: row.get(6)?,
+        folds: NormalizeOptions::from_bits(row.get(7)?),
+    })
+}
+
+impl LogStore {
+    // すべてのNGルール (期限切れのものは先に削除する)
+    pub fn list_ng_rules(&self) -> Result<Vec<NgRule>, String> {
+        let conn = self.conn()?;
+        conn.execute(
+            "DELETE FROM ng_rules WHERE expires_at IS NOT NULL AND expires_at <= ?1",
+            params![chrono::Utc::now().timestamp()],
+        )
+        .map_err(|e| e.to_string())?;
+        let mut stmt = conn
+            .prepare(&format!(
+                "SELECT {} FROM ng_rules ORDER BY id",
+                NG_RULE_COLUMNS
+            ))
+            .map_err(|e| e.to_string())?;
+        let rows = stmt
+            .query_map([], ng_rule_from_row)
+            .map_err(|e| e.to_string())?;
+        rows.collect::<Result<Vec<_>, _>>()
+            .map_err(|e| format!("NGルールの読み込みに失敗しました: {}", e))
+    }
+
+    // 指定したスレッドに適用される、期限内のNGルール
+    pub fn active_ng_rules(&self, board: &str, thread_id: &str) -> Result<Vec<NgRule>, String> {
+        let conn = self.conn()?;
+        let mut stmt = conn
+            .prepare(&format!(
+                "SELECT {} FROM ng_rules
+                 WHERE (expires_at IS NULL OR expires_at > ?3)
+                   AND (scope_board IS NULL
+                        OR (scope_board = ?1 AND (scope_thread IS NULL OR scope_thread = ?2)))
+                 ORDER BY id",
+                NG_RULE_COLUMNS
+            ))
+            .map_err(|e| e.to_string())?;
+        let rows = stmt
+            .query_map(
+                params![board, thread_id, chrono::Utc::now().timestamp()],
+                ng_rule_from_row,
+            )
+            .map_err(|e| e.to_string())?;
+        rows.collect::<Result<Vec<_>, _>>()
+            .map_err(|e| format!("NGルールの読み込みに失敗しました: {}", e))
+    }
+
+    pub fn add_ng_rule(&self, input: &NgRuleInput) -> Result<NgRule, String> {
+        let now = chrono::Utc::now().timestamp();
+        let expires_at = input.duration_secs.map(|secs| now + secs);
+        let (scope_board, scope_thread) = match &input.scope {
+            NgScope::Global => (None, None),
+            NgScope::Board { board } => (Some(board.as_str()), None),
+            NgScope::Thread { board, thread_id } => {
+                (Some(board.as_str()), Some(thread_id.as_str()))
+            }
+        };
+        let conn = self.conn()?;
+        conn.execute(
+            "INSERT INTO ng_rules
+                (target, pattern, scope_board, scope_thread, expires_at, created_at, folds)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)",
+            params![
+                input.target.as_str(),
+                input.pattern,
+                scope_board,
+                scope_thread,
+                expires_at,
+                now,
+                input.folds.to_bits()
+            ],
+        )
+        .map_err(|e| format!("NGルールの追加に失敗しました: {}", e))?;
+        Ok(NgRule {
+            id: conn.last_insert_rowid(),
+            target: input.target,
+            pattern: input.pattern.clone(),
+            scope: input.scope.clone(),
+            expires_at,
+            created_at: now,
+            folds: input.folds,
+        })
+    }
+
+    pub fn ng_options(&self) -> Result<NgOptions, String> {
+        let conn = self.conn()?;
+        conn.query_row(
+            "SELECT chain_enabled, chain_depth FROM ng_options WHERE id = 1",
+            [],
+            |row| {
+                Ok(NgOptions {
+                    chain_enabled: row.get(0)?,
+                    chain_depth: row.get(1)?,
+                })
+            },
+        )
+        .optional()
+        .map(|options| options.unwrap_or_default())
+        .map_err(|e| format!("NG設定の読み込みに失敗しました: {}", e))
+    }
+
+    pub fn set_ng_options(&self, options: &NgOptions) -> Result<(), String> {
+        let conn = self.conn()?;
+        conn.execute(
+            "INSERT INTO ng_options (id, chain_enabled, chain_depth) VALUES (1, ?1, ?2)
+             ON CONFLICT (id) DO UPDATE SET
+                chain_enabled = excluded.chain_enabled,
+                chain_depth = excluded.chain_depth",
+            params![options.chain_enabled, options.chain_depth],
+        )
+        .map_err(|e| format!("NG設定の保存に失敗しました: {}", e))?;
+        Ok(())
+    }
+
+    pub fn remove_ng_rule(&self, rule_id: i64) -> Result<(), String> {
+        let conn = self.conn()?;
+        conn.execute("DELETE FROM ng_rules WHERE id = ?1", params![rule_id])
+            .map_err(|e| format!("NGルールの削除に失敗しました: {}", e))?;
+        Ok(())
+    }
+}
+
+#[cfg(feature = "tauri")]
+#[tauri::command]
+pub async fn list_ng_rules(store: State<'_, LogStore>) -> Result<Vec<NgRule>, String> {
+    store.list_ng_rules()
+}
+
+#[cfg(feature = "tauri")]
+#[tauri::command]
+pub async fn add_ng_rule(rule: NgRuleInput, store: State<'_, LogStore>) -> Result<NgRule, String> {
+    if rule.pattern.trim().is_empty() {
+        return Err("NGにする内容を入力してください。".to_string());
+    }
+    if rule.target != NgTarget::Regex && normalize(&rule.pattern, &rule.folds).trim().is_empty() {
+        return Err(
+            "正規化すると空になるため NG にできません (ゼロ幅文字だけの語句など)。".to_string(),
+        );
+    }
+    if rule.target == NgTarget::Regex {
+        regex::Regex::new(&rule.pattern).map_err(|e| format!("正規表現が不正です: {}", e))?;
+    }
+    eprintln!("[Rust add_ng_rule] NGルールを追加します: {:?}", rule);
+    store.add_ng_rule(&rule)
+}
+
+#[cfg(feature = "tauri")]
+#[tauri::command]
+pub async fn remove_ng_rule(rule_id: i64, store: State<'_, LogStore>) -> Result<(), String> {
+    store.remove_ng_rule(rule_id)
+}
+
+#[cfg(feature = "tauri")]
+#[tauri::command]
+pub async fn get_ng_options(store: State<'_, LogStore>) -> Result<NgOptions, String> {
+    store.ng_options()
+}
+
+#[cfg(feature = "tauri")]
+#[tauri::command]
+pub async fn set_ng_options(options: NgOptions, store: State<'_, LogStore>) -> Result<(), String> {
+    eprintln!("[Rust set_ng_options] NG設定を保存します: {:?}", options);
+    store.set_ng_options(&options)
+}
@@ -0,0 +1,277 @@
+use regex::Regex;
+use rusqlite::params;
+use serde::Serialize;
+#[cfg(feature = "tauri")]
+use tauri::State;
+
+use crate::store::log_store::LogStore;
+#[cfg(feature = "tauri")]
+use crate::thread::board::BOARD_ID;
+use crate::thread::normalize::{normalize, NormalizeOptions};
+
+// スレッドタイトルのNGルール (板ごと)
+#[derive(Debug, Serialize, Clone)]
+pub struct TitleNgRule {
+    pub id: i64,
+    pub board: String,
+    pub pattern: String,
+    pub is_regex: bool, // false ならタイトルに含まれる語句
+    pub created_at: i64,
+    pub folds: NormalizeOptions, // 照合の前に行う正規化
+}
+
+impl LogStore {
+    pub fn list_title_ng_rules(&self, board: &str) -> Result<Vec<TitleNgRule>, String> {
+        let conn = self.conn()?;
+        let mut stmt = conn
+            .prepare(
+                "SELECT id, board, pattern, is_regex, created_at, folds
+                 FROM title_ng_rules WHERE board = ?1 ORDER BY id",
+            )
+            .map_err(|e| e.to_string())?;
+        let rows = stmt
+            .query_map(params![board], |row| {
+                Ok(TitleNgRule {
+                    id: row.get(0)?,
+                    board: row.get(1)?,
+                    pattern: row.get(2)?,
+                    is_regex: row.get(3)?,
+                    created_at: row.get(4)?,
+                    folds: NormalizeOptions::from_bits(row.get(5)?),
+                })
+            })
+            .map_err(|e| e.to_string())?;
+        rows.collect::<Result<Vec<_>, _>>()
+            .map_err(|e| format!("スレタイNGの読み込みに失敗しました: {}", e))
+    }
+
+    pub fn add_title_ng_rule(
+        &self,
+        board: &str,
+        pattern: &str,
+        is_regex: bool,
+        folds: NormalizeOptions,
+    ) -> Result<TitleNgRule, String> {
+        let now = chrono::Utc::now().timestamp();
+        let conn = self.conn()?;
+        conn.execute(
+            "INSERT INTO title_ng_rules (board, pattern, is_regex, created_at, folds)
+             VALUES (?1, ?2, ?3, ?4, ?5)",
+            params![board, pattern, is_regex, now, folds.to_bits()],
+        )
+        .map_err(|e| format!("スレタイNGの追加に失敗しました: {}", e))?;
+        Ok(TitleNgRule {
+            id: conn.last_insert_rowid(),
+            board: board.to_string(),
+            pattern: pattern.to_string(),
+            is_regex,
+            created_at: now,
+            folds,
+        })
+    }
+
+    pub fn remove_title_ng_rule(&self, rule_id: i64) -> Result<(), String> {
+        let conn = self.conn()?;
+        conn.execute("DELETE FROM title_ng_rules WHERE id = ?1", params![rule_id])
+            .map_err(|e| format!("スレタイNGの削除に失敗しました: {}", e))?;
+        Ok(())
+    }
+}
+
+enum TitleMatcher {
+    Contains(String),
+    Regex(Regex),
+}
+
+// スレッド一覧に適用するタイトルNG
+#[derive(Default)]
+pub struct TitleFilter {
+    matchers: Vec<(NormalizeOptions, TitleMatcher)>,
+}
+
+impl TitleFilter {
+    pub fn new(rules: &[TitleNgRule]) -> Self {
+        let matchers = rules
+            .iter()
+            .filter_map(|rule| {
+                if !rule.is_regex {
+                    let pattern = normalize(&rule.pattern, &rule.folds);
+                    return Some((rule.folds, TitleMatcher::Contains(pattern)));
+                }
+                match compile_regex(&rule.pattern, &rule.folds) {
+                    Ok(regex) => Some((rule.folds, TitleMatcher::Regex(regex))),
+                    Err(e) => {
+                        eprintln!(
+                            "[Rust TitleFilter] 正規表現をコンパイルできないためスレタイNG {} を無視します: {}",
+                            rule.id, e
+                        );
+                        None
+                    }
+                }
+            })
+            .collect();
+        Self { matchers }
+    }
+
+    pub fn is_match(&self, title: &str) -> bool {
+        self.matchers.iter().any(|(folds, matcher)| {
+            let title = normalize(title, folds);
+            match matcher {
+                TitleMatcher::Contains(pattern) => title.contains(pattern.as_str()),
+                TitleMatcher::Regex(regex) => regex.is_match(&title),
+            }
+        })
+    }
+}
+
+// 正規表現のNGは対象の文字列を正規化してから照合するので、パターンの文字にも同じ正規化をかけておく
+// (カタカナや全角英数のパターンが、ひらがな・半角に揃えた文字列にも一致するように)
+// 書き換えるのは ASCII 以外の文字だけで、記号やエスケープ (\p{Katakana} など) はそのまま残す
+// 大文字・小文字を区別しない場合は正規表現側でも無視する
+pub(crate) fn compile_regex(
+    pattern: &str,
+    folds: &NormalizeOptions,
+) -> Result<Regex, regex::Error> {
+    regex::RegexBuilder::new(&fold_regex_literals(pattern, folds))
+        .case_insensitive(folds.case)
+        .build()
+}
+
+fn fold_regex_literals(pattern: &str, folds: &NormalizeOptions) -> String {
+    if folds.is_empty() {
+        return pattern.to_string();
+    }
+    let mut folded = String::with_capacity(pattern.len());
+    let mut chars = pattern.chars().peekable();
+    let mut in_class = false;
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' => {
+                folded.push(c);
+                let Some(escaped) = chars.next() else {
+                    break;
+                };
+                folded.push(escaped);
+                // \p{..} や \x{..} は閉じ括弧まで書き換えない
+                if chars.peek() == Some(&'{') {
+                    for c in chars.by_ref() {
+                        folded.push(c);
+                        if c == '}' {
+                            break;
+                        }
+                    }
+                }
+            }
+            '[' if !in_class => {
+                in_class = true;
+                folded.push(c);
+            }
+            ']' if in_class => {
+                in_class = false;
+                folded.push(c);
+            }
+            c if c.is_ascii() => folded.push(c),
+            c => {
+                // 正規化で記号になる文字 (全角の括弧など) は、元と同じく文字そのものとして扱う
+                let literal = regex::escape(&normalize(&c.to_string(), folds));
+                // ㍻ → 平成 のように複数の文字になる場合、後ろの量指定子が全体にかかるようにまとめる
+                if !in_class && literal.chars().count() > 1 {
+                    folded.push_str(&format!("(?:{})", literal));
+                } else {
+                    folded.push_str(&literal);
+                }
+            }
+        }
+    }
+    folded
+}
+
+#[cfg(feature = "tauri")]
+#[tauri::command]
+pub async fn list_title_ng_rules(store: State<'_, LogStore>) -> Result<Vec<TitleNgRule>, String> {
+    store.list_title_ng_rules(BOARD_ID)
+}
+
+#[cfg(feature = "tauri")]
+#[tauri::command]
+pub async fn add_title_ng_rule(
+    pattern: String,
+    is_regex: bool,
+    folds: Option<NormalizeOptions>,
+    store: State<'_, LogStore>,
+) -> Result<TitleNgRule, String> {
+    if pattern.trim().is_empty() {
+        return Err("NGにする内容を入力してください。".to_string());
+    }
+    if is_regex {
+        Regex::new(&pattern).map_err(|e| format!("正規表現が不正です: {}", e))?;
+    }
+    eprintln!(
+        "[Rust add_title_ng_rule] スレタイNGを追加します: {} (正規表現: {})",
+        pattern, is_regex
+    );
+    store.add_title_ng_rule(BOARD_ID, &pattern, is_regex, folds.unwrap_or_default())
+}
+
+#[cfg(feature = "tauri")]
+#[tauri::command]
+pub async fn remove_title_ng_rule(rule_id: i64, store: State<'_, LogStore>) -> Result<(), String> {
+    store.remove_title_ng_rule(rule_id)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rule(pattern: &str, is_regex: bool, folds: NormalizeOptions) -> TitleNgRule {
+        TitleNgRule {
+            id: 1,
+            board: "test".to_string(),
+            pattern: pattern.to_string(),
+            is_regex,
+            created_at: 0,
+            folds,
+        }
+    }
+
+    #[test]
+    fn regex_literals_are_folded_like_the_title() {
+        let all = NormalizeOptions::all();
+        for (pattern, title) in [
+            ("カタカナ", "カタカナのスレ"),
+            ("ＡＢＣ", "abcスレ"),
+            ("①+", "11スレ"),
+            ("^[ア-ン]+スレ$", "アイウスレ"),
+            ("（笑）", "(笑)"),
+            ("㍻+", "平成平成"),
+        ] {
+            let filter = TitleFilter::new(&[rule(pattern, true, all)]);
+            assert!(filter.is_match(title), "{} / {}", pattern, title);
+        }
+        // 全角の括弧は正規化しても文字そのものとして扱う
+        let filter = TitleFilter::new(&[rule("（笑）", true, all)]);
+        assert!(!filter.is_match("笑"));
+        // エスケープの中は書き換えない
+        assert_eq!(
+            fold_regex_literals(r"\p{Katakana}ア", &all),
+            r"\p{Katakana}あ"
+        );
+        assert_eq!(
+            fold_regex_literals("ア", &NormalizeOptions::default()),
+            "ア"
+        );
+    }
+
+    #[test]
+    fn contains_rules_use_their_own_folds() {
+        let kana = NormalizeOptions {
+            kana: true,
+            ..Default::default()
+        };
+        let filter = TitleFilter::new(&[rule("テスト", false, kana)]);
+        assert!(filter.is_match("てすとスレ"));
+        assert!(!filter.is_match("ﾃｽﾄスレ"));
+        let filter = TitleFilter::new(&[rule("(", true, NormalizeOptions::default())]);
+        assert!(!filter.is_match("("));
+    }
+}
@@ -0,0 +1,490 @@
+use cookie_store::{CookieExpiration, CookieStore, RawCookie};
+use reqwest::header::HeaderValue;
+use reqwest::Url;
+use serde::Serialize;
+use std::collections::BTreeMap;
+use std::io::BufReader;
+use std::path::{Path, PathBuf};
+use std::sync::{Mutex, MutexGuard};
+#[cfg(feature = "tauri")]
+use tauri::State;
+
+#[cfg(feature = "tauri")]
+use super::submit::PostSession;
+use crate::settings::get_profile_dir;
+
+const COOKIE_FILENAME: &str = "cookies.json";
+
+// 設定ウィンドウに表示する Cookie (値は表示しない)
+#[derive(Debug, Serialize, Clone)]
+pub struct CookieInfo {
+    pub name: String,
+    pub path: String,
+    pub secure: bool,
+    pub expires: Option<i64>, // Unixタイムスタンプ。None はセッション Cookie (保存されない)
+}
+
+// ドメインごとにまとめた Cookie
+#[derive(Debug, Serialize, Clone)]
+pub struct DomainCookies {
+    pub domain: String,
+    pub cookies: Vec<CookieInfo>,
+}
+
+#[derive(Debug, Serialize, Clone, Default)]
+pub struct CookieImportReport {
+    pub imported: usize,
+    pub skipped: Vec<String>, // 取り込めなかった行 (理由付き)
+}
+
+struct JarState {
+    profile: String,
+    path: Option<PathBuf>, // None はファイルに保存しない (読み込めなかった場合の代わり)
+    store: CookieStore,
+}
+
+impl JarState {
+    fn save(&self) -> Result<(), String> {
+        match &self.path {
+            Some(path) => save_store(path, &self.store),
+            None => Ok(()),
+        }
+    }
+}
+
+// プロファイルごとにファイルへ保存される Cookie の入れ物
+// reqwest の CookieStore を実装しているので、HTTP クライアントの cookie_provider にそのまま渡せる
+pub struct PersistentCookieJar {
+    state: Mutex<JarState>,
+}
+
+fn load_store(path: &Path) -> Result<CookieStore, String> {
+    let file = match std::fs::File::open(path) {
+        Ok(file) => file,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(CookieStore::new()),
+        Err(e) => return Err(format!("{}: {}", path.display(), e)),
+    };
+    cookie_store::serde::json::load(BufReader::new(file)).map_err(|e| {
+        format!(
+            "Cookie の読み込みに失敗しました ({}): {}",
+            path.display(),
+            e
+        )
+    })
+}
+
+// 期限付きの Cookie だけをファイルに書き出す (セッション Cookie はアプリの終了で消える)
+fn save_store(path: &Path, store: &CookieStore) -> Result<(), String> {
+    let mut bytes = Vec::new();
+    cookie_store::serde::json::save(store, &mut bytes)
+        .map_err(|e| format!("Cookie の書き出しに失敗しました: {}", e))?;
+    // 書き込みの途中で終了しても壊れないよう、一時ファイルに書いてから置き換える
+    let tmp_path = path.with_extension("json.tmp");
+    std::fs::write(&tmp_path, bytes)
+        .and_then(|_| std::fs::rename(&tmp_path, path))
+        .map_err(|e| format!("{}: {}", path.display(), e))
+}
+
+fn cookie_info(cookie: &cookie_store::Cookie<'static>) -> CookieInfo {
+    CookieInfo {
+        name: cookie.name().to_string(),
+        path: String::from(&cookie.path),
+        secure: cookie.secure().unwrap_or(false),
+        expires: match &cookie.expires {
+            CookieExpiration::AtUtc(at) => Some(at.unix_timestamp()),
+            CookieExpiration::SessionEnd => None,
+        },
+    }
+}
+
+impl PersistentCookieJar {
+    // プロファイルの Cookie を読み込む (…/profiles/<名前>/cookies.json)
+    pub fn open_profile(profile: &str) -> Result<Self, String> {
+        let path = get_profile_dir(profile)?.join(COOKIE_FILENAME);
+        Self::open(profile, &path)
+    }
+
+    // ファイルを指定して開く (ファイルが無ければ空の状態から始める)
+    pub fn open(profile: &str, path: &Path) -> Result<Self, String> {
+        let store = load_store(path)?;
+        Ok(PersistentCookieJar {
+            state: Mutex::new(JarState {
+                profile: profile.to_string(),
+                path: Some(path.to_path_buf()),
+                store,
+            }),
+        })
+    }
+
+    // ファイルに保存しない空の Cookie (プロファイルの Cookie を読み込めなかった場合に使う)
+    // プロファイルを切り替えると、切り替え先のファイルに保存されるようになる
+    pub fn in_memory(profile: &str) -> Self {
+        PersistentCookieJar {
+            state: Mutex::new(JarState {
+                profile: profile.to_string(),
+                path: None,
+                store: CookieStore::new(),
+            }),
+        }
+    }
+
+    fn state(&self) -> Result<MutexGuard<'_, JarState>, String> {
+        self.state
+            .lock()
+            .map_err(|e| format!("Cookie のロックに失敗しました: {}", e))
+    }
+
+    pub fn profile(&self) -> Result<String, String> {
+        Ok(self.state()?.profile.clone())
+    }
+
+    // 別のプロファイルに切り替える。今の Cookie は保存してから読み替える
+    pub fn switch_profile(&self, profile: &str) -> Result<(), String> {
+        let path = get_profile_dir(profile)?.join(COOKIE_FILENAME);
+        self.switch_to(profile, path)
+    }
+
+    fn switch_to(&self, profile: &str, path: PathBuf) -> Result<(), String> {
+        let mut state = self.state()?;
+        if state.profile == profile {
+            return Ok(());
+        }
+        state.save()?;
+        state.store = load_store(&path)?;
+        state.profile = profile.to_string();
+        state.path = Some(path);
+        Ok(())
+    }
+
+    // 期限切れでない Cookie をドメインごとに返す
+    pub fn list_domains(&self) -> Result<Vec<DomainCookies>, String> {
+        let state = self.state()?;
+        let mut domains: BTreeMap<String, Vec<CookieInfo>> = BTreeMap::new();
+        for cookie in state.store.iter_unexpired() {
+            domains
+                .entry(String::from(&cookie.domain))
+                .or_default()
+                .push(cookie_info(cookie));
+        }
+        Ok(domains
+            .into_iter()
+            .map(|(domain, cookies)| DomainCookies { domain, cookies })
+            .collect())
+    }
+
+    // ドメインの Cookie をすべて削除する。削除した数を返す
+    pub fn clear_domain(&self, domain: &str) -> Result<usize, String> {
+        let mut state = self.state()?;
+        let targets: Vec<(String, String, String)> = state
+            .store
+            .iter_any()
+            .filter(|cookie| String::from(&cookie.domain) == domain)
+            .map(|cookie| {
+                (
+                    String::from(&cookie.domain),
+                    String::from(&cookie.path),
+                    cookie.name().to_string(),
+                )
+            })
+            .collect();
+        for (domain, path, name) in &targets {
+            state.store.remove(domain, path, name);
+        }
+        state.save()?;
+        Ok(targets.len())
+    }
+
+    pub fn clear_all(&self) -> Result<(), String> {
+        let mut state = self.state()?;
+        state.store.clear();
+        state.save()
+    }
+
+    // Netscape 形式 (curl や各種ブラウザの拡張機能が書き出す cookies.txt) の Cookie を取り込む
+    // 1行が「ドメイン, サブドメインを含むか, パス, secure, 期限, 名前, 値」のタブ区切り
+    pub fn import_netscape(&self, text: &str) -> Result<CookieImportReport, String> {
+        let mut report = CookieImportReport::default();
+        let now = chrono::Utc::now().timestamp();
+        let mut state = self.state()?;
+        for (index, line) in text.lines().enumerate() {
+            let line = line.trim_end_matches('\r');
+            // HttpOnly の Cookie は行頭に #HttpOnly_ が付く (それ以外の # はコメント)
+            let (line, http_only) = match line.strip_prefix("#HttpOnly_") {
+                Some(rest) => (rest, true),
+                None => (line, false),
+            };
+            if line.trim().is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let fields: Vec<&str> = line.split('\t').collect();
+            let [domain, include_subdomains, path, secure, expires, name, value] = fields[..]
+            else {
+                report
+                    .skipped
+                    .push(format!("{}行目: 項目の数が違います", index + 1));
+                continue;
+            };
+            let expires: i64 = expires.trim().parse().unwrap_or(0);
+            if expires != 0 && expires <= now {
+                report
+                    .skipped
+                    .push(format!("{}行目: 期限切れです ({})", index + 1, name));
+                continue;
+            }
+            let secure = secure.eq_ignore_ascii_case("TRUE");
+            let host = domain.trim_start_matches('.');
+            let scheme = if secure { "https" } else { "http" };
+            let Ok(url) = Url::parse(&format!("{}://{}{}", scheme, host, path)) else {
+                report.skipped.push(format!(
+                    "{}行目: ドメインが不正です ({})",
+                    index + 1,
+                    domain
+                ));
+                continue;
+            };
+
+            // Set-Cookie と同じ形にして、サーバーから受け取った Cookie と同じ規則で保存する
+            let mut set_cookie = format!("{}={}; Path={}", name, value, path);
+            if include_subdomains.eq_ignore_ascii_case("TRUE") {
+                set_cookie.push_str(&format!("; Domain={}", host));
+            }
+            if secure {
+                set_cookie.push_str("; Secure");
+            }
+            if http_only {
+                set_cookie.push_str("; HttpOnly");
+            }
+            // 期限が 0 の Cookie はセッション Cookie として扱う
+            if let Some(at) = chrono::DateTime::from_timestamp(expires, 0).filter(|_| expires != 0)
+            {
+                set_cookie.push_str(&format!(
+                    "; Expires={}",
+                    at.format("%a, %d %b %Y %H:%M:%S GMT")
+                ));
+            }
+            match state.store.parse(&set_cookie, &url) {
+                Ok(_) => report.imported += 1,
+                Err(e) => report
+                    .skipped
+                    .push(format!("{}行目: {} ({})", index + 1, e, name)),
+            }
+        }
+        state.save()?;
+        Ok(report)
+    }
+}
+
+impl reqwest::cookie::CookieStore for PersistentCookieJar {
+    fn set_cookies(&self, cookie_headers: &mut dyn Iterator<Item = &HeaderValue>, url: &Url) {
+        let cookies: Vec<RawCookie<'static>> = cookie_headers
+            .filter_map(|header| header.to_str().ok())
+            .filter_map(|header| RawCookie::parse(header.to_string()).ok())
+            .collect();
+        if cookies.is_empty() {
+            return;
+        }
+        let Ok(mut state) = self.state() else {
+            return;
+        };
+        state.store.store_response_cookies(cookies.into_iter(), url);
+        if let Err(e) = state.save() {
+            eprintln!("[Rust PersistentCookieJar] {}", e);
+        }
+    }
+
+    fn cookies(&self, url: &Url) -> Option<HeaderValue> {
+        let state = self.state().ok()?;
+        let header = state
+            .store
+            .get_request_values(url)
+            .map(|(name, value)| format!("{}={}", name, value))
+            .collect::<Vec<_>>()
+            .join("; ");
+        if header.is_empty() {
+            return None;
+        }
+        HeaderValue::from_str(&header).ok()
+    }
+}
+
+#[cfg(feature = "tauri")]
+#[tauri::command]
+pub fn list_cookies(session: State<'_, PostSession>) -> Result<Vec<DomainCookies>, String> {
+    session.cookies().list_domains()
+}
+
+#[cfg(feature = "tauri")]
+#[tauri::command]
+pub fn clear_cookies(
+    domain: Option<String>,
+    session: State<'_, PostSession>,
+) -> Result<(), String> {
+    match domain {
+        Some(domain) => {
+            let removed = session.cookies().clear_domain(&domain)?;
+            eprintln!(
+                "[Rust clear_cookies] {} の Cookie を {} 個削除しました",
+                domain, removed
+            );
+        }
+        None => {
+            session.cookies().clear_all()?;
+            eprintln!("[Rust clear_cookies] すべての Cookie を削除しました");
+        }
+    }
+    Ok(())
+}
+
+// Netscape 形式の Cookie ファイルを読み込んで、今のプロファイルに取り込む
+#[cfg(feature = "tauri")]
+#[tauri::command]
+pub fn import_cookie_file(
+    path: String,
+    session: State<'_, PostSession>,
+) -> Result<CookieImportReport, String> {
+    eprintln!("[Rust import_cookie_file] Cookie を取り込みます: {}", path);
+    let text = std::fs::read_to_string(&path).map_err(|e| format!("{}: {}", path, e))?;
+    let report = session.cookies().import_netscape(&text)?;
+    eprintln!(
+        "[Rust import_cookie_file] {} 個取り込み、{} 行スキップしました",
+        report.imported,
+        report.skipped.len()
+    );
+    Ok(report)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use reqwest::cookie::CookieStore as _;
+
+    fn temp_path(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("cookies-{}-{}", name, std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        dir.join(COOKIE_FILENAME)
+    }
+
+    // 送られる Cookie (順序は決まっていないので名前順にする)
+    fn sent(jar: &PersistentCookieJar, url: &str) -> String {
+        let Some(header) = jar.cookies(&Url::parse(url).unwrap()) else {
+            return String::new();
+        };
+        let mut cookies: Vec<&str> = header.to_str().unwrap().split("; ").collect();
+        cookies.sort();
+        cookies.join("; ")
+    }
+
+    fn later() -> i64 {
+        chrono::Utc::now().timestamp() + 86400 * 365
+    }
+
+    #[test]
+    fn imports_netscape_lines() {
+        let path = temp_path("import");
+        let jar = PersistentCookieJar::open("default", &path).unwrap();
+        let text = format!(
+            "# Netscape HTTP Cookie File\n\
+             .example.com\tTRUE\t/\tFALSE\t{later}\twide\t1\n\
+             host.example.com\tFALSE\t/\tFALSE\t{later}\thost\t2\n\
+             #HttpOnly_.example.com\tTRUE\t/\tTRUE\t{later}\tsecret\t3\n\
+             example.com\tFALSE\t/\tFALSE\t0\tsession\t4\n\
+             example.com\tFALSE\t/\tFALSE\t1000\texpired\t5\n\
+             example.com\tFALSE\t/\tbroken\n\
+             exa mple.com\tFALSE\t/\tFALSE\t0\tbad\t6\n",
+            later = later()
+        );
+        let report = jar.import_netscape(&text).unwrap();
+        assert_eq!(report.imported, 4);
+        assert_eq!(report.skipped.len(), 3, "{:?}", report.skipped);
+        assert!(report.skipped[0].starts_with("6行目: 期限切れです"));
+        assert!(report.skipped[1].starts_with("7行目: 項目の数が違います"));
+        assert!(report.skipped[2].starts_with("8行目: ドメインが不正です"));
+
+        // 先頭に . の付いたドメインはサブドメインにも送り、付いていないものはそのホストだけに送る
+        assert_eq!(sent(&jar, "http://sub.example.com/"), "wide=1");
+        assert_eq!(sent(&jar, "http://host.example.com/"), "host=2; wide=1");
+        assert_eq!(sent(&jar, "http://sub.host.example.com/"), "wide=1");
+        assert_eq!(sent(&jar, "http://example.com/"), "session=4; wide=1");
+        assert_eq!(
+            sent(&jar, "https://example.com/"),
+            "secret=3; session=4; wide=1"
+        );
+
+        let state = jar.state().unwrap();
+        let secret = state.store.get("example.com", "/", "secret").unwrap();
+        assert_eq!(secret.http_only(), Some(true));
+        assert_eq!(secret.secure(), Some(true));
+        let session = state.store.get("example.com", "/", "session").unwrap();
+        assert_eq!(session.expires, CookieExpiration::SessionEnd);
+        drop(state);
+        let _ = std::fs::remove_dir_all(path.parent().unwrap());
+    }
+
+    #[test]
+    fn saves_and_reloads_persistent_cookies() {
+        let path = temp_path("reload");
+        let jar = PersistentCookieJar::open("default", &path).unwrap();
+        let text = format!(
+            "example.com\tFALSE\t/\tFALSE\t{}\tkept\t1\n\
+             example.com\tFALSE\t/\tFALSE\t0\tsession\t2\n",
+            later()
+        );
+        jar.import_netscape(&text).unwrap();
+        assert!(path.is_file());
+        // 一時ファイルは置き換えた後に残らない
+        assert!(!path.with_extension("json.tmp").exists());
+
+        // セッション Cookie は保存されない
+        let reopened = PersistentCookieJar::open("default", &path).unwrap();
+        assert_eq!(sent(&reopened, "http://example.com/"), "kept=1");
+        let domains = reopened.list_domains().unwrap();
+        assert_eq!(domains.len(), 1);
+        assert_eq!(domains[0].domain, "example.com");
+        assert_eq!(domains[0].cookies[0].name, "kept");
+        assert!(domains[0].cookies[0].expires.is_some());
+
+        assert_eq!(reopened.clear_domain("example.com").unwrap(), 1);
+        let reopened = PersistentCookieJar::open("default", &path).unwrap();
+        assert!(reopened.list_domains().unwrap().is_empty());
+
+        // 壊れたファイルは読み込めないと報告する
+        std::fs::write(&path, "{not json").unwrap();
+        assert!(PersistentCookieJar::open("default", &path).is_err());
+        let _ = std::fs::remove_dir_all(path.parent().unwrap());
+    }
+
+    #[test]
+    fn switching_profiles_keeps_cookies_apart() {
+        let first = temp_path("profile-a");
+        let second = temp_path("profile-b");
+        let jar = PersistentCookieJar::open("a", &first).unwrap();
+        let set = |jar: &PersistentCookieJar, cookie: &str| {
+            let header = HeaderValue::from_str(&format!("{}; Max-Age=3600", cookie)).unwrap();
+            jar.set_cookies(
+                &mut std::iter::once(&header),
+                &Url::parse("http://example.com/").unwrap(),
+            );
+        };
+        set(&jar, "a=1");
+
+        jar.switch_to("b", second.clone()).unwrap();
+        assert_eq!(jar.profile().unwrap(), "b");
+        assert_eq!(sent(&jar, "http://example.com/"), "");
+        set(&jar, "b=2");
+
+        jar.switch_to("a", first.clone()).unwrap();
+        assert_eq!(sent(&jar, "http://example.com/"), "a=1");
+        let reopened = PersistentCookieJar::open("b", &second).unwrap();
+        assert_eq!(sent(&reopened, "http://example.com/"), "b=2");
+
+        // ファイルに保存しない Cookie から切り替えると、切り替え先のファイルを使う
+        let jar = PersistentCookieJar::in_memory("c");
+        set(&jar, "c=3");
+        jar.switch_to("a", first.clone()).unwrap();
+        assert_eq!(sent(&jar, "http://example.com/"), "a=1");
+        for path in [first, second] {
+            let _ = std::fs::remove_dir_all(path.parent().unwrap());
+        }
+    }
+}
@@ -0,0 +1,283 @@
+use regex::Regex;
+use serde::Deserialize;
+use std::sync::OnceLock;
+use std::time::Duration;
+#[cfg(feature = "tauri")]
+use tauri::State;
+
+use super::limits::{check_post_limits, PostText};
+#[cfg(feature = "tauri")]
+use super::submit::PostSession;
+use super::submit::{cached_regex, merge_confirmation_fields, send_form, PostResult};
+#[cfg(feature = "tauri")]
+use crate::store::log_store::LogStore;
+use crate::thread::client::BoardClient;
+use crate::thread::setting::BoardSetting;
+
+// 新しいスレッドが subject.json に載るまで待つ回数と間隔
+const SUBJECT_RETRY_COUNT: usize = 3;
+const SUBJECT_RETRY_INTERVAL: Duration = Duration::from_secs(1);
+
+// スレッドキーは作成時刻なので、送信時刻より少し前のものまでを候補にする (時計のずれの分)
+const THREAD_KEY_TOLERANCE_SECS: i64 = 60;
+
+// スレ立ての内容 (フロントエンドから受け取る)
+#[derive(Debug, Deserialize, Clone)]
+pub struct NewThreadForm {
+    pub title: String,
+    #[serde(default)]
+    pub name: String,
+    #[serde(default)]
+    pub mail: String,
+    pub body: String,
+    // 書き込み確認ページで承諾した場合は、NeedsConfirmation で返したフィールドをそのまま渡す
+    #[serde(default)]
+    pub confirmation: Option<Vec<(String, String)>>,
+}
+
+// スレッドを立てる。成功した場合は PostResult::Created で新しいスレッドのIDを返す
+pub async fn create_new_thread(
+    client: &BoardClient,
+    http: &reqwest::Client,
+    form: &NewThreadForm,
+) -> Result<PostResult, String> {
+    // 送信する前に SETTING.TXT の制限を確かめる (取得できなければサーバーの判定に任せる)
+    let setting = client.fetch_setting(http).await.unwrap_or_else(|e| {
+        eprintln!(
+            "[Rust create_new_thread] SETTING.TXT を取得できないため、長さを確かめずに送信します: {}",
+            e
+        );
+        BoardSetting::default()
+    });
+    let text = PostText {
+        subject: Some(&form.title),
+        name: &form.name,
+        mail: &form.mail,
+        body: &form.body,
+    };
+    let problems = check_post_limits(&setting, client.encoding(), &text);
+    if !problems.is_empty() {
+        return Ok(PostResult::Error {
+            message: problems.join("\n"),
+        });
+    }
+
+    let submitted_at = chrono::Utc::now().timestamp();
+    let fields = vec![
+        ("bbs".to_string(), client.board_id().to_string()),
+        ("time".to_string(), submitted_at.to_string()),
+        ("subject".to_string(), form.title.clone()),
+        ("FROM".to_string(), form.name.clone()),
+        ("mail".to_string(), form.mail.clone()),
+        ("MESSAGE".to_string(), form.body.clone()),
+        ("submit".to_string(), "新規スレッド作成".to_string()),
+    ];
+    let fields = merge_confirmation_fields(fields, form.confirmation.as_deref());
+    let referer = format!("{}/{}/", client.base_url(), client.board_id());
+    let (result, html) = send_form(client, http, &fields, &referer).await?;
+    if result != PostResult::Ok {
+        return Ok(result);
+    }
+
+    let thread_id = match thread_id_from_html(&html, client.board_id()) {
+        Some(thread_id) => Some(thread_id),
+        None => find_created_thread(client, http, &form.title, submitted_at).await,
+    };
+    Ok(PostResult::Created { thread_id })
+}
+
+// 成功ページに新しいスレッドへのリンク (read.cgi) があれば、そこからスレッドキーを読み取る
+fn thread_id_from_html(html: &str, board_id: &str) -> Option<String> {
+    static READ_CGI_RE: OnceLock<Regex> = OnceLock::new();
+    cached_regex(&READ_CGI_RE, r"/test/read\.cgi/([^/\s]+)/(\d{9,})")
+        .captures_iter(html)
+        .find(|caps| &caps[1] == board_id)
+        .map(|caps| caps[2].to_string())
+}
+
+// 成功ページから分からない場合は、スレッド一覧から同じタイトルの新しいスレッドを探す
+async fn find_created_thread(
+    client: &BoardClient,
+    http: &reqwest::Client,
+    title: &str,
+    submitted_at: i64,
+) -> Option<String> {
+    let title = title.trim();
+    for attempt in 0..SUBJECT_RETRY_COUNT {
+        if attempt > 0 {
+            tokio::time::sleep(SUBJECT_RETRY_INTERVAL).await;
+        }
+        let entries = match client.fetch_subject(http).await {
+            Ok(entries) => entries,
+            Err(e) => {
+                eprintln!(
+                    "[Rust find_created_thread] スレッド一覧の取得に失敗しました: {}",
+                    e
+                );
+                continue;
+            }
+        };
+        let found = entries
+            .iter()
+            .filter(|entry| entry.title.trim() == title)
+            .filter_map(|entry| entry.thread_id.parse::<i64>().ok())
+            .filter(|&key| key >= submitted_at - THREAD_KEY_TOLERANCE_SECS)
+            .max();
+        if let Some(key) = found {
+            return Some(key.to_string());
+        }
+    }
+    eprintln!(
+        "[Rust find_created_thread] 立てたスレッドがスレッド一覧に見つかりません: {}",
+        title
+    );
+    None
+}
+
+// スレッドを立てる。結果は PostResult で返し、通信できなかった場合だけ Err にする
+#[cfg(feature = "tauri")]
+#[tauri::command]
+pub async fn create_thread(
+    form: NewThreadForm,
+    session: State<'_, PostSession>,
+    store: State<'_, LogStore>,
+) -> Result<PostResult, String> {
+    eprintln!(
+        "[Rust create_thread] スレッドを立てます (タイトル: {}, 確認済み: {})",
+        form.title,
+        form.confirmation.is_some()
+    );
+    let client = BoardClient::default();
+    let sent_at = chrono::Utc::now().timestamp();
+    let result = create_new_thread(&client, session.http(), &form).await?;
+    eprintln!("[Rust create_thread] スレ立ての結果: {:?}", result);
+    // スレッドのIDが分かれば、1レス目を書き込み履歴に残す
+    if let PostResult::Created {
+        thread_id: Some(thread_id),
+    } = &result
+    {
+        if let Err(e) = store.record_my_post(
+            client.board_id(),
+            thread_id,
+            &form.name,
+            &form.mail,
+            &form.body,
+            sent_at,
+        ) {
+            eprintln!("[Rust create_thread] {}", e);
+        }
+    }
+    Ok(result)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_server::{Request, Response, TestServer};
+    use crate::thread::board::BoardEncoding;
+
+    fn form(title: &str) -> NewThreadForm {
+        NewThreadForm {
+            title: title.to_string(),
+            name: String::new(),
+            mail: String::new(),
+            body: "1レス目".to_string(),
+            confirmation: None,
+        }
+    }
+
+    const SUCCESS: &str =
+        "<html><head><title>書きこみました。</title></head><body><!-- 2ch_X:true -->";
+
+    fn board(request: &Request, success_page: &str, subject: &str) -> Response {
+        match (request.method.as_str(), request.path.as_str()) {
+            ("GET", "/news/SETTING.TXT") => Response::new(200, "BBS_SUBJECT_COUNT=48\n"),
+            ("GET", "/news/subject.json") => Response::new(200, subject.to_string()),
+            ("POST", "/test/bbs.cgi") => Response::new(200, success_page.to_string()),
+            _ => Response::new(404, ""),
+        }
+    }
+
+    #[test]
+    fn reads_thread_id_from_the_success_page() {
+        let html = r#"<a href="../test/read.cgi/other/1700000001/">別の板</a>
+            <a href="https://example.com/test/read.cgi/news/1700000002/l50">新しいスレ</a>"#;
+        assert_eq!(
+            thread_id_from_html(html, "news").as_deref(),
+            Some("1700000002")
+        );
+        assert_eq!(thread_id_from_html(html, "vip"), None);
+    }
+
+    #[tokio::test]
+    async fn finds_the_thread_on_the_success_page() {
+        let success = format!(
+            r#"{}<a href="/test/read.cgi/news/1700000123/">スレッド</a>"#,
+            SUCCESS
+        );
+        let server = TestServer::start(move |request| board(request, &success, "[]")).await;
+        let client = BoardClient::new(&server.url(), "news").with_encoding(BoardEncoding::ShiftJis);
+        let result = create_new_thread(&client, &reqwest::Client::new(), &form("新スレ"))
+            .await
+            .unwrap();
+        assert_eq!(
+            result,
+            PostResult::Created {
+                thread_id: Some("1700000123".to_string())
+            }
+        );
+
+        let requests = server.requests();
+        let paths: Vec<&str> = requests.iter().map(|r| r.path.as_str()).collect();
+        assert_eq!(paths, ["/news/SETTING.TXT", "/test/bbs.cgi"]);
+        let body = String::from_utf8_lossy(&requests[1].body);
+        assert!(body.contains("bbs=news&"), "{}", body);
+        // タイトルも板の文字コード (Shift_JIS) で送る
+        assert!(body.contains("subject=%90V%83X%83%8C"), "{}", body);
+        assert!(!body.contains("key="), "{}", body);
+        assert_eq!(
+            requests[1].header("referer"),
+            Some(format!("{}/news/", server.url()).as_str())
+        );
+    }
+
+    #[tokio::test]
+    async fn falls_back_to_the_subject_list() {
+        let now = chrono::Utc::now().timestamp();
+        // 同じタイトルでも、送信より前に立っていたスレッドは選ばない
+        let subject = serde_json::json!([
+            { "thread": now - 3600, "title": "新スレ", "number": 500, "date": now },
+            { "thread": now + 1, "title": "別のスレ", "number": 1, "date": now },
+            { "thread": now, "title": "新スレ ", "number": 1, "date": now },
+        ])
+        .to_string();
+        let server = TestServer::start(move |request| board(request, SUCCESS, &subject)).await;
+        let client = BoardClient::new(&server.url(), "news");
+        let result = create_new_thread(&client, &reqwest::Client::new(), &form("新スレ"))
+            .await
+            .unwrap();
+        assert_eq!(
+            result,
+            PostResult::Created {
+                thread_id: Some(now.to_string())
+            }
+        );
+        assert_eq!(server.requests().last().unwrap().path, "/news/subject.json");
+    }
+
+    #[tokio::test]
+    async fn limits_are_checked_before_sending() {
+        let server = TestServer::start(|request| board(request, SUCCESS, "[]")).await;
+        let client = BoardClient::new(&server.url(), "news");
+        // UTF-8 で数えると上限の48バイトを超える
+        let result = create_new_thread(&client, &reqwest::Client::new(), &form(&"あ".repeat(20)))
+            .await
+            .unwrap();
+        let PostResult::Error { message } = result else {
+            panic!("送信前に止まりませんでした: {:?}", result);
+        };
+        assert!(message.starts_with("タイトルが長すぎます"), "{}", message);
+        let paths: Vec<String> = server.requests().into_iter().map(|r| r.path).collect();
+        assert_eq!(paths, ["/news/SETTING.TXT"]);
+    }
+}
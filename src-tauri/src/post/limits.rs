@@ -0,0 +1,62 @@
+use crate::thread::board::BoardEncoding;
+use crate::thread::setting::BoardSetting;
+
+// 書き込む内容 (スレ立ての場合は subject にタイトルが入る)
+pub struct PostText<'a> {
+    pub subject: Option<&'a str>,
+    pub name: &'a str,
+    pub mail: &'a str,
+    pub body: &'a str,
+}
+
+// 板の文字コードで数えたバイト数 (サーバーが数えるのと同じ数え方)
+pub fn encoded_len(text: &str, encoding: BoardEncoding) -> usize {
+    encoding.encode(text).len()
+}
+
+// SETTING.TXT の制限を超えている項目を、利用者に見せるメッセージで返す (空なら送信してよい)
+pub fn check_post_limits(
+    setting: &BoardSetting,
+    encoding: BoardEncoding,
+    text: &PostText,
+) -> Vec<String> {
+    let mut problems = Vec::new();
+    if let Some(subject) = text.subject {
+        if subject.trim().is_empty() {
+            problems.push("タイトルがありません。".to_string());
+        }
+    }
+    if text.body.trim().is_empty() {
+        problems.push("本文がありません。".to_string());
+    }
+
+    let byte_limits = [
+        ("タイトル", text.subject, setting.subject_count),
+        ("名前", Some(text.name), setting.name_count),
+        ("メール欄", Some(text.mail), setting.mail_count),
+        ("本文", Some(text.body), setting.message_count),
+    ];
+    for (label, value, limit) in byte_limits {
+        let (Some(value), Some(limit)) = (value, limit) else {
+            continue;
+        };
+        let len = encoded_len(value, encoding);
+        if len > limit {
+            problems.push(format!(
+                "{}が長すぎます ({} バイト / 上限 {} バイト)",
+                label, len, limit
+            ));
+        }
+    }
+
+    if let Some(max_lines) = setting.max_message_lines {
+        let lines = text.body.lines().count();
+        if lines > max_lines {
+            problems.push(format!(
+                "本文の行数が多すぎます ({} 行 / 上限 {} 行)",
+                lines, max_lines
+            ));
+        }
+    }
+    problems
+}
@@ -0,0 +1,312 @@
+use serde::Serialize;
+#[cfg(feature = "tauri")]
+use tauri::State;
+
+use super::limits::{check_post_limits, encoded_len, PostText};
+use super::submit::PostForm;
+#[cfg(feature = "tauri")]
+use super::submit::PostSession;
+#[cfg(feature = "tauri")]
+use crate::ng::filter::load_ng_filter;
+#[cfg(feature = "tauri")]
+use crate::store::log_store::LogStore;
+use crate::thread::board::BoardEncoding;
+use crate::thread::body::parse_anchors;
+#[cfg(feature = "tauri")]
+use crate::thread::cache::ThreadCache;
+#[cfg(feature = "tauri")]
+use crate::thread::client::BoardClient;
+use crate::thread::dat::{parse_dat, ResponseItem};
+#[cfg(feature = "tauri")]
+use crate::thread::responses::cached_or_download;
+use crate::thread::setting::BoardSetting;
+
+// 板の設定に名無しの名前が無いときの名前
+const DEFAULT_NONAME_NAME: &str = "名無しさん";
+
+// プレビューの日付欄 (書き込むまで日付と ID は決まらない)
+const PREVIEW_DATE: &str = "(プレビュー)";
+
+// 本文中のアンカーのうち、指す先に問題があるもの
+#[derive(Debug, Serialize, Clone, PartialEq, Eq)]
+pub struct AnchorWarning {
+    pub number: u32,
+    pub message: String,
+}
+
+// preview_post の戻り値
+#[derive(Debug, Serialize, Clone)]
+pub struct PostPreview {
+    pub response: ResponseItem, // 受信したレスと同じ形 (番号は今書き込んだ場合の番号)
+    pub anchor_warnings: Vec<AnchorWarning>,
+    pub limit_problems: Vec<String>, // SETTING.TXT の制限を超えている項目 (空なら送信できる)
+    pub body_bytes: usize,           // 板の文字コードで数えた本文のバイト数
+    pub body_lines: usize,
+    pub setting: BoardSetting,
+}
+
+// サーバーが dat に書くのと同じ形にする (HTML の特殊文字を実体参照にし、改行を <br> にする)
+fn to_dat_field(text: &str) -> String {
+    text.replace("\r\n", "\n")
+        .split('\n')
+        .map(|line| {
+            line.replace('&', "&amp;")
+                .replace('<', "&lt;")
+                .replace('>', "&gt;")
+                .replace('"', "&quot;")
+        })
+        .collect::<Vec<_>>()
+        .join(" <br> ")
+}
+
+// 書き込む内容を dat の1行にして、受信したレスと同じパーサーに通す
+// responses はスレッドのレス (NG 適用済み)。取得できなかった場合は None で、アンカー先は確かめない
+pub fn build_preview(
+    form: &PostForm,
+    responses: Option<&[ResponseItem]>,
+    setting: &BoardSetting,
+    encoding: BoardEncoding,
+) -> Result<PostPreview, String> {
+    let name = if form.name.trim().is_empty() {
+        setting
+            .noname_name
+            .clone()
+            .unwrap_or_else(|| DEFAULT_NONAME_NAME.to_string())
+    } else {
+        to_dat_field(&form.name)
+    };
+    let line = format!(
+        "{}<>{}<>{}<> {} <>",
+        name,
+        to_dat_field(&form.mail),
+        PREVIEW_DATE,
+        to_dat_field(&form.body)
+    );
+    let mut response = parse_dat(&line)
+        .pop()
+        .ok_or_else(|| "プレビューを作成できませんでした。".to_string())?;
+    if let Some(responses) = responses {
+        response.id = (responses.len() + 1).to_string();
+    }
+
+    let anchor_warnings = match responses {
+        Some(responses) => check_anchors(&response.content, responses),
+        None => Vec::new(),
+    };
+    let text = PostText {
+        subject: None,
+        name: &form.name,
+        mail: &form.mail,
+        body: &form.body,
+    };
+    Ok(PostPreview {
+        anchor_warnings,
+        limit_problems: check_post_limits(setting, encoding, &text),
+        body_bytes: encoded_len(&form.body, encoding),
+        body_lines: form.body.lines().count(),
+        setting: setting.clone(),
+        response,
+    })
+}
+
+// まだ無いレスや NG にしたレスへのアンカーを探す
+fn check_anchors(content: &str, responses: &[ResponseItem]) -> Vec<AnchorWarning> {
+    parse_anchors(content)
+        .into_iter()
+        .filter_map(|number| {
+            let target = responses
+                .iter()
+                .find(|response| response.id.parse::<u32>().ok() == Some(number));
+            let message = match target {
+                None => format!(">>{} はありません (現在 {} レス)", number, responses.len()),
+                Some(ResponseItem { ng: Some(ng), .. }) => {
+                    format!(">>{} は NG にしたレスです ({})", number, ng.pattern)
+                }
+                Some(_) => return None,
+            };
+            Some(AnchorWarning { number, message })
+        })
+        .collect()
+}
+
+// 書き込む前の確認。受信したレスと同じ形で本文を組み立て、アンカー先と SETTING.TXT の制限を確かめる
+#[cfg(feature = "tauri")]
+#[tauri::command]
+pub async fn preview_post(
+    form: PostForm,
+    session: State<'_, PostSession>,
+    cache: State<'_, ThreadCache>,
+    store: State<'_, LogStore>,
+) -> Result<PostPreview, String> {
+    let client = BoardClient::default();
+    let setting = client
+        .fetch_setting(session.http())
+        .await
+        .unwrap_or_else(|e| {
+            eprintln!(
+                "[Rust preview_post] SETTING.TXT を取得できないため、長さは確かめません: {}",
+                e
+            );
+            BoardSetting::default()
+        });
+    let responses = match cached_or_download(session.http(), &form.thread_id, &cache, &store).await
+    {
+        Ok(responses) => {
+            let mut responses = responses.as_ref().clone();
+            load_ng_filter(&store, client.board_id(), &form.thread_id).apply(&mut responses);
+            Some(responses)
+        }
+        Err(e) => {
+            eprintln!(
+                "[Rust preview_post] スレッドを取得できないため、アンカー先は確かめません (スレッドID: {}): {}",
+                form.thread_id, e
+            );
+            None
+        }
+    };
+    build_preview(&form, responses.as_deref(), &setting, client.encoding())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ng::filter::NgMatch;
+    use crate::ng::rules::NgTarget;
+
+    fn form(name: &str, body: &str) -> PostForm {
+        PostForm {
+            thread_id: "1700000000".to_string(),
+            name: name.to_string(),
+            mail: "sage".to_string(),
+            body: body.to_string(),
+            confirmation: None,
+        }
+    }
+
+    fn thread(count: usize) -> Vec<ResponseItem> {
+        let dat: String = (1..=count)
+            .map(|n| format!("名無し<><>2024/01/01 ID:a<> レス{} <>\n", n))
+            .collect();
+        parse_dat(&dat)
+    }
+
+    #[test]
+    fn fields_are_escaped_like_the_server() {
+        assert_eq!(
+            to_dat_field("a<b>&\"c\"\r\n2行目\n\n4行目"),
+            "a&lt;b&gt;&amp;&quot;c&quot; <br> 2行目 <br>  <br> 4行目"
+        );
+        assert_eq!(to_dat_field(""), "");
+    }
+
+    #[test]
+    fn builds_a_response_like_a_received_one() {
+        let setting = BoardSetting {
+            noname_name: Some("風吹けば名無し".to_string()),
+            ..Default::default()
+        };
+        let preview = build_preview(
+            &form(" ", "<b>太字</b>\n2行目"),
+            None,
+            &setting,
+            BoardEncoding::Utf8,
+        )
+        .unwrap();
+        assert_eq!(preview.response.author, "風吹けば名無し");
+        assert_eq!(preview.response.mail, "sage");
+        assert!(preview.response.content.contains("&lt;b&gt;太字&lt;/b&gt;"));
+        assert!(preview.response.content.contains("<br>"));
+        assert!(preview.anchor_warnings.is_empty());
+        assert_eq!(preview.body_lines, 2);
+
+        let preview = build_preview(
+            &form("コテ", "本文"),
+            Some(&thread(3)),
+            &BoardSetting::default(),
+            BoardEncoding::Utf8,
+        )
+        .unwrap();
+        assert_eq!(preview.response.author, "コテ");
+        // 今書き込んだ場合の番号
+        assert_eq!(preview.response.id, "4");
+        assert_eq!(
+            build_preview(
+                &form("", "本文"),
+                None,
+                &BoardSetting::default(),
+                BoardEncoding::Utf8
+            )
+            .unwrap()
+            .response
+            .author,
+            DEFAULT_NONAME_NAME
+        );
+    }
+
+    #[test]
+    fn flags_missing_and_muted_anchor_targets() {
+        let mut responses = thread(3);
+        responses[1].ng = Some(NgMatch {
+            rule_id: 1,
+            target: NgTarget::Word,
+            pattern: "荒らし".to_string(),
+            chain_depth: 0,
+            chain_from: None,
+        });
+        let preview = build_preview(
+            &form("", ">>1\n>>2 >>5"),
+            Some(&responses),
+            &BoardSetting::default(),
+            BoardEncoding::Utf8,
+        )
+        .unwrap();
+        let numbers: Vec<u32> = preview
+            .anchor_warnings
+            .iter()
+            .map(|warning| warning.number)
+            .collect();
+        assert_eq!(numbers, [2, 5]);
+        assert_eq!(
+            preview.anchor_warnings[0].message,
+            ">>2 は NG にしたレスです (荒らし)"
+        );
+        assert_eq!(
+            preview.anchor_warnings[1].message,
+            ">>5 はありません (現在 3 レス)"
+        );
+    }
+
+    #[test]
+    fn counts_limits_in_the_board_encoding() {
+        let setting = BoardSetting {
+            message_count: Some(10),
+            max_message_lines: Some(2),
+            ..Default::default()
+        };
+        // ひらがな5文字は Shift_JIS なら10バイト、UTF-8 なら15バイト
+        let short = form("", "あいうえお");
+        let preview = build_preview(&short, None, &setting, BoardEncoding::ShiftJis).unwrap();
+        assert_eq!(preview.body_bytes, 10);
+        assert!(preview.limit_problems.is_empty());
+        let preview = build_preview(&short, None, &setting, BoardEncoding::Utf8).unwrap();
+        assert_eq!(preview.body_bytes, 15);
+        assert_eq!(
+            preview.limit_problems,
+            ["本文が長すぎます (15 バイト / 上限 10 バイト)"]
+        );
+
+        let preview = build_preview(
+            &form("", "a\nb\nc"),
+            None,
+            &setting,
+            BoardEncoding::ShiftJis,
+        )
+        .unwrap();
+        assert_eq!(preview.body_lines, 3);
+        assert_eq!(
+            preview.limit_problems,
+            ["本文の行数が多すぎます (3 行 / 上限 2 行)"]
+        );
+        assert_eq!(preview.setting, setting);
+    }
+}
@@ -0,0 +1,613 @@
+use html_escape::decode_html_entities;
+use regex::Regex;
+use reqwest::header::{CONTENT_TYPE, REFERER};
+use serde::{Deserialize, Serialize};
+use std::sync::{Arc, OnceLock};
+#[cfg(feature = "tauri")]
+use tauri::State;
+
+use super::cookies::PersistentCookieJar;
+#[cfg(feature = "tauri")]
+use crate::store::drafts::DraftSaver;
+#[cfg(feature = "tauri")]
+use crate::store::log_store::LogStore;
+use crate::thread::board::BoardEncoding;
+use crate::thread::body::plain_text;
+#[cfg(feature = "tauri")]
+use crate::thread::cache::ThreadCache;
+use crate::thread::client::BoardClient;
+
+// 専用ブラウザとして名乗る User-Agent (2ch 互換の掲示板の慣習)
+const USER_AGENT: &str = concat!("Monazilla/1.00 tulip-browser/", env!("CARGO_PKG_VERSION"));
+
+// エラーメッセージとして返す本文の最大文字数
+const MAX_MESSAGE_CHARS: usize = 300;
+
+// 書き込み確認ページから送り直すときも、利用者が入力した内容は入力どおりのものを使う
+const USER_INPUT_FIELDS: &[&str] = &["FROM", "mail", "MESSAGE", "subject"];
+
+// レスの書き込み内容 (フロントエンドから受け取る)
+#[derive(Debug, Deserialize, Clone)]
+pub struct PostForm {
+    pub thread_id: String,
+    #[serde(default)]
+    pub name: String,
+    #[serde(default)]
+    pub mail: String,
+    pub body: String,
+    // 書き込み確認ページで承諾した場合は、NeedsConfirmation で返したフィールドをそのまま渡す
+    #[serde(default)]
+    pub confirmation: Option<Vec<(String, String)>>,
+}
+
+// bbs.cgi の応答を判定した結果
+// フロントエンドでは { status: "rate_limited", message: "...", retry_after_secs: 60 } の形で受け取る
+#[derive(Debug, Serialize, Clone, PartialEq, Eq)]
+#[serde(rename_all = "snake_case", tag = "status")]
+pub enum PostResult {
+    Ok,
+    // スレ立てに成功した。新しいスレッドのIDが分からなかった場合は None
+    Created {
+        thread_id: Option<String>,
+    },
+    // 書き込み確認 (Cookie の確認) ページ。承諾する場合は fields を付けて送り直す
+    NeedsConfirmation {
+        message: String,
+        fields: Vec<(String, String)>,
+    },
+    Error {
+        message: String,
+    },
+    // 連続投稿などの規制。待つべき秒数が書かれていれば retry_after_secs に入れる
+    RateLimited {
+        message: String,
+        retry_after_secs: Option<u64>,
+    },
+}
+
+// 掲示板との通信に使う HTTP クライアント (dat・subject.json・SETTING.TXT・画像の取得と書き込みで共通)
+// 書き込み確認で発行された Cookie を取得と次の書き込みで送れるよう、アプリの起動中は同じものを使う
+// Cookie はプロファイルごとのファイルに保存され、次に起動したときも引き継がれる
+pub struct PostSession {
+    http: reqwest::Client,
+    cookies: Arc<PersistentCookieJar>,
+}
+
+impl PostSession {
+    pub fn new(cookies: Arc<PersistentCookieJar>) -> Result<Self, String> {
+        let http = reqwest::Client::builder()
+            .user_agent(USER_AGENT)
+            .cookie_provider(cookies.clone())
+            .build()
+            .map_err(|e| format!("HTTPクライアントの作成に失敗しました: {}", e))?;
+        Ok(PostSession { http, cookies })
+    }
+
+    // プロファイルの Cookie を読み込んで作る
+    // Cookie のファイルが壊れていても起動できるよう、読み込めなければ保存しない空の Cookie で始める
+    pub fn open_profile(profile: &str) -> Result<Self, String> {
+        let cookies = PersistentCookieJar::open_profile(profile).unwrap_or_else(|e| {
+            eprintln!(
+                "[Rust PostSession] Cookie を読み込めないため、保存しない空の Cookie で始めます (プロファイル: {}): {}",
+                profile, e
+            );
+            PersistentCookieJar::in_memory(profile)
+        });
+        Self::new(Arc::new(cookies))
+    }
+
+    pub fn http(&self) -> &reqwest::Client {
+        &self.http
+    }
+
+    pub fn cookies(&self) -> &PersistentCookieJar {
+        &self.cookies
+    }
+}
+
+// レスを書き込む
+pub async fn submit_post(
+    client: &BoardClient,
+    http: &reqwest::Client,
+    form: &PostForm,
+) -> Result<PostResult, String> {
+    if form.body.trim().is_empty() {
+        return Ok(PostResult::Error {
+            message: "本文がありません。".to_string(),
+        });
+    }
+    let fields = vec![
+        ("bbs".to_string(), client.board_id().to_string()),
+        ("key".to_string(), form.thread_id.clone()),
+        (
+            "time".to_string(),
+            chrono::Utc::now().timestamp().to_string(),
+        ),
+        ("FROM".to_string(), form.name.clone()),
+        ("mail".to_string(), form.mail.clone()),
+        ("MESSAGE".to_string(), form.body.clone()),
+        ("submit".to_string(), "書き込む".to_string()),
+    ];
+    let fields = merge_confirmation_fields(fields, form.confirmation.as_deref());
+    let (result, _) =
+        send_form(client, http, &fields, &client.read_cgi_url(&form.thread_id)).await?;
+    Ok(result)
+}
+
+// 書き込み確認ページの隠しフィールド (承諾の印など) を加える
+pub(crate) fn merge_confirmation_fields(
+    mut fields: Vec<(String, String)>,
+    confirmation: Option<&[(String, String)]>,
+) -> Vec<(String, String)> {
+    for (name, value) in confirmation.unwrap_or_default() {
+        if USER_INPUT_FIELDS.contains(&name.as_str()) {
+            continue;
+        }
+        match fields.iter_mut().find(|(existing, _)| existing == name) {
+            Some(field) => field.1 = value.clone(),
+            None => fields.push((name.clone(), value.clone())),
+        }
+    }
+    fields
+}
+
+// フォームを板の文字コードで送信し、応答を判定する (デコードした応答の HTML も返す)
+pub(crate) async fn send_form(
+    client: &BoardClient,
+    http: &reqwest::Client,
+    fields: &[(String, String)],
+    referer: &str,
+) -> Result<(PostResult, String), String> {
+    let url = client.bbs_cgi_url();
+    let response = http
+        .post(&url)
+        .header(CONTENT_TYPE, "application/x-www-form-urlencoded")
+        .header(REFERER, referer)
+        .body(encode_form(fields, client.encoding()))
+        .send()
+        .await
+        .map_err(|e| format!("書き込みのリクエストに失敗しました (URL: {}): {}", url, e))?;
+    let status = response.status();
+    let bytes = response
+        .bytes()
+        .await
+        .map_err(|e| format!("書き込み結果の読み取りに失敗しました: {}", e))?;
+    let html = client.encoding().decode(&bytes);
+
+    // HTML でない応答の HTTP エラーは、ステータスを伝える
+    if !status.is_success() && html_title(&html).is_none() {
+        let result = PostResult::Error {
+            message: format!("HTTPエラー: {}", status),
+        };
+        return Ok((result, html));
+    }
+    Ok((classify_post_response(&html), html))
+}
+
+// application/x-www-form-urlencoded の本文を作る (値は板の文字コードでエンコードしてからパーセントエンコードする)
+pub(crate) fn encode_form(fields: &[(String, String)], encoding: BoardEncoding) -> String {
+    fields
+        .iter()
+        .map(|(name, value)| {
+            // フォームの改行は CRLF で送る
+            let value = value.replace("\r\n", "\n").replace('\n', "\r\n");
+            format!(
+                "{}={}",
+                percent_encode(&encoding.encode(name)),
+                percent_encode(&encoding.encode(&value))
+            )
+        })
+        .collect::<Vec<_>>()
+        .join("&")
+}
+
+fn percent_encode(bytes: &[u8]) -> String {
+    let mut encoded = String::with_capacity(bytes.len() * 3);
+    for &byte in bytes {
+        match byte {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'_' | b'.' | b'*' => {
+                encoded.push(byte as char)
+            }
+            b' ' => encoded.push('+'),
+            _ => encoded.push_str(&format!("%{:02X}", byte)),
+        }
+    }
+    encoded
+}
+
+// ===== 応答の判定 =====
+// bbs.cgi は成功・失敗にかかわらず 200 を返すことが多いので、
+// <!-- 2ch_X:... --> の印と <title> で判定する
+
+pub(crate) fn cached_regex(cell: &'static OnceLock<Regex>, pattern: &str) -> &'static Regex {
+    cell.get_or_init(|| Regex::new(pattern).expect("正規表現が不正です"))
+}
+
+fn html_title(html: &str) -> Option<String> {
+    static TITLE_RE: OnceLock<Regex> = OnceLock::new();
+    cached_regex(&TITLE_RE, r"(?is)<title[^>]*>(.*?)</title>")
+        .captures(html)
+        .map(|caps| plain_text(&caps[1]))
+}
+
+pub fn classify_post_response(html: &str) -> PostResult {
+    static STATUS_MARK_RE: OnceLock<Regex> = OnceLock::new();
+    let mark = cached_regex(&STATUS_MARK_RE, r"<!--\s*2ch_X:(\w+)\s*-->")
+        .captures(html)
+        .map(|caps| caps[1].to_ascii_lowercase());
+    let title = html_title(html).unwrap_or_default();
+
+    match mark.as_deref() {
+        // false は「書き込みましたが注意があります」
+        Some("true") | Some("false") => PostResult::Ok,
+        Some("cookie") => confirmation_result(html),
+        Some("error") | Some("check") => error_result(html),
+        _ if title.contains("書きこみました") || title.contains("書き込みました") => {
+            PostResult::Ok
+        }
+        _ if title.contains("書き込み確認") => confirmation_result(html),
+        _ if title.contains("ERROR") || title.contains("ＥＲＲＯＲ") => error_result(html),
+        _ => PostResult::Error {
+            message: format!(
+                "書き込み結果を判別できませんでした: {}",
+                truncate_message(&body_text(html))
+            ),
+        },
+    }
+}
+
+fn confirmation_result(html: &str) -> PostResult {
+    PostResult::NeedsConfirmation {
+        message: truncate_message(&body_text(html)),
+        fields: hidden_fields(html),
+    }
+}
+
+fn error_result(html: &str) -> PostResult {
+    // ＥＲＲＯＲ：... は <b> の中にあることが多い
+    static ERROR_LINE_RE: OnceLock<Regex> = OnceLock::new();
+    let message = cached_regex(&ERROR_LINE_RE, r"(?is)<b>\s*((?:ERROR|ＥＲＲＯＲ).*?)</b>")
+        .captures(html)
+        .map(|caps| plain_text(&caps[1]))
+        .unwrap_or_else(|| body_text(html));
+    let message = truncate_message(&message);
+
+    static RATE_LIMIT_RE: OnceLock<Regex> = OnceLock::new();
+    static RETRY_AFTER_RE: OnceLock<Regex> = OnceLock::new();
+    if cached_regex(
+        &RATE_LIMIT_RE,
+        r"(?i)連続投稿|連投|たたないと書けません|時間を置いて|しばらく(?:お)?待|Samba|バーボン",
+    )
+    .is_match(&message)
+    {
+        let retry_after_secs = cached_regex(&RETRY_AFTER_RE, r"(?i)(\d+)\s*(?:sec|秒)")
+            .captures(&message)
+            .and_then(|caps| caps[1].parse().ok());
+        return PostResult::RateLimited {
+            message,
+            retry_after_secs,
+        };
+    }
+    PostResult::Error { message }
+}
+
+// <body> の中身をプレーンテキストにして、空白をまとめる
+fn body_text(html: &str) -> String {
+    static BODY_RE: OnceLock<Regex> = OnceLock::new();
+    static HIDDEN_RE: OnceLock<Regex> = OnceLock::new();
+    let body = cached_regex(&BODY_RE, r"(?is)<body[^>]*>(.*?)(?:</body>|$)")
+        .captures(html)
+        .map(|caps| caps[1].to_string())
+        .unwrap_or_else(|| html.to_string());
+    // script・style・コメントは表示されないので除く
+    let body = cached_regex(
+        &HIDDEN_RE,
+        r"(?is)<script\b.*?</script>|<style\b.*?</style>|<!--.*?-->",
+    )
+    .replace_all(&body, "");
+    plain_text(&body)
+        .split_whitespace()
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+fn truncate_message(message: &str) -> String {
+    if message.chars().count() <= MAX_MESSAGE_CHARS {
+        return message.to_string();
+    }
+    let mut truncated: String = message.chars().take(MAX_MESSAGE_CHARS).collect();
+    truncated.push('…');
+    truncated
+}
+
+// 書き込み確認ページのフォームにある隠しフィールド
+fn hidden_fields(html: &str) -> Vec<(String, String)> {
+    static INPUT_RE: OnceLock<Regex> = OnceLock::new();
+    static ATTRIBUTE_RE: OnceLock<Regex> = OnceLock::new();
+    let attribute = cached_regex(
+        &ATTRIBUTE_RE,
+        r#"(?i)\b(type|name|value)\s*=\s*(?:"([^"]*)"|'([^']*)'|([^\s>]+))"#,
+    );
+    cached_regex(&INPUT_RE, r"(?is)<input\b[^>]*>")
+        .find_iter(html)
+        .filter_map(|input| {
+            let (mut kind, mut name, mut value) = (None, None, String::new());
+            for caps in attribute.captures_iter(input.as_str()) {
+                let text = caps
+                    .get(2)
+                    .or_else(|| caps.get(3))
+                    .or_else(|| caps.get(4))
+                    .map_or("", |m| m.as_str());
+                let text = decode_html_entities(text).into_owned();
+                match caps[1].to_ascii_lowercase().as_str() {
+                    "type" => kind = Some(text.to_ascii_lowercase()),
+                    "name" => name = Some(text),
+                    _ => value = text,
+                }
+            }
+            match (kind.as_deref(), name) {
+                (Some("hidden"), Some(name)) => Some((name, value)),
+                _ => None,
+            }
+        })
+        .collect()
+}
+
+// レスを書き込む。結果は PostResult で返し、通信できなかった場合だけ Err にする
+#[cfg(feature = "tauri")]
+#[tauri::command]
+pub async fn post_response(
+    form: PostForm,
+    session: State<'_, PostSession>,
+    cache: State<'_, ThreadCache>,
+    store: State<'_, LogStore>,
+    drafts: State<'_, DraftSaver>,
+) -> Result<PostResult, String> {
+    eprintln!(
+        "[Rust post_response] 書き込みます (スレッドID: {}, 確認済み: {})",
+        form.thread_id,
+        form.confirmation.is_some()
+    );
+    let client = BoardClient::default();
+    let sent_at = chrono::Utc::now().timestamp();
+    let result = submit_post(&client, session.http(), &form).await?;
+    eprintln!("[Rust post_response] 書き込み結果: {:?}", result);
+    // 書き込み履歴に残す (レス番号は次に dat を取得したときに照合する)
+    if result == PostResult::Ok {
+        if let Err(e) = store.record_my_post(
+            client.board_id(),
+            &form.thread_id,
+            &form.name,
+            &form.mail,
+            &form.body,
+            sent_at,
+        ) {
+            eprintln!("[Rust post_response] {}", e);
+        }
+        if let Err(e) = drafts.discard(&store, client.board_id(), &form.thread_id) {
+            eprintln!("[Rust post_response] {}", e);
+        }
+        // 書き込んだレスが入るよう、次に開くときは取得し直す
+        cache.invalidate(&form.thread_id);
+    }
+    Ok(result)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_server::{Response, TestServer};
+
+    fn page(title: &str, body: &str) -> String {
+        format!(
+            "<html><head><title>{}</title></head><body>{}</body></html>",
+            title, body
+        )
+    }
+
+    fn fields(pairs: &[(&str, &str)]) -> Vec<(String, String)> {
+        pairs
+            .iter()
+            .map(|(name, value)| (name.to_string(), value.to_string()))
+            .collect()
+    }
+
+    #[test]
+    fn classifies_status_marks() {
+        assert_eq!(
+            classify_post_response(&page("", "<!-- 2ch_X:true -->")),
+            PostResult::Ok
+        );
+        assert_eq!(
+            classify_post_response(&page("", "<!-- 2ch_X:false --> 注意があります")),
+            PostResult::Ok
+        );
+        assert_eq!(
+            classify_post_response(&page(
+                "ＥＲＲＯＲ！",
+                "<!-- 2ch_X:error --><b>ERROR: 本文がありません！</b>"
+            )),
+            PostResult::Error {
+                message: "ERROR: 本文がありません！".to_string()
+            }
+        );
+        assert_eq!(
+            classify_post_response(&page(
+                "ＥＲＲＯＲ！",
+                "<!-- 2ch_X:check --><b>ＥＲＲＯＲ：連続投稿ですか？？ 30 sec たたないと書けません。</b>"
+            )),
+            PostResult::RateLimited {
+                message: "ＥＲＲＯＲ：連続投稿ですか？？ 30 sec たたないと書けません。".to_string(),
+                retry_after_secs: Some(30),
+            }
+        );
+        assert_eq!(
+            classify_post_response(&page(
+                "書き込み確認",
+                r#"<!-- 2ch_X:cookie -->書き込みと Cookie の確認<form><input type="hidden" name="yuki" value="akari"></form>"#
+            )),
+            PostResult::NeedsConfirmation {
+                message: "書き込みと Cookie の確認".to_string(),
+                fields: fields(&[("yuki", "akari")]),
+            }
+        );
+    }
+
+    #[test]
+    fn classifies_pages_without_status_marks_by_title() {
+        assert_eq!(
+            classify_post_response(&page("書きこみました。", "")),
+            PostResult::Ok
+        );
+        assert_eq!(
+            classify_post_response(&page(
+                "■ 書き込み確認 ■",
+                r#"投稿確認<input type=hidden name=feature value=confirmed>"#
+            )),
+            PostResult::NeedsConfirmation {
+                message: "投稿確認".to_string(),
+                fields: fields(&[("feature", "confirmed")]),
+            }
+        );
+        assert!(matches!(
+            classify_post_response(&page("ERROR", "<b>ERROR: 規制中です</b>")),
+            PostResult::Error { .. }
+        ));
+        assert!(matches!(
+            classify_post_response("<html>メンテナンス中</html>"),
+            PostResult::Error { message } if message.contains("判別できません")
+        ));
+    }
+
+    #[test]
+    fn encodes_form_in_shift_jis() {
+        let form = fields(&[
+            ("FROM", "名無し"),
+            ("mail", "sage"),
+            ("MESSAGE", "テスト\n2行目 です"),
+            ("x", "&=+"),
+        ]);
+        assert_eq!(
+            encode_form(&form, BoardEncoding::ShiftJis),
+            "FROM=%96%BC%96%B3%82%B5&mail=sage&MESSAGE=%83e%83X%83g%0D%0A2%8Ds%96%DA+%82%C5%82%B7&x=%26%3D%2B"
+        );
+        assert_eq!(
+            encode_form(&fields(&[("FROM", "名無し")]), BoardEncoding::Utf8),
+            "FROM=%E5%90%8D%E7%84%A1%E3%81%97"
+        );
+    }
+
+    #[test]
+    fn reads_hidden_fields() {
+        let html = r#"<form>
+            <input type="hidden" name="a" value="1">
+            <INPUT TYPE='HIDDEN' NAME='b' VALUE='x&amp;y'>
+            <input type=hidden name=c>
+            <input type="text" name="FROM" value="名無し">
+            <input type="submit" value="上記全てを承諾して書き込む">
+        </form>"#;
+        assert_eq!(
+            hidden_fields(html),
+            fields(&[("a", "1"), ("b", "x&y"), ("c", "")])
+        );
+    }
+
+    #[test]
+    fn merges_confirmation_fields_without_overwriting_user_input() {
+        let base = fields(&[("bbs", "news"), ("MESSAGE", "本文"), ("time", "1")]);
+        let confirmation = fields(&[("time", "2"), ("MESSAGE", "書き換え"), ("yuki", "akari")]);
+        assert_eq!(
+            merge_confirmation_fields(base.clone(), Some(&confirmation)),
+            fields(&[
+                ("bbs", "news"),
+                ("MESSAGE", "本文"),
+                ("time", "2"),
+                ("yuki", "akari")
+            ])
+        );
+        assert_eq!(merge_confirmation_fields(base.clone(), None), base);
+    }
+
+    // 書き込み確認で受け取った Cookie と隠しフィールドを付けて送り直すと書き込める
+    #[tokio::test]
+    async fn confirms_then_resubmits_with_cookie() {
+        let server = TestServer::start(|request| {
+            let body = String::from_utf8_lossy(&request.body);
+            match request.header("cookie") {
+                Some(cookie) if cookie == "yuki=akari" && body.contains("feature=confirmed") => {
+                    Response::new(200, page("書きこみました。", "<!-- 2ch_X:true -->"))
+                }
+                _ => {
+                    let mut response = Response::new(
+                        200,
+                        page(
+                            "書き込み確認",
+                            r#"<!-- 2ch_X:cookie -->確認してください<input type="hidden" name="feature" value="confirmed">"#,
+                        ),
+                    );
+                    response
+                        .headers
+                        .push(("Set-Cookie".to_string(), "yuki=akari; Path=/".to_string()));
+                    response
+                }
+            }
+        })
+        .await;
+        let client = BoardClient::new(&server.url(), "news").with_encoding(BoardEncoding::ShiftJis);
+        let session = PostSession::new(Arc::new(PersistentCookieJar::in_memory("test"))).unwrap();
+        let mut form = PostForm {
+            thread_id: "1700000000".to_string(),
+            name: "名無し".to_string(),
+            mail: String::new(),
+            body: "本文".to_string(),
+            confirmation: None,
+        };
+
+        let result = submit_post(&client, session.http(), &form).await.unwrap();
+        let PostResult::NeedsConfirmation { fields, .. } = result else {
+            panic!("書き込み確認になりませんでした: {:?}", result);
+        };
+        assert_eq!(
+            fields,
+            vec![("feature".to_string(), "confirmed".to_string())]
+        );
+
+        form.confirmation = Some(fields);
+        let result = submit_post(&client, session.http(), &form).await.unwrap();
+        assert_eq!(result, PostResult::Ok);
+
+        let requests = server.requests();
+        assert_eq!(requests.len(), 2);
+        for request in &requests {
+            assert_eq!(request.method, "POST");
+            assert_eq!(request.path, "/test/bbs.cgi");
+            assert_eq!(
+                request.header("referer"),
+                Some(client.read_cgi_url("1700000000").as_str())
+            );
+            let body = String::from_utf8_lossy(&request.body);
+            assert!(body.contains("bbs=news&key=1700000000&"), "{}", body);
+            assert!(body.contains("FROM=%96%BC%96%B3%82%B5"), "{}", body);
+        }
+        assert_eq!(requests[0].header("cookie"), None);
+        assert_eq!(requests[1].header("cookie"), Some("yuki=akari"));
+    }
+
+    #[tokio::test]
+    async fn empty_body_is_not_sent() {
+        let server = TestServer::start(|_| Response::new(200, "")).await;
+        let client = BoardClient::new(&server.url(), "news");
+        let form = PostForm {
+            thread_id: "1700000000".to_string(),
+            name: String::new(),
+            mail: String::new(),
+            body: " \n".to_string(),
+            confirmation: None,
+        };
+        let result = submit_post(&client, &reqwest::Client::new(), &form)
+            .await
+            .unwrap();
+        assert!(matches!(result, PostResult::Error { .. }));
+        assert!(server.requests().is_empty());
+    }
+}
@@ -0,0 +1,602 @@
+use base64::{engine::general_purpose::STANDARD as Base64Standard, Engine as _};
+use image::codecs::jpeg::JpegEncoder;
+use image::imageops::FilterType;
+use image::{DynamicImage, ImageDecoder, ImageFormat, ImageReader};
+use reqwest::header::AUTHORIZATION;
+use serde::Deserialize;
+use std::io::Cursor;
+use std::path::Path;
+#[cfg(feature = "tauri")]
+use tauri::State;
+
+#[cfg(feature = "tauri")]
+use crate::settings::load_settings;
+use crate::settings::ImageHostSettings;
+use crate::store::log_store::LogStore;
+use crate::store::uploads::ImageUpload;
+
+// JPEG を書き出し直すときの品質
+const JPEG_QUALITY: u8 = 90;
+
+// アップロードする画像の出どころ (フロントエンドでは { kind: "file", path: "..." } の形で渡す)
+#[derive(Debug, Deserialize, Clone)]
+#[serde(rename_all = "snake_case", tag = "kind")]
+pub enum ImageSource {
+    File { path: String },
+    // クリップボードから貼り付けた画像 (フロントエンドで base64 か data URL にしたもの)
+    Clipboard { data: String },
+}
+
+impl ImageSource {
+    // 画像のバイト列と、履歴に残す名前を返す
+    pub fn read(&self) -> Result<(Vec<u8>, String), String> {
+        match self {
+            ImageSource::File { path } => {
+                let bytes = std::fs::read(path).map_err(|e| format!("{}: {}", path, e))?;
+                let name = Path::new(path)
+                    .file_name()
+                    .map(|name| name.to_string_lossy().into_owned())
+                    .unwrap_or_else(|| path.clone());
+                Ok((bytes, name))
+            }
+            ImageSource::Clipboard { data } => {
+                let data = match data.strip_prefix("data:") {
+                    Some(data_url) => data_url.split_once(',').map_or("", |(_, data)| data),
+                    None => data.as_str(),
+                };
+                let bytes = Base64Standard
+                    .decode(data.trim())
+                    .map_err(|e| format!("クリップボードの画像を読み取れません: {}", e))?;
+                Ok((bytes, "クリップボード".to_string()))
+            }
+        }
+    }
+}
+
+// アップロードできる形にした画像
+#[derive(Debug, Clone)]
+pub struct PreparedImage {
+    pub bytes: Vec<u8>,
+    pub format: ImageFormat,
+    pub width: u32,
+    pub height: u32,
+}
+
+// EXIF などのメタデータを落とし、max_dimension があれば長辺がそれに収まるよう縮小する
+// 一度デコードして書き出し直すので、メタデータは残らない (向きだけは EXIF に従って画素を回しておく)
+// JPEG は JPEG のまま、それ以外は PNG にする。ただし GIF は縮小が要らなければアニメーションを残すため
+// 書き出し直さず、コメントや XMP などのメタデータのブロックだけを取り除いて送る
+pub fn prepare_image(bytes: &[u8], max_dimension: Option<u32>) -> Result<PreparedImage, String> {
+    let reader = ImageReader::new(Cursor::new(bytes))
+        .with_guessed_format()
+        .map_err(|e| format!("画像を読み込めません: {}", e))?;
+    let format = reader
+        .format()
+        .ok_or_else(|| "画像の形式が分かりません。".to_string())?;
+    let mut decoder = reader
+        .into_decoder()
+        .map_err(|e| format!("画像を読み込めません: {}", e))?;
+    let (width, height) = decoder.dimensions();
+    let needs_resize = max_dimension.is_some_and(|max| width.max(height) > max);
+    if format == ImageFormat::Gif && !needs_resize {
+        return Ok(PreparedImage {
+            bytes: strip_gif_metadata(bytes)?,
+            format,
+            width,
+            height,
+        });
+    }
+
+    let orientation = decoder
+        .orientation()
+        .map_err(|e| format!("画像の向きを読み取れません: {}", e))?;
+    let mut image =
+        DynamicImage::from_decoder(decoder).map_err(|e| format!("画像を読み込めません: {}", e))?;
+    image.apply_orientation(orientation);
+    if let Some(max) = max_dimension.filter(|&max| image.width().max(image.height()) > max) {
+        image = image.resize(max, max, FilterType::Lanczos3);
+    }
+
+    let mut out = Vec::new();
+    let format = if format == ImageFormat::Jpeg {
+        DynamicImage::ImageRgb8(image.to_rgb8())
+            .write_with_encoder(JpegEncoder::new_with_quality(&mut out, JPEG_QUALITY))
+            .map_err(|e| format!("画像の書き出しに失敗しました: {}", e))?;
+        ImageFormat::Jpeg
+    } else {
+        image
+            .write_to(&mut Cursor::new(&mut out), ImageFormat::Png)
+            .map_err(|e| format!("画像の書き出しに失敗しました: {}", e))?;
+        ImageFormat::Png
+    };
+    Ok(PreparedImage {
+        bytes: out,
+        format,
+        width: image.width(),
+        height: image.height(),
+    })
+}
+
+// GIF の拡張ブロックのうち、表示に使うもの (描画制御、テキスト、ループ回数) 以外を取り除く
+// コメント (0xFE) や、XMP・ICC プロファイルなどのアプリケーション拡張が落ちる
+fn strip_gif_metadata(bytes: &[u8]) -> Result<Vec<u8>, String> {
+    const GRAPHIC_CONTROL: u8 = 0xF9;
+    const PLAIN_TEXT: u8 = 0x01;
+    const APPLICATION: u8 = 0xFF;
+    let broken = || "GIF の構造が壊れています。".to_string();
+    let color_table_len = |packed: u8| {
+        if packed & 0x80 != 0 {
+            3 << ((packed & 0x07) + 1)
+        } else {
+            0
+        }
+    };
+    // サブブロックの並び (長さ1バイト + データ、長さ0で終わり) の終わりの位置
+    let sub_blocks_end = |mut pos: usize| -> Result<usize, String> {
+        loop {
+            let len = *bytes.get(pos).ok_or_else(broken)? as usize;
+            pos += 1 + len;
+            if len == 0 {
+                return Ok(pos);
+            }
+        }
+    };
+
+    // ヘッダー (6バイト) と論理画面記述子 (7バイト)、それに続くグローバルカラーテーブル
+    let packed = *bytes.get(10).ok_or_else(broken)?;
+    let mut pos = 13 + color_table_len(packed);
+    let mut out = bytes.get(..pos).ok_or_else(broken)?.to_vec();
+    loop {
+        match *bytes.get(pos).ok_or_else(broken)? {
+            // 画像記述子 (10バイト)、ローカルカラーテーブル、LZW の最小符号長、画像データ
+            0x2C => {
+                let packed = *bytes.get(pos + 9).ok_or_else(broken)?;
+                let end = sub_blocks_end(pos + 10 + color_table_len(packed) + 1)?;
+                out.extend_from_slice(bytes.get(pos..end).ok_or_else(broken)?);
+                pos = end;
+            }
+            0x21 => {
+                let label = *bytes.get(pos + 1).ok_or_else(broken)?;
+                let end = sub_blocks_end(pos + 2)?;
+                let block = bytes.get(pos..end).ok_or_else(broken)?;
+                // アプリケーション拡張は先頭のサブブロックの識別子 (11バイト) で見分ける
+                let keep = match label {
+                    GRAPHIC_CONTROL | PLAIN_TEXT => true,
+                    APPLICATION => matches!(
+                        block.get(3..14),
+                        Some(b"NETSCAPE2.0") | Some(b"ANIMEXTS1.0")
+                    ),
+                    _ => false,
+                };
+                if keep {
+                    out.extend_from_slice(block);
+                }
+                pos = end;
+            }
+            0x3B => {
+                out.push(0x3B);
+                return Ok(out);
+            }
+            _ => return Err(broken()),
+        }
+    }
+}
+
+// imgur 互換の API の応答 ({ data: {...}, success: true, status: 200 })
+#[derive(Debug, Deserialize)]
+struct HostResponse {
+    #[serde(default)]
+    data: serde_json::Value,
+    #[serde(default)]
+    success: bool,
+}
+
+impl HostResponse {
+    // 失敗したときの data.error は文字列のこともオブジェクトのこともある
+    fn error_message(&self) -> String {
+        match self.data.get("error") {
+            Some(serde_json::Value::String(message)) => message.clone(),
+            Some(serde_json::Value::Object(error)) => error
+                .get("message")
+                .and_then(|message| message.as_str())
+                .map(str::to_string)
+                .unwrap_or_else(|| serde_json::Value::Object(error.clone()).to_string()),
+            _ => self.data.to_string(),
+        }
+    }
+}
+
+// アップロードした画像の URL と、削除に使うハッシュ
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct UploadedImage {
+    pub url: String,
+    pub delete_hash: String,
+}
+
+// 画像のアップロード先 (imgur 互換の API)
+pub struct ImageHost {
+    api_url: String,
+    client_id: String,
+    http: reqwest::Client,
+}
+
+impl ImageHost {
+    pub fn new(settings: &ImageHostSettings) -> Result<Self, String> {
+        if settings.client_id.trim().is_empty() {
+            return Err(
+                "画像のアップロード先の Client ID が設定されていません (設定ウィンドウで設定してください)。"
+                    .to_string(),
+            );
+        }
+        Ok(ImageHost {
+            api_url: settings.api_url.trim_end_matches('/').to_string(),
+            client_id: settings.client_id.trim().to_string(),
+            http: reqwest::Client::new(),
+        })
+    }
+
+    pub fn api_url(&self) -> &str {
+        &self.api_url
+    }
+
+    fn authorization(&self) -> String {
+        format!("Client-ID {}", self.client_id)
+    }
+
+    // POST {api_url}/image (imgur と同じく、base64 にした画像を image フィールドで送る)
+    pub async fn upload(
+        &self,
+        image: &PreparedImage,
+        file_name: &str,
+    ) -> Result<UploadedImage, String> {
+        let url = format!("{}/image", self.api_url);
+        let form = [
+            ("image", Base64Standard.encode(&image.bytes)),
+            ("type", "base64".to_string()),
+            ("name", file_name.to_string()),
+        ];
+        let response = self
+            .http
+            .post(&url)
+            .header(AUTHORIZATION, self.authorization())
+            .form(&form)
+            .send()
+            .await
+            .map_err(|e| format!("アップロードに失敗しました (URL: {}): {}", url, e))?;
+        let status = response.status();
+        let body: HostResponse = response.json().await.map_err(|e| {
+            format!(
+                "アップロード先の応答を読み取れません (HTTP {}): {}",
+                status, e
+            )
+        })?;
+        if !status.is_success() || !body.success {
+            return Err(format!(
+                "アップロードに失敗しました (HTTP {}): {}",
+                status,
+                body.error_message()
+            ));
+        }
+        let field = |name: &str| {
+            body.data
+                .get(name)
+                .and_then(|value| value.as_str())
+                .map(str::to_string)
+                .ok_or_else(|| format!("アップロード先の応答に {} がありません。", name))
+        };
+        Ok(UploadedImage {
+            url: field("link")?,
+            delete_hash: field("deletehash")?,
+        })
+    }
+
+    // DELETE {api_url}/image/{delete_hash}。もう無い画像 (404) は削除できたものとして扱う
+    pub async fn delete(&self, delete_hash: &str) -> Result<(), String> {
+        let url = format!("{}/image/{}", self.api_url, delete_hash);
+        let response = self
+            .http
+            .delete(&url)
+            .header(AUTHORIZATION, self.authorization())
+            .send()
+            .await
+            .map_err(|e| format!("削除に失敗しました (URL: {}): {}", url, e))?;
+        let status = response.status();
+        if status == reqwest::StatusCode::NOT_FOUND {
+            eprintln!(
+                "[Rust ImageHost::delete] 画像はすでに削除されています: {}",
+                url
+            );
+            return Ok(());
+        }
+        if !status.is_success() {
+            let message = match response.json::<HostResponse>().await {
+                Ok(body) => body.error_message(),
+                Err(_) => String::new(),
+            };
+            return Err(format!("削除に失敗しました (HTTP {}): {}", status, message));
+        }
+        Ok(())
+    }
+}
+
+// 画像を読み込んでメタデータを落とし、アップロードして履歴に残す
+pub async fn upload_image_to_host(
+    store: &LogStore,
+    settings: &ImageHostSettings,
+    source: &ImageSource,
+) -> Result<ImageUpload, String> {
+    let host = ImageHost::new(settings)?;
+    let (bytes, file_name) = source.read()?;
+    // デコードと縮小は重いので、非同期のスレッドを止めないよう別スレッドで行う
+    let max_dimension = settings.max_dimension;
+    let image = tokio::task::spawn_blocking(move || prepare_image(&bytes, max_dimension))
+        .await
+        .map_err(|e| format!("画像の処理に失敗しました: {}", e))??;
+    let uploaded = host.upload(&image, &file_name).await?;
+    store.record_image_upload(&ImageUpload {
+        id: 0,
+        api_url: host.api_url().to_string(),
+        url: uploaded.url,
+        delete_hash: uploaded.delete_hash,
+        file_name,
+        width: image.width,
+        height: image.height,
+        size: image.bytes.len() as u64,
+        uploaded_at: chrono::Utc::now().timestamp(),
+    })
+}
+
+// アップロードした画像をアップロード先から削除し、履歴からも消す
+// 削除はアップロードしたときの API に送る (設定でアップロード先を変えていても消せるように)
+pub async fn delete_uploaded_image(
+    store: &LogStore,
+    settings: &ImageHostSettings,
+    id: i64,
+) -> Result<(), String> {
+    let upload = store
+        .get_image_upload(id)?
+        .ok_or_else(|| format!("アップロード履歴が見つかりません (ID: {})", id))?;
+    let host = ImageHost::new(&ImageHostSettings {
+        api_url: upload.api_url.clone(),
+        ..settings.clone()
+    })?;
+    host.delete(&upload.delete_hash).await?;
+    store.remove_image_upload(id)
+}
+
+// 画像をアップロードして、本文に貼る URL を含む履歴の1件を返す
+#[cfg(feature = "tauri")]
+#[tauri::command]
+pub async fn upload_image(
+    source: ImageSource,
+    store: State<'_, LogStore>,
+) -> Result<ImageUpload, String> {
+    let settings = load_settings()?.image_host;
+    let upload = upload_image_to_host(&store, &settings, &source).await?;
+    eprintln!(
+        "[Rust upload_image] アップロードしました: {} ({}x{}, {} bytes)",
+        upload.url, upload.width, upload.height, upload.size
+    );
+    Ok(upload)
+}
+
+// アップロード履歴 (新しい順、最大 limit 件)
+#[cfg(feature = "tauri")]
+#[tauri::command]
+pub async fn list_image_uploads(
+    limit: Option<usize>,
+    store: State<'_, LogStore>,
+) -> Result<Vec<ImageUpload>, String> {
+    store.list_image_uploads(limit.unwrap_or(200))
+}
+
+#[cfg(feature = "tauri")]
+#[tauri::command]
+pub async fn delete_image_upload(id: i64, store: State<'_, LogStore>) -> Result<(), String> {
+    let settings = load_settings()?.image_host;
+    delete_uploaded_image(&store, &settings, id).await?;
+    eprintln!("[Rust delete_image_upload] 削除しました (ID: {})", id);
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_server::{Response, TestServer};
+    use image::codecs::gif::{GifEncoder, Repeat};
+    use image::{AnimationDecoder, Frame, Rgb, RgbImage, RgbaImage};
+
+    // 左半分が白、右半分が黒の JPEG に、向き (Orientation) だけを持つ EXIF を付ける
+    fn jpeg_with_orientation(width: u32, height: u32, orientation: u8) -> Vec<u8> {
+        let image = RgbImage::from_fn(width, height, |x, _| {
+            if x < width / 2 {
+                Rgb([255, 255, 255])
+            } else {
+                Rgb([0, 0, 0])
+            }
+        });
+        let mut jpeg = Vec::new();
+        DynamicImage::ImageRgb8(image)
+            .write_to(&mut Cursor::new(&mut jpeg), ImageFormat::Jpeg)
+            .unwrap();
+        let mut exif = b"Exif\0\0MM\0*\0\0\0\x08\0\x01\x01\x12\0\x03\0\0\0\x01\0".to_vec();
+        exif.extend_from_slice(&[orientation, 0, 0, 0, 0, 0, 0]);
+        let mut out = jpeg[..2].to_vec();
+        out.extend_from_slice(&[0xFF, 0xE1]);
+        out.extend_from_slice(&(exif.len() as u16 + 2).to_be_bytes());
+        out.extend_from_slice(&exif);
+        out.extend_from_slice(&jpeg[2..]);
+        out
+    }
+
+    // 画像データ (SOS) の前にある APP1 のうち、EXIF のもの
+    fn has_exif_segment(jpeg: &[u8]) -> bool {
+        let mut pos = 2;
+        while pos + 4 <= jpeg.len() && jpeg[pos] == 0xFF && jpeg[pos + 1] != 0xDA {
+            let len = u16::from_be_bytes([jpeg[pos + 2], jpeg[pos + 3]]) as usize;
+            if jpeg[pos + 1] == 0xE1 && jpeg[pos + 4..].starts_with(b"Exif\0\0") {
+                return true;
+            }
+            pos += 2 + len;
+        }
+        false
+    }
+
+    fn png(width: u32, height: u32) -> Vec<u8> {
+        let mut out = Vec::new();
+        DynamicImage::ImageRgb8(RgbImage::new(width, height))
+            .write_to(&mut Cursor::new(&mut out), ImageFormat::Png)
+            .unwrap();
+        out
+    }
+
+    #[test]
+    fn strips_exif_and_applies_orientation() {
+        let source = jpeg_with_orientation(40, 20, 6);
+        assert!(has_exif_segment(&source));
+
+        let prepared = prepare_image(&source, None).unwrap();
+        assert_eq!(prepared.format, ImageFormat::Jpeg);
+        assert!(!has_exif_segment(&prepared.bytes));
+        // 時計回りに 90 度回すので、左半分の白が上半分に来る
+        let decoded = image::load_from_memory(&prepared.bytes).unwrap().to_luma8();
+        assert_eq!((decoded.width(), decoded.height()), (20, 40));
+        assert_eq!((prepared.width, prepared.height), (20, 40));
+        assert!(decoded.get_pixel(10, 5)[0] > 200);
+        assert!(decoded.get_pixel(10, 35)[0] < 50);
+    }
+
+    #[test]
+    fn downscales_to_max_dimension() {
+        let prepared = prepare_image(&jpeg_with_orientation(40, 20, 1), Some(10)).unwrap();
+        let decoded = image::load_from_memory(&prepared.bytes).unwrap();
+        assert_eq!((decoded.width(), decoded.height()), (10, 5));
+        assert_eq!((prepared.width, prepared.height), (10, 5));
+
+        // 収まっている画像はそのままの大きさで、PNG は PNG のまま
+        let prepared = prepare_image(&png(8, 6), Some(100)).unwrap();
+        assert_eq!(prepared.format, ImageFormat::Png);
+        assert_eq!((prepared.width, prepared.height), (8, 6));
+
+        assert!(prepare_image(b"not an image", None).is_err());
+    }
+
+    #[test]
+    fn keeps_gif_animation_but_drops_metadata_blocks() {
+        let mut gif = Vec::new();
+        {
+            let mut encoder = GifEncoder::new(&mut gif);
+            encoder.set_repeat(Repeat::Infinite).unwrap();
+            for shade in [0, 255] {
+                let frame = RgbaImage::from_pixel(4, 4, image::Rgba([shade, shade, shade, 255]));
+                encoder.encode_frame(Frame::new(frame)).unwrap();
+            }
+        }
+        // 終端 (0x3B) の前にコメントと XMP のアプリケーション拡張を差し込む
+        let trailer = gif.pop().unwrap();
+        gif.extend_from_slice(b"\x21\xFE\x07secret!\x00");
+        gif.extend_from_slice(b"\x21\xFF\x0BXMP DataXMP\x05<xmp>\x00");
+        gif.push(trailer);
+
+        let prepared = prepare_image(&gif, None).unwrap();
+        assert_eq!(prepared.format, ImageFormat::Gif);
+        let contains = |needle: &[u8]| prepared.bytes.windows(needle.len()).any(|w| w == needle);
+        assert!(!contains(b"secret!"));
+        assert!(!contains(b"XMP DataXMP"));
+        assert!(contains(b"NETSCAPE2.0"));
+        assert_eq!(prepared.bytes.len(), gif.len() - 11 - 21);
+        let frames = image::codecs::gif::GifDecoder::new(Cursor::new(&prepared.bytes))
+            .unwrap()
+            .into_frames()
+            .count();
+        assert_eq!(frames, 2);
+
+        assert!(strip_gif_metadata(&gif[..gif.len() - 1]).is_err());
+    }
+
+    #[tokio::test]
+    async fn uploads_and_deletes_through_the_host() {
+        let server = TestServer::start(|request| match request.method.as_str() {
+            "POST" => Response::new(
+                200,
+                r#"{"data":{"id":"abc","link":"https://i.example/abc.jpg","deletehash":"DEL1"},"success":true,"status":200}"#,
+            ),
+            _ => Response::new(200, r#"{"data":true,"success":true,"status":200}"#),
+        })
+        .await;
+        let store = LogStore::open(Path::new(":memory:")).unwrap();
+        let settings = ImageHostSettings {
+            api_url: format!("{}/3/", server.url()),
+            client_id: "cid".to_string(),
+            max_dimension: Some(16),
+        };
+        let source = ImageSource::Clipboard {
+            data: format!(
+                "data:image/jpeg;base64,{}",
+                Base64Standard.encode(jpeg_with_orientation(40, 20, 6))
+            ),
+        };
+
+        let upload = upload_image_to_host(&store, &settings, &source)
+            .await
+            .unwrap();
+        assert_eq!(upload.url, "https://i.example/abc.jpg");
+        assert_eq!(upload.delete_hash, "DEL1");
+        assert_eq!(upload.api_url, format!("{}/3", server.url()));
+        assert_eq!((upload.width, upload.height), (8, 16));
+        assert_eq!(store.list_image_uploads(10).unwrap().len(), 1);
+
+        // 送った画像は縮小済みで EXIF が無い
+        let requests = server.requests();
+        assert_eq!(requests[0].path, "/3/image");
+        assert_eq!(requests[0].header("authorization"), Some("Client-ID cid"));
+        let form = reqwest::Url::parse(&format!(
+            "http://localhost/?{}",
+            String::from_utf8_lossy(&requests[0].body)
+        ))
+        .unwrap();
+        let sent = form
+            .query_pairs()
+            .find(|(name, _)| name == "image")
+            .map(|(_, value)| Base64Standard.decode(value.as_bytes()).unwrap())
+            .unwrap();
+        assert_eq!(sent.len() as u64, upload.size);
+        assert!(!has_exif_segment(&sent));
+
+        delete_uploaded_image(&store, &settings, upload.id)
+            .await
+            .unwrap();
+        let requests = server.requests();
+        assert_eq!(requests[1].method, "DELETE");
+        assert_eq!(requests[1].path, "/3/image/DEL1");
+        assert!(store.list_image_uploads(10).unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn reports_host_errors() {
+        let server = TestServer::start(|request| match request.method.as_str() {
+            "POST" => Response::new(
+                400,
+                r#"{"data":{"error":"Bad image","method":"POST"},"success":false,"status":400}"#,
+            ),
+            _ => Response::new(
+                404,
+                r#"{"data":{"error":"Unable to find an image"},"success":false}"#,
+            ),
+        })
+        .await;
+        let host = ImageHost::new(&ImageHostSettings {
+            api_url: server.url(),
+            client_id: "cid".to_string(),
+            max_dimension: None,
+        })
+        .unwrap();
+        let image = prepare_image(&png(4, 4), None).unwrap();
+        let error = host.upload(&image, "a.png").await.unwrap_err();
+        assert!(error.contains("Bad image"), "{}", error);
+        // もう無い画像の削除は成功として扱う
+        host.delete("GONE").await.unwrap();
+
+        assert!(ImageHost::new(&ImageHostSettings::default()).is_err());
+    }
+}
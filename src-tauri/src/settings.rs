@@ -0,0 +1,132 @@
+// アプリの設定の型と設定ファイルの場所
+// 設定ウィンドウ (menu::settings) は tauri_plugin_store 経由で読み書きするが、
+// ファイルの形式は単純な JSON なので、Tauri を使わないツールからも load_settings で読める
+use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct AppSettings {
+    pub theme: String,
+    pub font_size: u32,
+    // Cookie などを保存するプロファイルの名前 (古い設定ファイルには無いのでデフォルトを補う)
+    #[serde(default = "default_profile")]
+    pub profile: String,
+    #[serde(default)]
+    pub image_host: ImageHostSettings,
+}
+
+// 画像のアップロード先 (imgur 互換の API)
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+#[serde(default)]
+pub struct ImageHostSettings {
+    // API のベースURL。手元のモックサーバーなど、imgur 互換の別のサーバーにも向けられる
+    pub api_url: String,
+    pub client_id: String,
+    // 長辺をこのピクセル数までに縮小する (None なら縮小しない)
+    pub max_dimension: Option<u32>,
+}
+
+impl Default for ImageHostSettings {
+    fn default() -> Self {
+        Self {
+            api_url: DEFAULT_IMAGE_HOST_URL.to_string(),
+            client_id: String::new(),
+            max_dimension: None,
+        }
+    }
+}
+
+pub const DEFAULT_IMAGE_HOST_URL: &str = "https://api.imgur.com/3";
+
+impl Default for AppSettings {
+    fn default() -> Self {
+        Self {
+            theme: "light".to_string(),
+            font_size: 14,
+            profile: default_profile(),
+            image_host: ImageHostSettings::default(),
+        }
+    }
+}
+
+pub const DEFAULT_PROFILE: &str = "default";
+
+fn default_profile() -> String {
+    DEFAULT_PROFILE.to_string()
+}
+
+pub const SETTINGS_STORE_PATH_FILENAME: &str = "setting.json";
+pub const SETTINGS_KEY: &str = "app_settings";
+
+// アプリ用の設定ディレクトリ (…/tulip-browser) を取得し、なければ作成する関数
+pub fn get_app_config_dir() -> Result<PathBuf, String> {
+    let config_dir = dirs::config_dir()
+        .ok_or_else(|| "ユーザーの設定ディレクトリが見つかりませんでした。".to_string())?;
+    let app_config_subdir = config_dir.join("tulip-browser");
+    if !app_config_subdir.exists() {
+        std::fs::create_dir_all(&app_config_subdir).map_err(|e| {
+            format!(
+                "ディレクトリ '{}' の作成に失敗しました: {}",
+                app_config_subdir.display(),
+                e
+            )
+        })?;
+    }
+    Ok(app_config_subdir)
+}
+
+// プロファイルごとのディレクトリ (…/tulip-browser/profiles/<名前>) を取得し、なければ作成する
+// 名前はそのままディレクトリ名になるので、英数字と - _ だけを受け付ける
+pub fn get_profile_dir(profile: &str) -> Result<PathBuf, String> {
+    if profile.is_empty()
+        || !profile
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+    {
+        return Err(format!(
+            "プロファイル名 '{}' は使えません (英数字と - _ のみ)。",
+            profile
+        ));
+    }
+    let profile_dir = get_app_config_dir()?.join("profiles").join(profile);
+    std::fs::create_dir_all(&profile_dir).map_err(|e| {
+        format!(
+            "ディレクトリ '{}' の作成に失敗しました: {}",
+            profile_dir.display(),
+            e
+        )
+    })?;
+    Ok(profile_dir)
+}
+
+// 設定ファイルのフルパスを取得する関数
+pub fn get_store_path() -> Result<PathBuf, String> {
+    Ok(get_app_config_dir()?.join(SETTINGS_STORE_PATH_FILENAME))
+}
+
+// 設定ファイルを直接読む。ファイルやキーが無ければデフォルト設定を返す (ファイルは作らない)
+pub fn load_settings() -> Result<AppSettings, String> {
+    let path = get_store_path()?;
+    let text = match std::fs::read_to_string(&path) {
+        Ok(text) => text,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(AppSettings::default()),
+        Err(e) => return Err(format!("{}: {}", path.display(), e)),
+    };
+    let mut root: serde_json::Map<String, serde_json::Value> = serde_json::from_str(&text)
+        .map_err(|e| {
+            format!(
+                "設定ファイルのパースに失敗しました ({}): {}",
+                path.display(),
+                e
+            )
+        })?;
+    match root.remove(SETTINGS_KEY) {
+        Some(value) => serde_json::from_value(value).map_err(|e| {
+            format!(
+                "キー '{}' からの設定のデシリアライズに失敗しました: {}",
+                SETTINGS_KEY, e
+            )
+        }),
+        None => Ok(AppSettings::default()),
+    }
+}
@@ -0,0 +1,429 @@
+use rusqlite::{params, OptionalExtension};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Mutex, MutexGuard};
+use std::time::Duration;
+#[cfg(feature = "tauri")]
+use tauri::{AppHandle, Manager, State};
+
+use super::log_store::LogStore;
+#[cfg(feature = "tauri")]
+use crate::thread::board::BOARD_ID;
+
+// 最後の入力からこの時間が経ったら下書きをログDBに書く
+pub const DRAFT_SAVE_DELAY: Duration = Duration::from_secs(1);
+
+// スレッドごとの書きかけのレス
+#[derive(Debug, Serialize, Clone, PartialEq, Eq)]
+pub struct Draft {
+    pub board: String,
+    pub thread_id: String,
+    pub thread_title: String, // 一覧のときだけ入る (ログDBにスレッドが無ければ空)
+    pub name: String,
+    pub mail: String,
+    pub body: String,
+    pub updated_at: i64,
+}
+
+// フロントエンドから受け取る下書き (入力のたびに送られてくる)
+#[derive(Debug, Deserialize, Clone)]
+pub struct DraftInput {
+    pub thread_id: String,
+    #[serde(default)]
+    pub name: String,
+    #[serde(default)]
+    pub mail: String,
+    #[serde(default)]
+    pub body: String,
+}
+
+impl Draft {
+    pub fn new(board: &str, input: DraftInput) -> Self {
+        Draft {
+            board: board.to_string(),
+            thread_id: input.thread_id,
+            thread_title: String::new(),
+            name: input.name,
+            mail: input.mail,
+            body: input.body,
+            updated_at: chrono::Utc::now().timestamp(),
+        }
+    }
+
+    // 名前とメール欄だけの下書きは残さない (本文を消したら下書きも消す)
+    fn is_empty(&self) -> bool {
+        self.body.trim().is_empty()
+    }
+}
+
+// (板, スレッドID)
+type DraftKey = (String, String);
+
+struct PendingDraft {
+    generation: u64,
+    draft: Draft,
+}
+
+// 下書きの書き込みを遅らせてまとめる (入力のたびにログDBへ書かないように)
+// schedule で預かった下書きは、DRAFT_SAVE_DELAY の間に次の入力がなければ save_if_current で書く
+#[derive(Default)]
+pub struct DraftSaver {
+    pending: Mutex<HashMap<DraftKey, PendingDraft>>, // スレッドごとの最新の下書き
+    next_generation: AtomicU64,
+}
+
+impl DraftSaver {
+    fn pending(&self) -> Result<MutexGuard<'_, HashMap<DraftKey, PendingDraft>>, String> {
+        self.pending
+            .lock()
+            .map_err(|e| format!("下書きのロックに失敗しました: {}", e))
+    }
+
+    // 下書きを預かる。返した世代が save_if_current に渡すもの
+    pub fn schedule(&self, draft: Draft) -> Result<u64, String> {
+        let generation = self.next_generation.fetch_add(1, Ordering::Relaxed);
+        self.pending()?.insert(
+            (draft.board.clone(), draft.thread_id.clone()),
+            PendingDraft { generation, draft },
+        );
+        Ok(generation)
+    }
+
+    // 預かってから新しい入力がなければ書く。書いたら true
+    // (書き終わるまでロックを持ち、discard で消した直後に古い下書きを書き戻さないようにする)
+    // 書けなかった下書きは預かったままにして、次の flush で書き直す
+    pub fn save_if_current(
+        &self,
+        store: &LogStore,
+        board: &str,
+        thread_id: &str,
+        generation: u64,
+    ) -> Result<bool, String> {
+        let key = (board.to_string(), thread_id.to_string());
+        let mut pending = self.pending()?;
+        match pending.get(&key) {
+            Some(entry) if entry.generation == generation => {}
+            _ => return Ok(false),
+        }
+        let Some(entry) = pending.remove(&key) else {
+            return Ok(false);
+        };
+        if let Err(e) = store.write_draft(&entry.draft) {
+            pending.insert(key, entry);
+            return Err(e);
+        }
+        Ok(true)
+    }
+
+    // 預かっている下書きをすべて書く (読み出す前と終了するときに呼ぶ)。書いた数を返す
+    // 1件書けなくても残りは書き、書けなかった下書きは預かったままにしてエラーをまとめて返す
+    pub fn flush(&self, store: &LogStore) -> Result<usize, String> {
+        let mut pending = self.pending()?;
+        let mut written = 0;
+        let mut errors = Vec::new();
+        pending.retain(|_, entry| match store.write_draft(&entry.draft) {
+            Ok(()) => {
+                written += 1;
+                false
+            }
+            Err(e) => {
+                errors.push(format!("{} (スレッドID: {})", e, entry.draft.thread_id));
+                true
+            }
+        });
+        if errors.is_empty() {
+            Ok(written)
+        } else {
+            Err(errors.join("\n"))
+        }
+    }
+
+    // 書き込みに成功したときなど、預かっている分も含めて下書きを捨てる
+    pub fn discard(&self, store: &LogStore, board: &str, thread_id: &str) -> Result<(), String> {
+        let mut pending = self.pending()?;
+        pending.remove(&(board.to_string(), thread_id.to_string()));
+        store.delete_draft(board, thread_id)
+    }
+}
+
+impl LogStore {
+    // 下書きを保存する。本文が空なら削除する
+    pub fn write_draft(&self, draft: &Draft) -> Result<(), String> {
+        if draft.is_empty() {
+            return self.delete_draft(&draft.board, &draft.thread_id);
+        }
+        self.conn()?
+            .execute(
+                "INSERT INTO drafts (board, thread_id, name, mail, body, updated_at)
+                 VALUES (?1, ?2, ?3, ?4, ?5, ?6)
+                 ON CONFLICT (board, thread_id) DO UPDATE SET
+                    name = excluded.name,
+                    mail = excluded.mail,
+                    body = excluded.body,
+                    updated_at = excluded.updated_at",
+                params![
+                    draft.board,
+                    draft.thread_id,
+                    draft.name,
+                    draft.mail,
+                    draft.body,
+                    draft.updated_at
+                ],
+            )
+            .map_err(|e| format!("下書きの保存に失敗しました: {}", e))?;
+        Ok(())
+    }
+
+    pub fn load_draft(&self, board: &str, thread_id: &str) -> Result<Option<Draft>, String> {
+        self.conn()?
+            .query_row(
+                "SELECT name, mail, body, updated_at FROM drafts
+                 WHERE board = ?1 AND thread_id = ?2",
+                params![board, thread_id],
+                |row| {
+                    Ok(Draft {
+                        board: board.to_string(),
+                        thread_id: thread_id.to_string(),
+                        thread_title: String::new(),
+                        name: row.get(0)?,
+                        mail: row.get(1)?,
+                        body: row.get(2)?,
+                        updated_at: row.get(3)?,
+                    })
+                },
+            )
+            .optional()
+            .map_err(|e| format!("下書きの読み込みに失敗しました: {}", e))
+    }
+
+    pub fn delete_draft(&self, board: &str, thread_id: &str) -> Result<(), String> {
+        self.conn()?
+            .execute(
+                "DELETE FROM drafts WHERE board = ?1 AND thread_id = ?2",
+                params![board, thread_id],
+            )
+            .map_err(|e| format!("下書きの削除に失敗しました: {}", e))?;
+        Ok(())
+    }
+
+    // すべての下書き (新しい順)
+    pub fn list_drafts(&self) -> Result<Vec<Draft>, String> {
+        let conn = self.conn()?;
+        let mut stmt = conn
+            .prepare(
+                "SELECT d.board, d.thread_id, COALESCE(t.title, ''), d.name, d.mail, d.body,
+                        d.updated_at
+                 FROM drafts d
+                 LEFT JOIN threads t ON t.board = d.board AND t.thread_id = d.thread_id
+                 ORDER BY d.updated_at DESC",
+            )
+            .map_err(|e| e.to_string())?;
+        let rows = stmt
+            .query_map([], |row| {
+                Ok(Draft {
+                    board: row.get(0)?,
+                    thread_id: row.get(1)?,
+                    thread_title: row.get(2)?,
+                    name: row.get(3)?,
+                    mail: row.get(4)?,
+                    body: row.get(5)?,
+                    updated_at: row.get(6)?,
+                })
+            })
+            .map_err(|e| e.to_string())?;
+        rows.collect::<Result<Vec<_>, _>>()
+            .map_err(|e| format!("下書きの読み込みに失敗しました: {}", e))
+    }
+}
+
+// 入力のたびに呼ばれる。ログDBへは最後の入力から DRAFT_SAVE_DELAY 後にまとめて書く
+#[cfg(feature = "tauri")]
+#[tauri::command]
+pub fn save_draft(
+    draft: DraftInput,
+    app_handle: AppHandle,
+    saver: State<'_, DraftSaver>,
+) -> Result<(), String> {
+    let draft = Draft::new(BOARD_ID, draft);
+    let thread_id = draft.thread_id.clone();
+    let generation = saver.schedule(draft)?;
+    tauri::async_runtime::spawn(async move {
+        tokio::time::sleep(DRAFT_SAVE_DELAY).await;
+        let store = app_handle.state::<LogStore>();
+        let saver = app_handle.state::<DraftSaver>();
+        if let Err(e) = saver.save_if_current(&store, BOARD_ID, &thread_id, generation) {
+            eprintln!("[Rust save_draft] {} (スレッドID: {})", e, thread_id);
+        }
+    });
+    Ok(())
+}
+
+// スレッドを開いたときに下書きを戻す
+#[cfg(feature = "tauri")]
+#[tauri::command]
+pub fn get_draft(
+    thread_id: String,
+    saver: State<'_, DraftSaver>,
+    store: State<'_, LogStore>,
+) -> Result<Option<Draft>, String> {
+    saver.flush(&store)?;
+    store.load_draft(BOARD_ID, &thread_id)
+}
+
+#[cfg(feature = "tauri")]
+#[tauri::command]
+pub fn list_drafts(
+    saver: State<'_, DraftSaver>,
+    store: State<'_, LogStore>,
+) -> Result<Vec<Draft>, String> {
+    saver.flush(&store)?;
+    store.list_drafts()
+}
+
+#[cfg(feature = "tauri")]
+#[tauri::command]
+pub fn delete_draft(
+    thread_id: String,
+    saver: State<'_, DraftSaver>,
+    store: State<'_, LogStore>,
+) -> Result<(), String> {
+    saver.discard(&store, BOARD_ID, &thread_id)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::Path;
+
+    fn draft(thread_id: &str, body: &str) -> Draft {
+        Draft::new(
+            "test",
+            DraftInput {
+                thread_id: thread_id.to_string(),
+                name: "名無し".to_string(),
+                mail: "sage".to_string(),
+                body: body.to_string(),
+            },
+        )
+    }
+
+    fn stored_body(store: &LogStore, thread_id: &str) -> Option<String> {
+        store
+            .load_draft("test", thread_id)
+            .unwrap()
+            .map(|draft| draft.body)
+    }
+
+    // thread_id が一致する下書きだけ書き込みに失敗させる
+    fn fail_writes_for(store: &LogStore, thread_id: &str) {
+        store
+            .conn()
+            .unwrap()
+            .execute_batch(&format!(
+                "CREATE TRIGGER fail_draft BEFORE INSERT ON drafts
+                 WHEN NEW.thread_id = '{}' BEGIN SELECT RAISE(ABORT, 'disk full'); END;",
+                thread_id
+            ))
+            .unwrap();
+    }
+
+    #[test]
+    fn only_the_latest_scheduled_draft_is_saved() {
+        let store = LogStore::open(Path::new(":memory:")).unwrap();
+        let saver = DraftSaver::default();
+        let stale = saver.schedule(draft("1700000000", "書きか")).unwrap();
+        let current = saver.schedule(draft("1700000000", "書きかけ")).unwrap();
+
+        assert!(!saver
+            .save_if_current(&store, "test", "1700000000", stale)
+            .unwrap());
+        assert_eq!(stored_body(&store, "1700000000"), None);
+        assert!(saver
+            .save_if_current(&store, "test", "1700000000", current)
+            .unwrap());
+        assert_eq!(
+            stored_body(&store, "1700000000").as_deref(),
+            Some("書きかけ")
+        );
+        // 書いた後は預かっていないので、同じ世代でももう書かない
+        assert!(!saver
+            .save_if_current(&store, "test", "1700000000", current)
+            .unwrap());
+    }
+
+    #[test]
+    fn discard_wins_over_a_pending_save() {
+        let store = LogStore::open(Path::new(":memory:")).unwrap();
+        let saver = DraftSaver::default();
+        store
+            .write_draft(&draft("1700000000", "前の下書き"))
+            .unwrap();
+        let generation = saver.schedule(draft("1700000000", "送信した本文")).unwrap();
+
+        saver.discard(&store, "test", "1700000000").unwrap();
+        assert!(!saver
+            .save_if_current(&store, "test", "1700000000", generation)
+            .unwrap());
+        assert_eq!(stored_body(&store, "1700000000"), None);
+        assert_eq!(saver.flush(&store).unwrap(), 0);
+        assert_eq!(stored_body(&store, "1700000000"), None);
+    }
+
+    #[test]
+    fn flush_writes_everything_and_keeps_failed_drafts() {
+        let store = LogStore::open(Path::new(":memory:")).unwrap();
+        let saver = DraftSaver::default();
+        saver.schedule(draft("1700000000", "一つ目")).unwrap();
+        saver.schedule(draft("1700000001", "二つ目")).unwrap();
+        saver.schedule(draft("1700000002", "三つ目")).unwrap();
+        fail_writes_for(&store, "1700000001");
+
+        let error = saver.flush(&store).unwrap_err();
+        assert!(error.contains("1700000001"), "{}", error);
+        assert_eq!(stored_body(&store, "1700000000").as_deref(), Some("一つ目"));
+        assert_eq!(stored_body(&store, "1700000002").as_deref(), Some("三つ目"));
+        assert_eq!(stored_body(&store, "1700000001"), None);
+
+        // 書けなかった下書きは次の flush で書く
+        store
+            .conn()
+            .unwrap()
+            .execute_batch("DROP TRIGGER fail_draft;")
+            .unwrap();
+        assert_eq!(saver.flush(&store).unwrap(), 1);
+        assert_eq!(stored_body(&store, "1700000001").as_deref(), Some("二つ目"));
+        assert_eq!(saver.flush(&store).unwrap(), 0);
+    }
+
+    #[test]
+    fn failed_save_is_kept_for_flush() {
+        let store = LogStore::open(Path::new(":memory:")).unwrap();
+        let saver = DraftSaver::default();
+        let generation = saver.schedule(draft("1700000000", "本文")).unwrap();
+        fail_writes_for(&store, "1700000000");
+        assert!(saver
+            .save_if_current(&store, "test", "1700000000", generation)
+            .is_err());
+        store
+            .conn()
+            .unwrap()
+            .execute_batch("DROP TRIGGER fail_draft;")
+            .unwrap();
+        assert_eq!(saver.flush(&store).unwrap(), 1);
+        assert_eq!(stored_body(&store, "1700000000").as_deref(), Some("本文"));
+    }
+
+    #[test]
+    fn empty_body_deletes_the_draft() {
+        let store = LogStore::open(Path::new(":memory:")).unwrap();
+        let saver = DraftSaver::default();
+        store.write_draft(&draft("1700000000", "書きかけ")).unwrap();
+        let generation = saver.schedule(draft("1700000000", "  \n")).unwrap();
+        assert!(saver
+            .save_if_current(&store, "test", "1700000000", generation)
+            .unwrap());
+        assert_eq!(stored_body(&store, "1700000000"), None);
+        assert!(store.list_drafts().unwrap().is_empty());
+    }
+}
@@ -0,0 +1,522 @@
+use rusqlite::{params, Connection, OptionalExtension, Transaction};
+use serde::Serialize;
+#[cfg(feature = "tauri")]
+use tauri::{AppHandle, State};
+
+use super::log_store::LogStore;
+#[cfg(feature = "tauri")]
+use super::replies::notify_new_replies;
+#[cfg(feature = "tauri")]
+use crate::post::submit::PostSession;
+use crate::thread::board::{board_dat_url, BOARD_BASE_URL, BOARD_ID};
+use crate::thread::cache::ThreadCache;
+use crate::thread::dat::{decode_dat_bytes, parse_dat, parse_dat_title};
+use crate::thread::fetch::{fetch_dat_at, is_dat_dropped, DatUpdate};
+
+// お気に入りのフォルダ
+#[derive(Debug, Serialize, Clone)]
+pub struct FavoriteFolder {
+    id: i64,
+    name: String,
+    position: u32,
+}
+
+// お気に入りに登録したスレッド
+#[derive(Debug, Serialize, Clone)]
+pub struct Favorite {
+    board: String,
+    thread_id: String,
+    title: String,
+    folder_id: Option<i64>,    // フォルダに入っていない場合は None
+    position: u32,             // フォルダ内での並び順
+    response_count: u32,       // 最後に確認したときのレス数
+    new_response_count: u32,   // 最後に開いてから増えたレス数
+    unread_count: Option<u32>, // 未読レス数。一度も開いていないスレッドは None
+    is_dropped: bool,          // dat落ちして取得できなくなった
+    checked_at: Option<i64>,
+}
+
+#[derive(Debug, Serialize, Clone)]
+pub struct FavoriteList {
+    folders: Vec<FavoriteFolder>,
+    favorites: Vec<Favorite>,
+}
+
+// お気に入り更新チェックの結果 (1スレッド分)
+#[derive(Debug, Serialize, Clone)]
+pub struct FavoriteCheckResult {
+    thread_id: String,
+    title: String,
+    response_count: u32,
+    status: FavoriteCheckStatus,
+}
+
+#[derive(Debug, Serialize, Clone)]
+#[serde(rename_all = "snake_case", tag = "status")]
+pub enum FavoriteCheckStatus {
+    Updated { new_responses: u32 }, // 前回のチェックから増えたレス数
+    Unchanged,
+    Dropped,
+    Failed { message: String },
+}
+
+impl LogStore {
+    pub fn list_favorites(&self) -> Result<FavoriteList, String> {
+        let conn = self.conn()?;
+        let folders = {
+            let mut stmt = conn
+                .prepare("SELECT id, name, position FROM favorite_folders ORDER BY position, id")
+                .map_err(|e| e.to_string())?;
+            let rows = stmt
+                .query_map([], |row| {
+                    Ok(FavoriteFolder {
+                        id: row.get(0)?,
+                        name: row.get(1)?,
+                        position: row.get(2)?,
+                    })
+                })
+                .map_err(|e| e.to_string())?;
+            rows.collect::<Result<Vec<_>, _>>()
+                .map_err(|e| format!("お気に入りフォルダの読み込みに失敗しました: {}", e))?
+        };
+        let favorites = {
+            let mut stmt = conn
+                .prepare(
+                    "SELECT f.board, f.thread_id, f.title, f.folder_id, f.position, f.response_count,
+                            f.new_response_count, r.last_read, f.is_dropped, f.checked_at
+                     FROM favorites f
+                     LEFT JOIN read_state r ON r.board = f.board AND r.thread_id = f.thread_id
+                     ORDER BY f.folder_id IS NOT NULL, f.folder_id, f.position",
+                )
+                .map_err(|e| e.to_string())?;
+            let rows = stmt
+                .query_map([], |row| {
+                    let response_count: u32 = row.get(5)?;
+                    let last_read: Option<u32> = row.get(7)?;
+                    Ok(Favorite {
+                        board: row.get(0)?,
+                        thread_id: row.get(1)?,
+                        title: row.get(2)?,
+                        folder_id: row.get(3)?,
+                        position: row.get(4)?,
+                        response_count,
+                        new_response_count: row.get(6)?,
+                        unread_count: last_read.map(|read| response_count.saturating_sub(read)),
+                        is_dropped: row.get(8)?,
+                        checked_at: row.get(9)?,
+                    })
+                })
+                .map_err(|e| e.to_string())?;
+            rows.collect::<Result<Vec<_>, _>>()
+                .map_err(|e| format!("お気に入りの読み込みに失敗しました: {}", e))?
+        };
+        Ok(FavoriteList { folders, favorites })
+    }
+
+    // お気に入りに追加する (登録済みの場合はタイトルだけ更新する)
+    pub fn add_favorite(
+        &self,
+        board: &str,
+        thread_id: &str,
+        title: &str,
+        folder_id: Option<i64>,
+    ) -> Result<(), String> {
+        let conn = self.conn()?;
+        let position = next_favorite_position(&conn, folder_id)?;
+        let response_count: u32 = conn
+            .query_row(
+                "SELECT response_count FROM threads WHERE board = ?1 AND thread_id = ?2",
+                params![board, thread_id],
+                |row| row.get(0),
+            )
+            .optional()
+            .map_err(|e| e.to_string())?
+            .unwrap_or(0);
+        conn.execute(
+            "INSERT INTO favorites (board, thread_id, title, folder_id, position, response_count, added_at)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)
+             ON CONFLICT (board, thread_id) DO UPDATE SET title = excluded.title",
+            params![
+                board,
+                thread_id,
+                title,
+                folder_id,
+                position,
+                response_count,
+                chrono::Utc::now().timestamp()
+            ],
+        )
+        .map_err(|e| format!("お気に入りの追加に失敗しました: {}", e))?;
+        Ok(())
+    }
+
+    pub fn remove_favorite(&self, board: &str, thread_id: &str) -> Result<(), String> {
+        let conn = self.conn()?;
+        conn.execute(
+            "DELETE FROM favorites WHERE board = ?1 AND thread_id = ?2",
+            params![board, thread_id],
+        )
+        .map_err(|e| format!("お気に入りの削除に失敗しました: {}", e))?;
+        Ok(())
+    }
+
+    // お気に入りを別のフォルダ (または同じフォルダ内) の指定位置へ移動する
+    pub fn move_favorite(
+        &self,
+        board: &str,
+        thread_id: &str,
+        folder_id: Option<i64>,
+        position: usize,
+    ) -> Result<(), String> {
+        let mut conn = self.conn()?;
+        let tx = conn.transaction().map_err(|e| e.to_string())?;
+        let mut ordered = favorite_keys_in_folder(&tx, folder_id)?;
+        ordered.retain(|(b, t)| !(b == board && t == thread_id));
+        ordered.insert(
+            position.min(ordered.len()),
+            (board.to_string(), thread_id.to_string()),
+        );
+        tx.execute(
+            "UPDATE favorites SET folder_id = ?3 WHERE board = ?1 AND thread_id = ?2",
+            params![board, thread_id, folder_id],
+        )
+        .map_err(|e| format!("お気に入りの移動に失敗しました: {}", e))?;
+        for (index, (b, t)) in ordered.iter().enumerate() {
+            tx.execute(
+                "UPDATE favorites SET position = ?3 WHERE board = ?1 AND thread_id = ?2",
+                params![b, t, index as i64],
+            )
+            .map_err(|e| e.to_string())?;
+        }
+        tx.commit().map_err(|e| e.to_string())
+    }
+
+    pub fn create_favorite_folder(&self, name: &str) -> Result<FavoriteFolder, String> {
+        let conn = self.conn()?;
+        let position: u32 = conn
+            .query_row(
+                "SELECT COALESCE(MAX(position) + 1, 0) FROM favorite_folders",
+                [],
+                |row| row.get(0),
+            )
+            .map_err(|e| e.to_string())?;
+        conn.execute(
+            "INSERT INTO favorite_folders (name, position) VALUES (?1, ?2)",
+            params![name, position],
+        )
+        .map_err(|e| format!("フォルダの作成に失敗しました: {}", e))?;
+        Ok(FavoriteFolder {
+            id: conn.last_insert_rowid(),
+            name: name.to_string(),
+            position,
+        })
+    }
+
+    pub fn rename_favorite_folder(&self, folder_id: i64, name: &str) -> Result<(), String> {
+        let conn = self.conn()?;
+        conn.execute(
+            "UPDATE favorite_folders SET name = ?2 WHERE id = ?1",
+            params![folder_id, name],
+        )
+        .map_err(|e| format!("フォルダ名の変更に失敗しました: {}", e))?;
+        Ok(())
+    }
+
+    // フォルダを削除する。中のお気に入りはフォルダ外の末尾へ移す
+    pub fn delete_favorite_folder(&self, folder_id: i64) -> Result<(), String> {
+        let mut conn = self.conn()?;
+        let tx = conn.transaction().map_err(|e| e.to_string())?;
+        let base = next_favorite_position(&tx, None)?;
+        tx.execute(
+            "UPDATE favorites SET folder_id = NULL, position = position + ?2 WHERE folder_id = ?1",
+            params![folder_id, base],
+        )
+        .map_err(|e| e.to_string())?;
+        tx.execute(
+            "DELETE FROM favorite_folders WHERE id = ?1",
+            params![folder_id],
+        )
+        .map_err(|e| format!("フォルダの削除に失敗しました: {}", e))?;
+        tx.commit().map_err(|e| e.to_string())
+    }
+
+    pub fn move_favorite_folder(&self, folder_id: i64, position: usize) -> Result<(), String> {
+        let mut conn = self.conn()?;
+        let tx = conn.transaction().map_err(|e| e.to_string())?;
+        let mut ordered: Vec<i64> = {
+            let mut stmt = tx
+                .prepare("SELECT id FROM favorite_folders ORDER BY position, id")
+                .map_err(|e| e.to_string())?;
+            let rows = stmt
+                .query_map([], |row| row.get(0))
+                .map_err(|e| e.to_string())?;
+            rows.collect::<Result<Vec<_>, _>>()
+                .map_err(|e| e.to_string())?
+        };
+        ordered.retain(|id| *id != folder_id);
+        ordered.insert(position.min(ordered.len()), folder_id);
+        for (index, id) in ordered.iter().enumerate() {
+            tx.execute(
+                "UPDATE favorite_folders SET position = ?2 WHERE id = ?1",
+                params![id, index as i64],
+            )
+            .map_err(|e| format!("フォルダの移動に失敗しました: {}", e))?;
+        }
+        tx.commit().map_err(|e| e.to_string())
+    }
+
+    // 更新チェックの結果を記録する
+    fn record_favorite_check(
+        &self,
+        board: &str,
+        thread_id: &str,
+        title: Option<&str>,
+        response_count: Option<u32>,
+        is_dropped: bool,
+    ) -> Result<(), String> {
+        let conn = self.conn()?;
+        let last_seen: u32 = conn
+            .query_row(
+                "SELECT last_seen_count FROM read_state WHERE board = ?1 AND thread_id = ?2",
+                params![board, thread_id],
+                |row| row.get(0),
+            )
+            .optional()
+            .map_err(|e| e.to_string())?
+            .unwrap_or(0);
+        conn.execute(
+            "UPDATE favorites SET
+                title = COALESCE(?3, title),
+                response_count = COALESCE(?4, response_count),
+                new_response_count = MAX(COALESCE(?4, response_count) - ?5, 0),
+                is_dropped = ?6,
+                checked_at = ?7
+             WHERE board = ?1 AND thread_id = ?2",
+            params![
+                board,
+                thread_id,
+                title,
+                response_count,
+                last_seen,
+                is_dropped,
+                chrono::Utc::now().timestamp()
+            ],
+        )
+        .map_err(|e| format!("更新チェック結果の記録に失敗しました: {}", e))?;
+        Ok(())
+    }
+}
+
+fn next_favorite_position(conn: &Connection, folder_id: Option<i64>) -> Result<u32, String> {
+    conn.query_row(
+        "SELECT COALESCE(MAX(position) + 1, 0) FROM favorites WHERE folder_id IS ?1",
+        params![folder_id],
+        |row| row.get(0),
+    )
+    .map_err(|e| e.to_string())
+}
+
+fn favorite_keys_in_folder(
+    tx: &Transaction<'_>,
+    folder_id: Option<i64>,
+) -> Result<Vec<(String, String)>, String> {
+    let mut stmt = tx
+        .prepare("SELECT board, thread_id FROM favorites WHERE folder_id IS ?1 ORDER BY position")
+        .map_err(|e| e.to_string())?;
+    let rows = stmt
+        .query_map(params![folder_id], |row| Ok((row.get(0)?, row.get(1)?)))
+        .map_err(|e| e.to_string())?;
+    rows.collect::<Result<Vec<_>, _>>()
+        .map_err(|e| format!("お気に入りの読み込みに失敗しました: {}", e))
+}
+
+#[cfg(feature = "tauri")]
+#[tauri::command]
+pub async fn list_favorites(store: State<'_, LogStore>) -> Result<FavoriteList, String> {
+    store.list_favorites()
+}
+
+#[cfg(feature = "tauri")]
+#[tauri::command]
+pub async fn add_favorite(
+    thread_id: String,
+    title: String,
+    folder_id: Option<i64>,
+    store: State<'_, LogStore>,
+) -> Result<(), String> {
+    store.add_favorite(BOARD_ID, &thread_id, &title, folder_id)
+}
+
+#[cfg(feature = "tauri")]
+#[tauri::command]
+pub async fn remove_favorite(thread_id: String, store: State<'_, LogStore>) -> Result<(), String> {
+    store.remove_favorite(BOARD_ID, &thread_id)
+}
+
+#[cfg(feature = "tauri")]
+#[tauri::command]
+pub async fn move_favorite(
+    thread_id: String,
+    folder_id: Option<i64>,
+    position: usize,
+    store: State<'_, LogStore>,
+) -> Result<(), String> {
+    store.move_favorite(BOARD_ID, &thread_id, folder_id, position)
+}
+
+#[cfg(feature = "tauri")]
+#[tauri::command]
+pub async fn create_favorite_folder(
+    name: String,
+    store: State<'_, LogStore>,
+) -> Result<FavoriteFolder, String> {
+    if name.trim().is_empty() {
+        return Err("フォルダ名を入力してください。".to_string());
+    }
+    store.create_favorite_folder(name.trim())
+}
+
+#[cfg(feature = "tauri")]
+#[tauri::command]
+pub async fn rename_favorite_folder(
+    folder_id: i64,
+    name: String,
+    store: State<'_, LogStore>,
+) -> Result<(), String> {
+    if name.trim().is_empty() {
+        return Err("フォルダ名を入力してください。".to_string());
+    }
+    store.rename_favorite_folder(folder_id, name.trim())
+}
+
+#[cfg(feature = "tauri")]
+#[tauri::command]
+pub async fn delete_favorite_folder(
+    folder_id: i64,
+    store: State<'_, LogStore>,
+) -> Result<(), String> {
+    store.delete_favorite_folder(folder_id)
+}
+
+#[cfg(feature = "tauri")]
+#[tauri::command]
+pub async fn move_favorite_folder(
+    folder_id: i64,
+    position: usize,
+    store: State<'_, LogStore>,
+) -> Result<(), String> {
+    store.move_favorite_folder(folder_id, position)
+}
+
+// すべてのお気に入りの dat を (可能なら差分で) 取得し、新着レス数を調べる
+#[cfg(feature = "tauri")]
+#[tauri::command]
+pub async fn check_favorites(
+    app_handle: AppHandle,
+    session: State<'_, PostSession>,
+    cache: State<'_, ThreadCache>,
+    store: State<'_, LogStore>,
+) -> Result<Vec<FavoriteCheckResult>, String> {
+    let results = check_all_favorites(session.http(), &store, &cache).await?;
+    notify_new_replies(&app_handle, &store);
+    Ok(results)
+}
+
+// check_favorites の本体 (Tauri なしでも使える)
+pub async fn check_all_favorites(
+    http: &reqwest::Client,
+    store: &LogStore,
+    cache: &ThreadCache,
+) -> Result<Vec<FavoriteCheckResult>, String> {
+    let favorites = store.list_favorites()?.favorites;
+    eprintln!(
+        "[Rust check_favorites] {} 件のお気に入りの更新をチェックします。",
+        favorites.len()
+    );
+
+    let mut results = Vec::with_capacity(favorites.len());
+    for favorite in favorites {
+        // 1件の失敗で残りのチェックを止めないよう、失敗はそのお気に入りの結果に入れる
+        let (response_count, status) = match check_favorite(http, store, cache, &favorite).await {
+            Ok(checked) => checked,
+            Err(e) => {
+                eprintln!(
+                    "[Rust check_favorites] チェックに失敗しました (板: {}, スレッドID: {}): {}",
+                    favorite.board, favorite.thread_id, e
+                );
+                (
+                    favorite.response_count,
+                    FavoriteCheckStatus::Failed { message: e },
+                )
+            }
+        };
+        results.push(FavoriteCheckResult {
+            thread_id: favorite.thread_id,
+            title: favorite.title,
+            response_count,
+            status,
+        });
+    }
+    Ok(results)
+}
+
+// お気に入り1件の dat を、登録された板から取得して新着レス数を調べる
+async fn check_favorite(
+    http: &reqwest::Client,
+    store: &LogStore,
+    cache: &ThreadCache,
+    favorite: &Favorite,
+) -> Result<(u32, FavoriteCheckStatus), String> {
+    let previous = store.load_dat(&favorite.board, &favorite.thread_id)?;
+    let dat_file_url = board_dat_url(BOARD_BASE_URL, &favorite.board, &favorite.thread_id)?;
+    match fetch_dat_at(http, &dat_file_url, previous).await {
+        Ok(fetched) => {
+            let (content_str, _) = decode_dat_bytes(&fetched.bytes);
+            let mut responses = parse_dat(&content_str);
+            let response_count = responses.len() as u32;
+            let title = parse_dat_title(&content_str);
+            if fetched.update != DatUpdate::Unchanged {
+                // 新しいレスに自分の書き込みへの返信があれば受信箱に入れる
+                if let Err(e) =
+                    store.track_my_posts(&favorite.board, &favorite.thread_id, &mut responses)
+                {
+                    eprintln!(
+                        "[Rust check_favorites] 書き込み履歴との照合に失敗しました (スレッドID: {}): {}",
+                        favorite.thread_id, e
+                    );
+                }
+                // 既読情報 (取得レス数) は実際に開いたときだけ更新するので、ここでは保存のみ
+                store.save_thread(
+                    &favorite.board,
+                    &favorite.thread_id,
+                    title.as_deref().unwrap_or_default(),
+                    &fetched.bytes,
+                    &responses,
+                )?;
+                // キャッシュはアプリが開く板のスレッドだけを持つ
+                if favorite.board == BOARD_ID {
+                    cache.insert(&favorite.thread_id, responses);
+                }
+            }
+            store.record_favorite_check(
+                &favorite.board,
+                &favorite.thread_id,
+                title.as_deref(),
+                Some(response_count),
+                false,
+            )?;
+            let status = match fetched.update {
+                DatUpdate::Unchanged => FavoriteCheckStatus::Unchanged,
+                _ => FavoriteCheckStatus::Updated {
+                    new_responses: response_count.saturating_sub(favorite.response_count),
+                },
+            };
+            Ok((response_count, status))
+        }
+        Err(e) if is_dat_dropped(&e) => {
+            store.record_favorite_check(&favorite.board, &favorite.thread_id, None, None, true)?;
+            Ok((favorite.response_count, FavoriteCheckStatus::Dropped))
+        }
+        Err(e) => Err(e),
+    }
+}
@@ -0,0 +1,596 @@
+use regex::Regex;
+use rusqlite::{params, OptionalExtension};
+use serde::Serialize;
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
+#[cfg(feature = "tauri")]
+use tauri::State;
+
+use super::log_store::LogStore;
+use crate::thread::board::BOARD_ID;
+#[cfg(feature = "tauri")]
+use crate::thread::cache::ThreadCache;
+use crate::thread::dat::{decode_dat_bytes, parse_dat, parse_dat_title};
+
+// フォルダをたどる深さの上限 (Jane の Logs/カテゴリ/板 などを想定)
+const MAX_SCAN_DEPTH: usize = 6;
+
+// 板名ではなく、ログを入れておくためによく使われるフォルダ名
+const CONTAINER_FOLDERS: &[&str] = &["dat", "log", "logs", "kako"];
+
+// 取り込むログの形式
+#[derive(Debug, Serialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum ImportFormat {
+    PlainDat,  // .dat ファイルだけが置かれたフォルダ
+    JaneStyle, // 同じ名前の .idx と .dat の組
+    TwoChMate, // 2chMate のエクスポート (dat フォルダと、スレッドURLを並べたお気に入り)
+}
+
+// 取り込む (dry run では取り込める) スレッド
+#[derive(Debug, Serialize, Clone)]
+pub struct ImportedThread {
+    pub path: String,
+    pub format: ImportFormat,
+    pub board: String,
+    pub thread_id: String,
+    pub title: String,
+    pub response_count: usize,
+    pub encoding: String,       // "UTF-8" または "Shift_JIS"
+    pub last_read: Option<u32>, // .idx や 2chMate の一覧から読み取った既読位置
+    pub already_stored: bool,   // ログDBに同じスレッドがある (レスの多い方を残すか上書きする)
+}
+
+// 取り込むお気に入り
+#[derive(Debug, Serialize, Clone)]
+pub struct ImportedFavorite {
+    pub path: String,
+    pub board: String,
+    pub thread_id: String,
+    pub title: String,
+}
+
+// 取り込めなかったファイル
+#[derive(Debug, Serialize, Clone)]
+pub struct ImportSkip {
+    pub path: String,
+    pub reason: String,
+}
+
+#[derive(Debug, Serialize, Clone, Default)]
+pub struct ImportReport {
+    pub dry_run: bool,
+    pub threads: Vec<ImportedThread>,
+    pub favorites: Vec<ImportedFavorite>,
+    pub skipped: Vec<ImportSkip>,
+}
+
+// .idx から既読位置を読み取る
+// key=value 形式 (Jane 系) の場合は既読位置らしいキーを探し、
+// 1行1項目の形式 (ギコナビ系) の場合は 8行目 (ここまで読んだ) を使う
+fn parse_idx_last_read(idx_text: &str) -> Option<u32> {
+    let lines: Vec<&str> = idx_text.lines().map(str::trim).collect();
+    if lines.iter().any(|line| line.contains('=')) {
+        return lines.iter().find_map(|line| {
+            let (key, value) = line.split_once('=')?;
+            match key.trim().to_ascii_lowercase().as_str() {
+                "kokomade" | "lastread" | "readpos" | "readposition" | "lastreadno" => {
+                    value.trim().parse().ok()
+                }
+                _ => None,
+            }
+        });
+    }
+    lines
+        .get(7)
+        .and_then(|value| value.parse::<u32>().ok())
+        .filter(|&n| n > 0)
+}
+
+fn is_thread_key(stem: &str) -> bool {
+    stem.len() >= 9 && stem.chars().all(|c| c.is_ascii_digit())
+}
+
+fn collect_files(
+    dir: &Path,
+    depth: usize,
+    files: &mut Vec<PathBuf>,
+    skipped: &mut Vec<ImportSkip>,
+) {
+    let entries = match std::fs::read_dir(dir) {
+        Ok(entries) => entries,
+        Err(e) => {
+            skipped.push(ImportSkip {
+                path: dir.display().to_string(),
+                reason: format!("フォルダを読み込めません: {}", e),
+            });
+            return;
+        }
+    };
+    let mut paths: Vec<PathBuf> = entries.filter_map(|e| e.ok()).map(|e| e.path()).collect();
+    paths.sort();
+    for path in paths {
+        if path.is_dir() {
+            if depth < MAX_SCAN_DEPTH {
+                collect_files(&path, depth + 1, files, skipped);
+            }
+        } else {
+            files.push(path);
+        }
+    }
+}
+
+// パスのどこかに 2chMate のフォルダ名が含まれているか
+fn is_2chmate_path(path: &Path) -> bool {
+    path.components().any(|component| {
+        component
+            .as_os_str()
+            .to_string_lossy()
+            .to_ascii_lowercase()
+            .contains("2chmate")
+    })
+}
+
+// お気に入りの一覧か (履歴などほかの一覧は既読位置を読むためだけに使う)
+fn is_favorites_list(path: &Path) -> bool {
+    let stem = path
+        .file_stem()
+        .map(|s| s.to_string_lossy().to_lowercase())
+        .unwrap_or_default();
+    ["fav", "bookmark", "お気に入り"]
+        .iter()
+        .any(|name| stem.contains(name))
+}
+
+// 取り込み先の板 (指定がなければこの掲示板の板)
+fn target_board(board_override: Option<&str>) -> &str {
+    board_override
+        .map(str::trim)
+        .filter(|b| !b.is_empty())
+        .unwrap_or(BOARD_ID)
+}
+
+// dat の置かれたフォルダ名が板名 (英数字) であれば返す
+// 取り込むフォルダそのものや、dat/logs などの入れ物のフォルダは板名とみなさない
+fn folder_board(root: &Path, path: &Path) -> Option<String> {
+    let parent = path.parent().filter(|parent| *parent != root)?;
+    let name = parent.file_name()?.to_str()?;
+    let is_board_name = !name.is_empty()
+        && name
+            .chars()
+            .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '_');
+    (is_board_name && !CONTAINER_FOLDERS.contains(&name)).then(|| name.to_string())
+}
+
+// お気に入りや履歴の一覧の1行 (スレッドURLの後ろにタイトルや既読位置が並ぶ)
+struct ListedThread {
+    board: String,
+    thread_id: String,
+    title: String,
+    last_read: Option<u32>,
+}
+
+// URLの後ろの欄を読む。数字だけの欄は既読位置 (2chMate の一覧)、それ以外の最初の欄をタイトルとする
+fn parse_listed_thread(line: &str, caps: &regex::Captures) -> ListedThread {
+    let rest = line[caps.get(0).map_or(0, |m| m.end())..].trim_start_matches('/');
+    let separator = if rest.contains('\t') { '\t' } else { ',' };
+    let fields: Vec<&str> = rest
+        .split(separator)
+        .map(str::trim)
+        .filter(|field| !field.is_empty())
+        .collect();
+    let is_number = |field: &str| field.chars().all(|c| c.is_ascii_digit());
+    ListedThread {
+        board: caps[1].to_string(),
+        thread_id: caps[2].to_string(),
+        title: fields
+            .iter()
+            .find(|field| !is_number(field))
+            .map(|title| title.to_string())
+            .unwrap_or_default(),
+        last_read: fields
+            .iter()
+            .rev()
+            .find(|field| is_number(field))
+            .and_then(|n| n.parse().ok())
+            .filter(|&n| n > 0),
+    }
+}
+
+impl LogStore {
+    // 保存済みのスレッドのレス数 (保存されていなければ None)
+    fn stored_response_count(&self, board: &str, thread_id: &str) -> Result<Option<usize>, String> {
+        let conn = self.conn()?;
+        conn.query_row(
+            "SELECT response_count FROM threads WHERE board = ?1 AND thread_id = ?2",
+            params![board, thread_id],
+            |row| row.get::<_, i64>(0),
+        )
+        .optional()
+        .map(|count| count.map(|count| count as usize))
+        .map_err(|e| e.to_string())
+    }
+
+    // フォルダを調べて他のブラウザのログを取り込む。dry_run の場合は何が取り込まれるかだけを返す
+    // 取り込み先は board_override (なければこの掲示板の板) で、別の板のログはスキップする
+    // ログDBに同じスレッドがある場合は、overwrite でなければレスの多い方を残す
+    pub fn import_logs(
+        &self,
+        root: &Path,
+        board_override: Option<&str>,
+        overwrite: bool,
+        dry_run: bool,
+    ) -> Result<ImportReport, String> {
+        if !root.is_dir() {
+            return Err(format!("フォルダ '{}' が見つかりません。", root.display()));
+        }
+        let mut report = ImportReport {
+            dry_run,
+            ..Default::default()
+        };
+        let mut files = Vec::new();
+        collect_files(root, 0, &mut files, &mut report.skipped);
+
+        let read_cgi = Regex::new(r"https?://[^\s/]+/test/read\.cgi/([^/\s]+)/(\d+)")
+            .expect("正規表現が不正です");
+        let board = target_board(board_override);
+        let mut seen_favorites: HashSet<(String, String)> = HashSet::new();
+
+        // 先に一覧 (お気に入りや履歴) を読み、dat の既読位置と 2chMate の判定に使う
+        // お気に入りに加えるのは、お気に入りの一覧に載っているスレッドだけ
+        let mut listed: HashMap<String, Option<u32>> = HashMap::new();
+        for path in &files {
+            let path_str = path.display().to_string();
+            let extension = path
+                .extension()
+                .map(|e| e.to_string_lossy().to_ascii_lowercase())
+                .unwrap_or_default();
+            if !matches!(extension.as_str(), "txt" | "csv" | "tsv") {
+                continue;
+            }
+            let bytes = match std::fs::read(path) {
+                Ok(bytes) => bytes,
+                Err(e) => {
+                    report.skipped.push(ImportSkip {
+                        path: path_str,
+                        reason: format!("読み込めません: {}", e),
+                    });
+                    continue;
+                }
+            };
+            let (text, _) = decode_dat_bytes(&bytes);
+            let is_favorites = is_favorites_list(path);
+            let mut found_url = false;
+            for line in text.lines() {
+                let Some(caps) = read_cgi.captures(line) else {
+                    continue;
+                };
+                found_url = true;
+                let thread = parse_listed_thread(line, &caps);
+                if thread.board != board {
+                    report.skipped.push(ImportSkip {
+                        path: path_str.clone(),
+                        reason: format!(
+                            "別の板 ({}) のスレッドです: {}",
+                            thread.board,
+                            caps.get(0).map_or("", |m| m.as_str())
+                        ),
+                    });
+                    continue;
+                }
+                let last_read = listed.entry(thread.thread_id.clone()).or_default();
+                *last_read = (*last_read).max(thread.last_read);
+                if !is_favorites
+                    || !seen_favorites.insert((thread.board.clone(), thread.thread_id.clone()))
+                {
+                    continue;
+                }
+                if !dry_run {
+                    self.add_favorite(&thread.board, &thread.thread_id, &thread.title, None)?;
+                }
+                report.favorites.push(ImportedFavorite {
+                    path: path_str.clone(),
+                    board: thread.board,
+                    thread_id: thread.thread_id,
+                    title: thread.title,
+                });
+            }
+            if !found_url {
+                report.skipped.push(ImportSkip {
+                    path: path_str,
+                    reason: "スレッドのURLが見つかりません".to_string(),
+                });
+            }
+        }
+
+        for path in &files {
+            let path_str = path.display().to_string();
+            let extension = path
+                .extension()
+                .map(|e| e.to_string_lossy().to_ascii_lowercase())
+                .unwrap_or_default();
+            let stem = path
+                .file_stem()
+                .map(|s| s.to_string_lossy().into_owned())
+                .unwrap_or_default();
+
+            match extension.as_str() {
+                "dat" => {
+                    if let Some(folder) = folder_board(root, path).filter(|f| f != board) {
+                        report.skipped.push(ImportSkip {
+                            path: path_str,
+                            reason: format!(
+                                "別の板 ({}) のログです (取り込み先: {})",
+                                folder, board
+                            ),
+                        });
+                        continue;
+                    }
+                    if !is_thread_key(&stem) {
+                        report.skipped.push(ImportSkip {
+                            path: path_str,
+                            reason: "ファイル名がスレッドキーではありません".to_string(),
+                        });
+                        continue;
+                    }
+                    let bytes = match std::fs::read(path) {
+                        Ok(bytes) => bytes,
+                        Err(e) => {
+                            report.skipped.push(ImportSkip {
+                                path: path_str,
+                                reason: format!("読み込めません: {}", e),
+                            });
+                            continue;
+                        }
+                    };
+                    let (text, encoding) = decode_dat_bytes(&bytes);
+                    let responses = parse_dat(&text);
+                    if responses.is_empty() {
+                        report.skipped.push(ImportSkip {
+                            path: path_str,
+                            reason: "dat として読み取れるレスがありません".to_string(),
+                        });
+                        continue;
+                    }
+
+                    let idx_path = path.with_extension("idx");
+                    let (format, last_read) = if idx_path.is_file() {
+                        let idx_bytes = std::fs::read(&idx_path).unwrap_or_default();
+                        let (idx_text, _) = decode_dat_bytes(&idx_bytes);
+                        (ImportFormat::JaneStyle, parse_idx_last_read(&idx_text))
+                    } else if let Some(&last_read) = listed.get(&stem) {
+                        // 2chMate のエクスポートは dat と、既読位置つきのスレッドの一覧の組
+                        (ImportFormat::TwoChMate, last_read)
+                    } else if is_2chmate_path(path) {
+                        (ImportFormat::TwoChMate, None)
+                    } else {
+                        (ImportFormat::PlainDat, None)
+                    };
+                    let title = parse_dat_title(&text).unwrap_or_default();
+                    let stored_count = self.stored_response_count(board, &stem)?;
+                    if let Some(stored_count) =
+                        stored_count.filter(|&count| !overwrite && count >= responses.len())
+                    {
+                        report.skipped.push(ImportSkip {
+                            path: path_str,
+                            reason: format!(
+                                "ログDBに同じスレッドがあります ({}レス、取り込むログは{}レス)",
+                                stored_count,
+                                responses.len()
+                            ),
+                        });
+                        continue;
+                    }
+
+                    if !dry_run {
+                        // 差分取得の Range はサーバーの dat のバイト数で数えるので、元のエンコーディングのまま保存する
+                        self.save_thread(board, &stem, &title, &bytes, &responses)?;
+                        self.record_seen(board, &stem, responses.len() as u32)?;
+                        if let Some(last_read) = last_read {
+                            self.mark_read(board, &stem, last_read.min(responses.len() as u32))?;
+                        }
+                    }
+                    report.threads.push(ImportedThread {
+                        path: path_str,
+                        format,
+                        board: board.to_string(),
+                        thread_id: stem,
+                        title,
+                        response_count: responses.len(),
+                        encoding: encoding.to_string(),
+                        last_read,
+                        already_stored: stored_count.is_some(),
+                    });
+                }
+                // お気に入りや履歴の一覧は上で読む
+                "txt" | "csv" | "tsv" => {}
+                // .dat と組になっている .idx は上で読む
+                "idx" if path.with_extension("dat").is_file() => {}
+                _ => report.skipped.push(ImportSkip {
+                    path: path_str,
+                    reason: "対応していないファイルです".to_string(),
+                }),
+            }
+        }
+        Ok(report)
+    }
+}
+
+#[cfg(feature = "tauri")]
+#[tauri::command]
+pub async fn import_logs(
+    path: String,
+    board: Option<String>,
+    overwrite: bool,
+    dry_run: bool,
+    cache: State<'_, ThreadCache>,
+    store: State<'_, LogStore>,
+) -> Result<ImportReport, String> {
+    eprintln!(
+        "[Rust import_logs] ログを取り込みます (上書き: {}, dry run: {}): {}",
+        overwrite, dry_run, path
+    );
+    let report = store.import_logs(Path::new(&path), board.as_deref(), overwrite, dry_run)?;
+    if !dry_run {
+        for thread in &report.threads {
+            cache.invalidate(&thread.thread_id);
+        }
+    }
+    eprintln!(
+        "[Rust import_logs] スレッド {} 件、お気に入り {} 件、スキップ {} 件",
+        report.threads.len(),
+        report.favorites.len(),
+        report.skipped.len()
+    );
+    Ok(report)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const DAT: &str = "名無し<><>2024/01/01 ID:a<> 1 <>スレ\n名無し<><>2024/01/01 ID:b<> 2 <>\n";
+
+    fn temp_root(name: &str) -> PathBuf {
+        let root = std::env::temp_dir().join(format!("import-{}-{}", name, std::process::id()));
+        let _ = std::fs::remove_dir_all(&root);
+        std::fs::create_dir_all(&root).unwrap();
+        root
+    }
+
+    fn skip_reasons(report: &ImportReport) -> Vec<String> {
+        report.skipped.iter().map(|s| s.reason.clone()).collect()
+    }
+
+    #[test]
+    fn listed_thread_reads_title_and_read_position() {
+        let read_cgi = Regex::new(r"https?://[^\s/]+/test/read\.cgi/([^/\s]+)/(\d+)").unwrap();
+        let line = "https://x.5ch.net/test/read.cgi/vip/1600000000/\tスレ\t120";
+        let thread = parse_listed_thread(line, &read_cgi.captures(line).unwrap());
+        assert_eq!(thread.board, "vip");
+        assert_eq!(thread.thread_id, "1600000000");
+        assert_eq!(thread.title, "スレ");
+        assert_eq!(thread.last_read, Some(120));
+    }
+
+    #[test]
+    fn other_boards_and_unreadable_lists_are_skipped() {
+        let root = temp_root("boards");
+        std::fs::create_dir_all(root.join("Logs/vip")).unwrap();
+        std::fs::create_dir_all(root.join(format!("Logs/{}", BOARD_ID))).unwrap();
+        std::fs::write(root.join("Logs/vip/1600000000.dat"), DAT).unwrap();
+        std::fs::write(root.join(format!("Logs/{}/1700000000.dat", BOARD_ID)), DAT).unwrap();
+        std::fs::write(
+            root.join("favorites.txt"),
+            "https://x.5ch.net/test/read.cgi/vip/1600000000/\tスレ\n",
+        )
+        .unwrap();
+        std::fs::write(root.join("memo.txt"), "URLのないメモ\n").unwrap();
+
+        let store = LogStore::open(Path::new(":memory:")).unwrap();
+        let report = store.import_logs(&root, None, false, true).unwrap();
+        assert_eq!(report.threads.len(), 1);
+        assert_eq!(report.threads[0].board, BOARD_ID);
+        assert!(report.favorites.is_empty());
+        let reasons = skip_reasons(&report);
+        assert!(reasons
+            .iter()
+            .any(|r| r.starts_with("別の板 (vip) のログです")));
+        assert!(reasons
+            .iter()
+            .any(|r| r.starts_with("別の板 (vip) のスレッドです")));
+        assert!(reasons.iter().any(|r| r == "スレッドのURLが見つかりません"));
+        let _ = std::fs::remove_dir_all(&root);
+    }
+
+    #[test]
+    fn longer_stored_log_is_kept_unless_overwriting() {
+        let root = temp_root("overwrite");
+        let mate = root.join("2chMate/dat");
+        std::fs::create_dir_all(&mate).unwrap();
+        std::fs::write(mate.join("1700000000.dat"), DAT).unwrap();
+        std::fs::write(
+            root.join("2chMate/history.txt"),
+            format!(
+                "https://x.5ch.net/test/read.cgi/{}/1700000000/\tスレ\t1\n",
+                BOARD_ID
+            ),
+        )
+        .unwrap();
+
+        let store = LogStore::open(Path::new(":memory:")).unwrap();
+        let longer = format!("{}名無し<><>2024/01/01 ID:c<> 3 <>\n", DAT);
+        store
+            .save_thread(
+                BOARD_ID,
+                "1700000000",
+                "スレ",
+                longer.as_bytes(),
+                &parse_dat(&longer),
+            )
+            .unwrap();
+
+        let report = store.import_logs(&root, None, false, false).unwrap();
+        assert!(report.threads.is_empty());
+        assert!(skip_reasons(&report)
+            .iter()
+            .any(|r| r.starts_with("ログDBに同じスレッドがあります")));
+        assert_eq!(
+            store.load_dat(BOARD_ID, "1700000000").unwrap().unwrap(),
+            longer.as_bytes()
+        );
+
+        let report = store.import_logs(&root, None, true, false).unwrap();
+        assert_eq!(report.threads.len(), 1);
+        assert_eq!(report.threads[0].format, ImportFormat::TwoChMate);
+        assert_eq!(report.threads[0].last_read, Some(1));
+        assert!(report.threads[0].already_stored);
+        assert_eq!(
+            store.load_dat(BOARD_ID, "1700000000").unwrap().unwrap(),
+            DAT.as_bytes()
+        );
+        let _ = std::fs::remove_dir_all(&root);
+    }
+
+    #[test]
+    fn only_favorites_list_adds_favorites_and_dat_keeps_its_encoding() {
+        let root = temp_root("mate");
+        let mate = root.join("2chMate/dat");
+        std::fs::create_dir_all(&mate).unwrap();
+        let (sjis, _, _) = encoding_rs::SHIFT_JIS.encode(DAT);
+        std::fs::write(mate.join("1700000000.dat"), &sjis).unwrap();
+        std::fs::write(mate.join("1700000001.dat"), DAT).unwrap();
+        let url = |id: &str| format!("https://x.5ch.net/test/read.cgi/{}/{}/", BOARD_ID, id);
+        std::fs::write(
+            root.join("2chMate/favorites.txt"),
+            format!("{}\tスレ\t2\n", url("1700000000")),
+        )
+        .unwrap();
+        std::fs::write(
+            root.join("2chMate/history.txt"),
+            format!("{}\t別のスレ\t1\n", url("1700000001")),
+        )
+        .unwrap();
+
+        let store = LogStore::open(Path::new(":memory:")).unwrap();
+        let report = store.import_logs(&root, None, false, false).unwrap();
+        let favorites: Vec<&str> = report
+            .favorites
+            .iter()
+            .map(|f| f.thread_id.as_str())
+            .collect();
+        assert_eq!(favorites, ["1700000000"]);
+        assert_eq!(report.threads.len(), 2);
+        let sjis_thread = &report.threads[0];
+        assert_eq!(sjis_thread.encoding, "Shift_JIS");
+        assert_eq!(sjis_thread.title, "スレ");
+        assert_eq!(sjis_thread.last_read, Some(2));
+        // 履歴の一覧は既読位置にだけ使う
+        assert_eq!(report.threads[1].last_read, Some(1));
+        assert_eq!(
+            store.load_dat(BOARD_ID, "1700000000").unwrap().unwrap(),
+            sjis.as_ref()
+        );
+        let _ = std::fs::remove_dir_all(&root);
+    }
+}
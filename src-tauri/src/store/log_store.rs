@@ -0,0 +1,375 @@
+use rusqlite::{params, Connection, OptionalExtension};
+use std::path::Path;
+use std::sync::{Mutex, MutexGuard};
+
+use super::search::{ensure_search_index, index_responses};
+use crate::settings::get_app_config_dir;
+use crate::thread::dat::{count_id_occurrences, ResponseItem};
+
+const LOG_DB_FILENAME: &str = "logs.sqlite3";
+
+// スキーマのマイグレーション (PRAGMA user_version がそのまま適用済みの数になる)
+// 既存の要素は書き換えず、変更は必ず末尾に追加すること
+const MIGRATIONS: &[&str] = &[
+    // v1: dat の生データとパース済みレス
+    "CREATE TABLE threads (
+        board TEXT NOT NULL,
+        thread_id TEXT NOT NULL,
+        title TEXT NOT NULL DEFAULT '',
+        dat BLOB NOT NULL,
+        response_count INTEGER NOT NULL,
+        fetched_at INTEGER NOT NULL,
+        PRIMARY KEY (board, thread_id)
+    );
+    CREATE TABLE responses (
+        board TEXT NOT NULL,
+        thread_id TEXT NOT NULL,
+        number INTEGER NOT NULL,
+        author TEXT NOT NULL,
+        mail TEXT NOT NULL,
+        created_at TEXT NOT NULL,
+        user_id_info TEXT NOT NULL,
+        parsed_user_id TEXT,
+        content TEXT NOT NULL,
+        PRIMARY KEY (board, thread_id, number)
+    );",
+    // v2: 既読位置と、前回取得したスレッド一覧
+    "CREATE TABLE read_state (
+        board TEXT NOT NULL,
+        thread_id TEXT NOT NULL,
+        last_read INTEGER NOT NULL DEFAULT 0,
+        last_seen_count INTEGER NOT NULL DEFAULT 0,
+        last_read_at INTEGER,
+        PRIMARY KEY (board, thread_id)
+    );
+    CREATE TABLE subject_snapshot (
+        board TEXT NOT NULL,
+        thread_id TEXT NOT NULL,
+        title TEXT NOT NULL,
+        response_count INTEGER NOT NULL,
+        PRIMARY KEY (board, thread_id)
+    );",
+    // v3: お気に入りとフォルダ (folder_id が NULL のものはフォルダに入っていない)
+    "CREATE TABLE favorite_folders (
+        id INTEGER PRIMARY KEY AUTOINCREMENT,
+        name TEXT NOT NULL,
+        position INTEGER NOT NULL
+    );
+    CREATE TABLE favorites (
+        board TEXT NOT NULL,
+        thread_id TEXT NOT NULL,
+        title TEXT NOT NULL,
+        folder_id INTEGER,
+        position INTEGER NOT NULL,
+        response_count INTEGER NOT NULL DEFAULT 0,
+        new_response_count INTEGER NOT NULL DEFAULT 0,
+        is_dropped INTEGER NOT NULL DEFAULT 0,
+        checked_at INTEGER,
+        added_at INTEGER NOT NULL,
+        PRIMARY KEY (board, thread_id)
+    );",
+    // v4: NGルール (scope_board / scope_thread が NULL なら全体に適用)
+    "CREATE TABLE ng_rules (
+        id INTEGER PRIMARY KEY AUTOINCREMENT,
+        target TEXT NOT NULL,
+        pattern TEXT NOT NULL,
+        scope_board TEXT,
+        scope_thread TEXT,
+        expires_at INTEGER,
+        created_at INTEGER NOT NULL
+    );",
+    // v5: NG全体の設定 (1行のみ)
+    "CREATE TABLE ng_options (
+        id INTEGER PRIMARY KEY CHECK (id = 1),
+        chain_enabled INTEGER NOT NULL DEFAULT 0,
+        chain_depth INTEGER NOT NULL DEFAULT 3
+    );",
+    // v6: スレタイNGとピン留めしたスレッド (板ごと)
+    "CREATE TABLE title_ng_rules (
+        id INTEGER PRIMARY KEY AUTOINCREMENT,
+        board TEXT NOT NULL,
+        pattern TEXT NOT NULL,
+        is_regex INTEGER NOT NULL DEFAULT 0,
+        created_at INTEGER NOT NULL
+    );
+    CREATE TABLE pinned_threads (
+        board TEXT NOT NULL,
+        thread_id TEXT NOT NULL,
+        pinned_at INTEGER NOT NULL,
+        PRIMARY KEY (board, thread_id)
+    );",
+    // v7: NGルールごとの文字列の正規化 (NormalizeOptions のビット列)
+    "ALTER TABLE ng_rules ADD COLUMN folds INTEGER NOT NULL DEFAULT 0;
+    ALTER TABLE title_ng_rules ADD COLUMN folds INTEGER NOT NULL DEFAULT 0;",
+    // v8: 全文検索の索引 (本文・名前・IDを正規化して bigram に分けたもの)
+    "CREATE VIRTUAL TABLE response_search USING fts5(
+        board UNINDEXED,
+        thread_id UNINDEXED,
+        number UNINDEXED,
+        body,
+        name,
+        user_id
+    );",
+    // v9: 自分の書き込みの履歴 (number はレスと照合できたらそのレス番号、まだなら NULL)
+    "CREATE TABLE my_posts (
+        id INTEGER PRIMARY KEY AUTOINCREMENT,
+        board TEXT NOT NULL,
+        thread_id TEXT NOT NULL,
+        name TEXT NOT NULL,
+        mail TEXT NOT NULL,
+        body TEXT NOT NULL,
+        posted_at INTEGER NOT NULL,
+        number INTEGER
+    );
+    CREATE INDEX my_posts_thread ON my_posts (board, thread_id);",
+    // v10: 自分の書き込みへの返信の受信箱 (1つのレスにつき1件。my_number は最初にアンカーされた自分のレス)
+    "CREATE TABLE reply_inbox (
+        id INTEGER PRIMARY KEY AUTOINCREMENT,
+        board TEXT NOT NULL,
+        thread_id TEXT NOT NULL,
+        number INTEGER NOT NULL,
+        my_number INTEGER NOT NULL,
+        author TEXT NOT NULL,
+        content TEXT NOT NULL,
+        created_at TEXT NOT NULL,
+        detected_at INTEGER NOT NULL,
+        is_notified INTEGER NOT NULL DEFAULT 0,
+        is_read INTEGER NOT NULL DEFAULT 0,
+        UNIQUE (board, thread_id, number)
+    );",
+    // v11: スレッドごとの書きかけのレス
+    "CREATE TABLE drafts (
+        board TEXT NOT NULL,
+        thread_id TEXT NOT NULL,
+        name TEXT NOT NULL,
+        mail TEXT NOT NULL,
+        body TEXT NOT NULL,
+        updated_at INTEGER NOT NULL,
+        PRIMARY KEY (board, thread_id)
+    );",
+    // v12: 画像のアップロード履歴 (後から削除できるよう delete_hash を残す)
+    "CREATE TABLE image_uploads (
+        id INTEGER PRIMARY KEY AUTOINCREMENT,
+        api_url TEXT NOT NULL,
+        url TEXT NOT NULL,
+        delete_hash TEXT NOT NULL,
+        file_name TEXT NOT NULL,
+        width INTEGER NOT NULL,
+        height INTEGER NOT NULL,
+        size INTEGER NOT NULL,
+        uploaded_at INTEGER NOT NULL
+    );",
+];
+
+// ローカルのログ保存 (SQLite)。Tauri の State として管理する
+pub struct LogStore {
+    conn: Mutex<Connection>,
+}
+
+impl LogStore {
+    // 設定ディレクトリの logs.sqlite3 を開く
+    pub fn open_default() -> Result<Self, String> {
+        let path = get_app_config_dir()?.join(LOG_DB_FILENAME);
+        Self::open(&path)
+    }
+
+    pub fn open(path: &Path) -> Result<Self, String> {
+        eprintln!("[Rust LogStore] ログDBを開きます: '{}'", path.display());
+        let mut conn = Connection::open(path)
+            .map_err(|e| format!("ログDB '{}' を開けませんでした: {}", path.display(), e))?;
+        let migrated_from = migrate(&mut conn)?;
+        ensure_search_index(&mut conn, migrated_from)?;
+        Ok(Self {
+            conn: Mutex::new(conn),
+        })
+    }
+
+    pub(crate) fn conn(&self) -> Result<MutexGuard<'_, Connection>, String> {
+        self.conn
+            .lock()
+            .map_err(|e| format!("ログDBのロックに失敗しました: {}", e))
+    }
+
+    // dat の生データとパース済みレスをまとめて保存する (既存のものは置き換え)
+    pub fn save_thread(
+        &self,
+        board: &str,
+        thread_id: &str,
+        title: &str,
+        dat: &[u8],
+        responses: &[ResponseItem],
+    ) -> Result<(), String> {
+        let mut conn = self.conn()?;
+        let tx = conn.transaction().map_err(|e| e.to_string())?;
+        tx.execute(
+            "INSERT INTO threads (board, thread_id, title, dat, response_count, fetched_at)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6)
+             ON CONFLICT (board, thread_id) DO UPDATE SET
+                title = CASE WHEN excluded.title = '' THEN threads.title ELSE excluded.title END,
+                dat = excluded.dat,
+                response_count = excluded.response_count,
+                fetched_at = excluded.fetched_at",
+            params![
+                board,
+                thread_id,
+                title,
+                dat,
+                responses.len() as i64,
+                chrono::Utc::now().timestamp()
+            ],
+        )
+        .map_err(|e| format!("スレッドの保存に失敗しました: {}", e))?;
+        tx.execute(
+            "DELETE FROM responses WHERE board = ?1 AND thread_id = ?2",
+            params![board, thread_id],
+        )
+        .map_err(|e| e.to_string())?;
+        {
+            let mut stmt = tx
+                .prepare(
+                    "INSERT INTO responses
+                        (board, thread_id, number, author, mail, created_at, user_id_info, parsed_user_id, content)
+                     VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9)",
+                )
+                .map_err(|e| e.to_string())?;
+            for (index, response) in responses.iter().enumerate() {
+                stmt.execute(params![
+                    board,
+                    thread_id,
+                    (index + 1) as i64,
+                    response.author,
+                    response.mail,
+                    response.created_at,
+                    response.user_id_info,
+                    response.parsed_user_id,
+                    response.content,
+                ])
+                .map_err(|e| format!("レスの保存に失敗しました: {}", e))?;
+            }
+        }
+        index_responses(&tx, board, thread_id, responses)?;
+        tx.commit().map_err(|e| e.to_string())
+    }
+
+    // 保存済みの dat の生データ
+    pub fn load_dat(&self, board: &str, thread_id: &str) -> Result<Option<Vec<u8>>, String> {
+        let conn = self.conn()?;
+        conn.query_row(
+            "SELECT dat FROM threads WHERE board = ?1 AND thread_id = ?2",
+            params![board, thread_id],
+            |row| row.get(0),
+        )
+        .optional()
+        .map_err(|e| format!("スレッドの読み込みに失敗しました: {}", e))
+    }
+
+    // 保存済みのパース済みレスを返す (オフライン表示用)。スレッドが保存されていなければ None
+    pub fn load_responses(
+        &self,
+        board: &str,
+        thread_id: &str,
+    ) -> Result<Option<Vec<ResponseItem>>, String> {
+        let conn = self.conn()?;
+        let stored = conn
+            .query_row(
+                "SELECT 1 FROM threads WHERE board = ?1 AND thread_id = ?2",
+                params![board, thread_id],
+                |_| Ok(()),
+            )
+            .optional()
+            .map_err(|e| format!("スレッドの読み込みに失敗しました: {}", e))?;
+        if stored.is_none() {
+            return Ok(None);
+        }
+        let mut stmt = conn
+            .prepare(
+                "SELECT number, author, mail, created_at, user_id_info, parsed_user_id, content
+                 FROM responses WHERE board = ?1 AND thread_id = ?2 ORDER BY number",
+            )
+            .map_err(|e| e.to_string())?;
+        let rows = stmt
+            .query_map(params![board, thread_id], |row| {
+                Ok(ResponseItem {
+                    id: row.get::<_, i64>(0)?.to_string(),
+                    author: row.get(1)?,
+                    mail: row.get(2)?,
+                    created_at: row.get(3)?,
+                    user_id_info: row.get(4)?,
+                    parsed_user_id: row.get(5)?,
+                    content: row.get(6)?,
+                    id_occurrence_count: 0,
+                    id_total_count: 0,
+                    ng: None,
+                    is_mine: false,
+                })
+            })
+            .map_err(|e| e.to_string())?;
+        let mut responses = rows
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(|e| format!("レスの読み込みに失敗しました: {}", e))?;
+        count_id_occurrences(&mut responses);
+        Ok(Some(responses))
+    }
+}
+
+// 未適用のマイグレーションを適用し、適用前のスキーマのバージョンを返す
+fn migrate(conn: &mut Connection) -> Result<usize, String> {
+    let current: usize = conn
+        .query_row("PRAGMA user_version", [], |row| row.get::<_, i64>(0))
+        .map_err(|e| format!("スキーマバージョンの取得に失敗しました: {}", e))?
+        as usize;
+    if current > MIGRATIONS.len() {
+        return Err(format!(
+            "ログDBのスキーマ (v{}) がこのバージョンのアプリ (v{}) より新しいため開けません。",
+            current,
+            MIGRATIONS.len()
+        ));
+    }
+    for (index, sql) in MIGRATIONS.iter().enumerate().skip(current) {
+        let version = index + 1;
+        eprintln!("[Rust LogStore] スキーマを v{} に更新します。", version);
+        let tx = conn.transaction().map_err(|e| e.to_string())?;
+        tx.execute_batch(sql)
+            .map_err(|e| format!("スキーマ v{} への更新に失敗しました: {}", version, e))?;
+        tx.pragma_update(None, "user_version", version as i64)
+            .map_err(|e| e.to_string())?;
+        tx.commit().map_err(|e| e.to_string())?;
+    }
+    Ok(current)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::thread::dat::parse_dat;
+
+    #[test]
+    fn load_responses_reads_saved_rows() {
+        let store = LogStore::open(Path::new(":memory:")).unwrap();
+        assert!(store
+            .load_responses("board", "1000000001")
+            .unwrap()
+            .is_none());
+        let dat = "a<><>2024/05/01(水) 00:00:00.00 ID:abc<> 1 <>スレ\n\
+                   b<>sage<>2024/05/01(水) 00:00:01.00 ID:xyz<> 2 <>\n\
+                   c<><>2024/05/01(水) 00:00:02.00 ID:abc<> 3 <>\n";
+        let parsed = parse_dat(dat);
+        store
+            .save_thread("board", "1000000001", "スレ", dat.as_bytes(), &parsed)
+            .unwrap();
+        let loaded = store
+            .load_responses("board", "1000000001")
+            .unwrap()
+            .unwrap();
+        assert_eq!(loaded.len(), parsed.len());
+        for (loaded, parsed) in loaded.iter().zip(&parsed) {
+            assert_eq!(loaded.id, parsed.id);
+            assert_eq!(loaded.author, parsed.author);
+            assert_eq!(loaded.mail, parsed.mail);
+            assert_eq!(loaded.content, parsed.content);
+            assert_eq!(loaded.parsed_user_id, parsed.parsed_user_id);
+            assert_eq!(loaded.id_occurrence_count, parsed.id_occurrence_count);
+            assert_eq!(loaded.id_total_count, parsed.id_total_count);
+        }
+    }
+}
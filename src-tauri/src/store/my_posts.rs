@@ -0,0 +1,366 @@
+use rusqlite::params;
+use serde::Serialize;
+use std::collections::HashSet;
+#[cfg(feature = "tauri")]
+use tauri::State;
+
+use super::log_store::LogStore;
+use super::replies::has_replies;
+use crate::ng::filter::load_ng_filter;
+use crate::thread::body::plain_text;
+use crate::thread::dat::{parse_response_timestamp, ResponseItem};
+
+// 送信を始めた時刻からこの範囲の日付のレスだけを照合する
+// サーバーはそれより後の日付を付けるので、前には時計のずれの分だけ広げる
+// (直前に書かれた別の人の同じ本文のレスを自分の書き込みとみなさないよう、狭くしておく)
+const MATCH_BEFORE_SECS: i64 = 30;
+const MATCH_AFTER_SECS: i64 = 600;
+
+// 書き込み履歴の1件
+#[derive(Debug, Serialize, Clone)]
+pub struct MyPost {
+    pub id: i64,
+    pub board: String,
+    pub thread_id: String,
+    pub thread_title: String, // ログDBにスレッドが無ければ空
+    pub name: String,
+    pub mail: String,
+    pub body: String,
+    pub posted_at: i64,      // 送信を始めた時刻
+    pub number: Option<u32>, // レスと照合できたらそのレス番号
+}
+
+// まだレスと照合できていない書き込み
+struct PendingPost {
+    id: i64,
+    body: String, // normalize_body 済み
+    posted_at: i64,
+}
+
+// 1つのスレッドの自分の書き込み。レスに is_mine を付けながら、未照合の書き込みをレスと照合する
+// (受信しながら少しずつ届くレスにも使えるよう、NgFilter と同じく apply を何度も呼べる)
+pub struct MyPostMarker {
+    numbers: HashSet<u32>,
+    pending: Vec<PendingPost>,
+    matched: Vec<(i64, u32)>, // 今回新しく照合できた (書き込みのID, レス番号)
+}
+
+// 書き込んだ本文とレスの本文を比べるための形にする
+// サーバーは行末の空白や前後の空行を落とすことがあるので、それに合わせる
+fn normalize_body(body: &str) -> String {
+    body.replace("\r\n", "\n")
+        .lines()
+        .map(str::trim_end)
+        .collect::<Vec<_>>()
+        .join("\n")
+        .trim()
+        .to_string()
+}
+
+impl MyPostMarker {
+    pub fn apply(&mut self, responses: &mut [ResponseItem]) {
+        for response in responses.iter_mut() {
+            let Ok(number) = response.id.parse::<u32>() else {
+                continue;
+            };
+            if !self.numbers.contains(&number) {
+                if let Some(index) = self.find_pending(response) {
+                    let post = self.pending.remove(index);
+                    self.matched.push((post.id, number));
+                    self.numbers.insert(number);
+                }
+            }
+            response.is_mine = self.numbers.contains(&number);
+        }
+    }
+
+    // 本文が同じで、日付が書き込んだ時刻に近い書き込みを探す (古い書き込みから順に)
+    // 日付を読み取れないレスは、時刻で確かめられないので照合しない
+    fn find_pending(&self, response: &ResponseItem) -> Option<usize> {
+        if self.pending.is_empty() {
+            return None;
+        }
+        let body = normalize_body(&plain_text(&response.content));
+        let timestamp = parse_response_timestamp(&response.created_at);
+        self.pending.iter().position(|post| {
+            post.body == body
+                && timestamp.is_some_and(|t| {
+                    t >= post.posted_at - MATCH_BEFORE_SECS
+                        && t <= post.posted_at + MATCH_AFTER_SECS
+                })
+        })
+    }
+
+    // 自分の書き込みのレス番号 (照合済みのもの)
+    pub fn numbers(&self) -> &HashSet<u32> {
+        &self.numbers
+    }
+
+    // 照合の結果を記録し、自分の書き込みへの返信を受信箱に入れる。受信箱に新しく入った数を返す
+    // muted は表示用に判定済みの NG になったレスの番号。None なら返信がある場合だけここで判定する
+    pub fn finish(
+        &self,
+        store: &LogStore,
+        board: &str,
+        thread_id: &str,
+        responses: &[ResponseItem],
+        muted: Option<&HashSet<u32>>,
+    ) -> Result<usize, String> {
+        self.save(store)?;
+        if !has_replies(responses, &self.numbers) {
+            return Ok(0);
+        }
+        match muted {
+            Some(muted) => store.collect_replies(board, thread_id, responses, &self.numbers, muted),
+            None => {
+                let muted = load_ng_filter(store, board, thread_id).find_muted(responses);
+                store.collect_replies(board, thread_id, responses, &self.numbers, &muted)
+            }
+        }
+    }
+
+    // 新しく照合できた書き込みのレス番号をログDBに記録する
+    pub fn save(&self, store: &LogStore) -> Result<(), String> {
+        if self.matched.is_empty() {
+            return Ok(());
+        }
+        let conn = store.conn()?;
+        for (id, number) in &self.matched {
+            conn.execute(
+                "UPDATE my_posts SET number = ?2 WHERE id = ?1",
+                params![id, number],
+            )
+            .map_err(|e| format!("書き込み履歴の更新に失敗しました: {}", e))?;
+        }
+        Ok(())
+    }
+}
+
+impl LogStore {
+    // 書き込みに成功したときに記録する。レス番号は次に dat を取得したときに照合する
+    // posted_at には送信を始めた時刻を渡す (レスの日付と照合するため)
+    pub fn record_my_post(
+        &self,
+        board: &str,
+        thread_id: &str,
+        name: &str,
+        mail: &str,
+        body: &str,
+        posted_at: i64,
+    ) -> Result<i64, String> {
+        let conn = self.conn()?;
+        conn.execute(
+            "INSERT INTO my_posts (board, thread_id, name, mail, body, posted_at)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+            params![board, thread_id, name, mail, body, posted_at],
+        )
+        .map_err(|e| format!("書き込み履歴の保存に失敗しました: {}", e))?;
+        Ok(conn.last_insert_rowid())
+    }
+
+    pub fn my_post_marker(&self, board: &str, thread_id: &str) -> Result<MyPostMarker, String> {
+        let conn = self.conn()?;
+        let mut stmt = conn
+            .prepare(
+                "SELECT id, body, posted_at, number FROM my_posts
+                 WHERE board = ?1 AND thread_id = ?2 ORDER BY posted_at, id",
+            )
+            .map_err(|e| e.to_string())?;
+        let rows = stmt
+            .query_map(params![board, thread_id], |row| {
+                Ok((
+                    row.get::<_, i64>(0)?,
+                    row.get::<_, String>(1)?,
+                    row.get::<_, i64>(2)?,
+                    row.get::<_, Option<u32>>(3)?,
+                ))
+            })
+            .map_err(|e| e.to_string())?;
+
+        let mut marker = MyPostMarker {
+            numbers: HashSet::new(),
+            pending: Vec::new(),
+            matched: Vec::new(),
+        };
+        for row in rows {
+            let (id, body, posted_at, number) =
+                row.map_err(|e| format!("書き込み履歴の読み込みに失敗しました: {}", e))?;
+            match number {
+                Some(number) => {
+                    marker.numbers.insert(number);
+                }
+                None => marker.pending.push(PendingPost {
+                    id,
+                    body: normalize_body(&body),
+                    posted_at,
+                }),
+            }
+        }
+        Ok(marker)
+    }
+
+    // スレッドのレスに自分の書き込みの印を付け、返信を受信箱に入れる (受信箱に新しく入った数を返す)
+    pub fn track_my_posts(
+        &self,
+        board: &str,
+        thread_id: &str,
+        responses: &mut [ResponseItem],
+    ) -> Result<usize, String> {
+        let mut marker = self.my_post_marker(board, thread_id)?;
+        marker.apply(responses);
+        marker.finish(self, board, thread_id, responses, None)
+    }
+
+    // すべてのスレッドの書き込み履歴 (新しい順)
+    pub fn list_my_posts(&self, limit: usize) -> Result<Vec<MyPost>, String> {
+        let conn = self.conn()?;
+        let mut stmt = conn
+            .prepare(
+                "SELECT p.id, p.board, p.thread_id, COALESCE(t.title, ''), p.name, p.mail, p.body,
+                        p.posted_at, p.number
+                 FROM my_posts p
+                 LEFT JOIN threads t ON t.board = p.board AND t.thread_id = p.thread_id
+                 ORDER BY p.posted_at DESC, p.id DESC
+                 LIMIT ?1",
+            )
+            .map_err(|e| e.to_string())?;
+        let rows = stmt
+            .query_map(params![limit as i64], |row| {
+                Ok(MyPost {
+                    id: row.get(0)?,
+                    board: row.get(1)?,
+                    thread_id: row.get(2)?,
+                    thread_title: row.get(3)?,
+                    name: row.get(4)?,
+                    mail: row.get(5)?,
+                    body: row.get(6)?,
+                    posted_at: row.get(7)?,
+                    number: row.get(8)?,
+                })
+            })
+            .map_err(|e| e.to_string())?;
+        rows.collect::<Result<Vec<_>, _>>()
+            .map_err(|e| format!("書き込み履歴の読み込みに失敗しました: {}", e))
+    }
+}
+
+// 書き込み履歴 (新しい順、最大 limit 件)
+#[cfg(feature = "tauri")]
+#[tauri::command]
+pub async fn get_my_posts(
+    limit: Option<usize>,
+    store: State<'_, LogStore>,
+) -> Result<Vec<MyPost>, String> {
+    store.list_my_posts(limit.unwrap_or(200))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::thread::dat::parse_dat;
+    use chrono_tz::Asia::Tokyo;
+    use std::path::Path;
+
+    // 書き込みを送った時刻 (2024/01/01 12:00:00 JST)
+    fn posted_at() -> i64 {
+        parse_response_timestamp("2024/01/01 12:00:00").unwrap()
+    }
+
+    // posted_at から offset 秒後の日付の dat の行 (None なら日付の無い行)
+    fn line(offset: Option<i64>, body: &str) -> String {
+        let date = offset.map_or("あぼーん".to_string(), |offset| {
+            chrono::DateTime::from_timestamp(posted_at() + offset, 0)
+                .unwrap()
+                .with_timezone(&Tokyo)
+                .format("%Y/%m/%d(月) %H:%M:%S.00 ID:abc")
+                .to_string()
+        });
+        format!("名無し<>sage<>{}<> {} <>\n", date, body)
+    }
+
+    fn marker(body: &str) -> (LogStore, MyPostMarker) {
+        let store = LogStore::open(Path::new(":memory:")).unwrap();
+        store
+            .record_my_post("test", "1700000000", "", "sage", body, posted_at())
+            .unwrap();
+        let marker = store.my_post_marker("test", "1700000000").unwrap();
+        (store, marker)
+    }
+
+    fn mine(responses: &[ResponseItem]) -> Vec<&str> {
+        responses
+            .iter()
+            .filter(|response| response.is_mine)
+            .map(|response| response.id.as_str())
+            .collect()
+    }
+
+    #[test]
+    fn body_is_normalized_like_the_server() {
+        assert_eq!(
+            normalize_body("\r\n一行目  \r\n\r\n三行目\t\r\n\r\n"),
+            "一行目\n\n三行目"
+        );
+        assert_eq!(normalize_body("  乙 "), "乙");
+        assert_eq!(normalize_body("\n\n"), "");
+    }
+
+    #[test]
+    fn same_body_from_someone_else_is_not_mine() {
+        let (_, mut marker) = marker("乙");
+        let dat = [
+            line(Some(-60), "乙"),
+            line(None, "乙"),
+            line(Some(5), "乙です"),
+            line(Some(10), "乙"),
+            line(Some(20), "乙"),
+        ]
+        .concat();
+        let mut responses = parse_dat(&dat);
+        marker.apply(&mut responses);
+        assert_eq!(mine(&responses), ["4"]);
+    }
+
+    #[test]
+    fn time_window_edges() {
+        for (offset, matched) in [
+            (-MATCH_BEFORE_SECS - 1, false),
+            (-MATCH_BEFORE_SECS, true),
+            (MATCH_AFTER_SECS, true),
+            (MATCH_AFTER_SECS + 1, false),
+        ] {
+            let (_, mut marker) = marker("テスト");
+            let mut responses = parse_dat(&line(Some(offset), "テスト"));
+            marker.apply(&mut responses);
+            assert_eq!(responses[0].is_mine, matched, "offset {}", offset);
+        }
+    }
+
+    #[test]
+    fn apply_keeps_marks_across_calls() {
+        let (store, mut marker) = marker("書いた\n本文");
+        let dat = [
+            line(Some(1), "別のレス"),
+            line(Some(2), "書いた<br>本文"),
+            line(Some(3), "書いた<br>本文"),
+        ]
+        .concat();
+        let mut responses = parse_dat(&dat);
+
+        // 受信しながら少しずつ渡す
+        marker.apply(&mut responses[..1]);
+        assert!(mine(&responses).is_empty());
+        marker.apply(&mut responses[1..]);
+        assert_eq!(mine(&responses), ["2"]);
+        // 同じレスをもう一度渡しても、照合済みの書き込みで別のレスを印付けしない
+        marker.apply(&mut responses);
+        assert_eq!(mine(&responses), ["2"]);
+        assert_eq!(marker.matched, [(1, 2)]);
+
+        marker.save(&store).unwrap();
+        let reloaded = store.my_post_marker("test", "1700000000").unwrap();
+        assert!(reloaded.pending.is_empty());
+        assert_eq!(reloaded.numbers(), &HashSet::from([2]));
+        assert_eq!(store.list_my_posts(10).unwrap()[0].number, Some(2));
+    }
+}
@@ -0,0 +1,56 @@
+use rusqlite::params;
+#[cfg(feature = "tauri")]
+use tauri::State;
+
+use super::log_store::LogStore;
+#[cfg(feature = "tauri")]
+use crate::thread::board::BOARD_ID;
+
+impl LogStore {
+    // ピン留めしたスレッドID (ピン留めした順)
+    pub fn pinned_threads(&self, board: &str) -> Result<Vec<String>, String> {
+        let conn = self.conn()?;
+        let mut stmt = conn
+            .prepare(
+                "SELECT thread_id FROM pinned_threads WHERE board = ?1 ORDER BY pinned_at, rowid",
+            )
+            .map_err(|e| e.to_string())?;
+        let rows = stmt
+            .query_map(params![board], |row| row.get(0))
+            .map_err(|e| e.to_string())?;
+        rows.collect::<Result<Vec<String>, _>>()
+            .map_err(|e| format!("ピン留めの読み込みに失敗しました: {}", e))
+    }
+
+    pub fn pin_thread(&self, board: &str, thread_id: &str) -> Result<(), String> {
+        let conn = self.conn()?;
+        conn.execute(
+            "INSERT OR IGNORE INTO pinned_threads (board, thread_id, pinned_at) VALUES (?1, ?2, ?3)",
+            params![board, thread_id, chrono::Utc::now().timestamp()],
+        )
+        .map_err(|e| format!("ピン留めに失敗しました: {}", e))?;
+        Ok(())
+    }
+
+    pub fn unpin_thread(&self, board: &str, thread_id: &str) -> Result<(), String> {
+        let conn = self.conn()?;
+        conn.execute(
+            "DELETE FROM pinned_threads WHERE board = ?1 AND thread_id = ?2",
+            params![board, thread_id],
+        )
+        .map_err(|e| format!("ピン留めの解除に失敗しました: {}", e))?;
+        Ok(())
+    }
+}
+
+#[cfg(feature = "tauri")]
+#[tauri::command]
+pub async fn pin_thread(thread_id: String, store: State<'_, LogStore>) -> Result<(), String> {
+    store.pin_thread(BOARD_ID, &thread_id)
+}
+
+#[cfg(feature = "tauri")]
+#[tauri::command]
+pub async fn unpin_thread(thread_id: String, store: State<'_, LogStore>) -> Result<(), String> {
+    store.unpin_thread(BOARD_ID, &thread_id)
+}
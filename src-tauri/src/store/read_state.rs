@@ -0,0 +1,243 @@
+use rusqlite::{params, Connection, OptionalExtension};
+use serde::Serialize;
+use std::collections::HashMap;
+#[cfg(feature = "tauri")]
+use tauri::State;
+
+use super::log_store::LogStore;
+#[cfg(feature = "tauri")]
+use crate::thread::board::BOARD_ID;
+
+// スレッドごとの既読情報
+#[derive(Debug, Serialize, Clone)]
+pub struct ReadState {
+    pub last_read: u32,            // 最後に読んだレス番号
+    pub last_seen_count: u32,      // 最後に取得したときのレス数
+    pub last_read_at: Option<i64>, // 最後に既読位置を更新した日時 (Unixタイムスタンプ)
+}
+
+// 前回取得したスレッド一覧の1件
+#[derive(Debug, Clone)]
+pub struct SnapshotThread {
+    pub title: String,
+    pub response_count: u32,
+}
+
+impl LogStore {
+    // スレッドを取得したときのレス数を記録する
+    pub fn record_seen(
+        &self,
+        board: &str,
+        thread_id: &str,
+        response_count: u32,
+    ) -> Result<(), String> {
+        let conn = self.conn()?;
+        conn.execute(
+            "INSERT INTO read_state (board, thread_id, last_seen_count) VALUES (?1, ?2, ?3)
+             ON CONFLICT (board, thread_id) DO UPDATE SET last_seen_count = excluded.last_seen_count",
+            params![board, thread_id, response_count],
+        )
+        .map_err(|e| format!("取得レス数の記録に失敗しました: {}", e))?;
+        Ok(())
+    }
+
+    // 既読位置を更新する (戻ることはないので、大きい方を残す)
+    pub fn mark_read(
+        &self,
+        board: &str,
+        thread_id: &str,
+        last_read: u32,
+    ) -> Result<ReadState, String> {
+        {
+            let conn = self.conn()?;
+            conn.execute(
+                "INSERT INTO read_state (board, thread_id, last_read, last_read_at) VALUES (?1, ?2, ?3, ?4)
+                 ON CONFLICT (board, thread_id) DO UPDATE SET
+                    last_read = MAX(read_state.last_read, excluded.last_read),
+                    last_read_at = excluded.last_read_at",
+                params![board, thread_id, last_read, chrono::Utc::now().timestamp()],
+            )
+            .map_err(|e| format!("既読位置の記録に失敗しました: {}", e))?;
+        }
+        self.read_state(board, thread_id)?
+            .ok_or_else(|| "既読位置の記録に失敗しました。".to_string())
+    }
+
+    pub fn read_state(&self, board: &str, thread_id: &str) -> Result<Option<ReadState>, String> {
+        let conn = self.conn()?;
+        conn.query_row(
+            "SELECT last_read, last_seen_count, last_read_at FROM read_state
+             WHERE board = ?1 AND thread_id = ?2",
+            params![board, thread_id],
+            |row| {
+                Ok(ReadState {
+                    last_read: row.get(0)?,
+                    last_seen_count: row.get(1)?,
+                    last_read_at: row.get(2)?,
+                })
+            },
+        )
+        .optional()
+        .map_err(|e| format!("既読情報の読み込みに失敗しました: {}", e))
+    }
+
+    // 板内のすべてのスレッドの既読情報 (スレッドID → 既読情報)
+    pub fn read_states(&self, board: &str) -> Result<HashMap<String, ReadState>, String> {
+        let conn = self.conn()?;
+        let mut stmt = conn
+            .prepare(
+                "SELECT thread_id, last_read, last_seen_count, last_read_at FROM read_state
+                 WHERE board = ?1",
+            )
+            .map_err(|e| e.to_string())?;
+        let rows = stmt
+            .query_map(params![board], |row| {
+                Ok((
+                    row.get::<_, String>(0)?,
+                    ReadState {
+                        last_read: row.get(1)?,
+                        last_seen_count: row.get(2)?,
+                        last_read_at: row.get(3)?,
+                    },
+                ))
+            })
+            .map_err(|e| format!("既読情報の読み込みに失敗しました: {}", e))?;
+        rows.collect::<Result<HashMap<_, _>, _>>()
+            .map_err(|e| format!("既読情報の読み込みに失敗しました: {}", e))
+    }
+
+    // 前回のスレッド一覧 (スレッドID → タイトルとレス数) を置き換えずに返す
+    pub fn subject_snapshot(&self, board: &str) -> Result<HashMap<String, SnapshotThread>, String> {
+        read_subject_snapshot(&*self.conn()?, board)
+    }
+
+    // 前回のスレッド一覧 (スレッドID → タイトルとレス数) を返し、今回の一覧で置き換える
+    pub fn replace_subject_snapshot(
+        &self,
+        board: &str,
+        threads: &[(String, String, u32)], // (スレッドID, タイトル, レス数)
+    ) -> Result<HashMap<String, SnapshotThread>, String> {
+        let mut conn = self.conn()?;
+        let tx = conn.transaction().map_err(|e| e.to_string())?;
+        let previous = read_subject_snapshot(&tx, board)?;
+        tx.execute(
+            "DELETE FROM subject_snapshot WHERE board = ?1",
+            params![board],
+        )
+        .map_err(|e| e.to_string())?;
+        {
+            let mut stmt = tx
+                .prepare(
+                    "INSERT OR REPLACE INTO subject_snapshot (board, thread_id, title, response_count)
+                     VALUES (?1, ?2, ?3, ?4)",
+                )
+                .map_err(|e| e.to_string())?;
+            for (thread_id, title, response_count) in threads {
+                stmt.execute(params![board, thread_id, title, response_count])
+                    .map_err(|e| format!("スレッド一覧の保存に失敗しました: {}", e))?;
+            }
+        }
+        tx.commit().map_err(|e| e.to_string())?;
+        Ok(previous)
+    }
+}
+
+// subject_snapshot テーブルから板の一覧を読む
+fn read_subject_snapshot(
+    conn: &Connection,
+    board: &str,
+) -> Result<HashMap<String, SnapshotThread>, String> {
+    let mut stmt = conn
+        .prepare("SELECT thread_id, title, response_count FROM subject_snapshot WHERE board = ?1")
+        .map_err(|e| e.to_string())?;
+    let rows = stmt
+        .query_map(params![board], |row| {
+            Ok((
+                row.get(0)?,
+                SnapshotThread {
+                    title: row.get(1)?,
+                    response_count: row.get(2)?,
+                },
+            ))
+        })
+        .map_err(|e| e.to_string())?;
+    rows.collect::<Result<HashMap<String, SnapshotThread>, _>>()
+        .map_err(|e| format!("前回のスレッド一覧の読み込みに失敗しました: {}", e))
+}
+
+// フロントエンドから既読位置 (画面に表示した最後のレス番号) を受け取る
+#[cfg(feature = "tauri")]
+#[tauri::command]
+pub async fn mark_thread_read(
+    thread_id: String,
+    last_read: u32,
+    store: State<'_, LogStore>,
+) -> Result<ReadState, String> {
+    store.mark_read(BOARD_ID, &thread_id, last_read)
+}
+
+#[cfg(feature = "tauri")]
+#[tauri::command]
+pub async fn get_read_state(
+    thread_id: String,
+    store: State<'_, LogStore>,
+) -> Result<Option<ReadState>, String> {
+    store.read_state(BOARD_ID, &thread_id)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::Path;
+
+    #[test]
+    fn mark_read_keeps_the_furthest_position() {
+        let store = LogStore::open(Path::new(":memory:")).unwrap();
+        assert!(store.read_state("test", "1700000000").unwrap().is_none());
+
+        let state = store.mark_read("test", "1700000000", 50).unwrap();
+        assert_eq!(state.last_read, 50);
+        assert!(state.last_read_at.is_some());
+        // 戻ることはない
+        assert_eq!(
+            store.mark_read("test", "1700000000", 20).unwrap().last_read,
+            50
+        );
+        assert_eq!(
+            store.mark_read("test", "1700000000", 80).unwrap().last_read,
+            80
+        );
+
+        // 取得したレス数の記録は既読位置を変えない
+        store.record_seen("test", "1700000000", 120).unwrap();
+        let state = store.read_state("test", "1700000000").unwrap().unwrap();
+        assert_eq!((state.last_read, state.last_seen_count), (80, 120));
+
+        store.record_seen("test", "1700000001", 10).unwrap();
+        let states = store.read_states("test").unwrap();
+        assert_eq!(states.len(), 2);
+        assert_eq!(states["1700000001"].last_read, 0);
+        assert!(states["1700000001"].last_read_at.is_none());
+        assert!(store.read_states("other").unwrap().is_empty());
+    }
+
+    #[test]
+    fn subject_snapshot_is_replaced_per_board() {
+        let store = LogStore::open(Path::new(":memory:")).unwrap();
+        let threads = vec![("1700000000".to_string(), "スレ".to_string(), 10)];
+        assert!(store
+            .replace_subject_snapshot("test", &threads)
+            .unwrap()
+            .is_empty());
+        store
+            .replace_subject_snapshot("other", &[("1".to_string(), "別".to_string(), 1)])
+            .unwrap();
+
+        let previous = store.replace_subject_snapshot("test", &[]).unwrap();
+        assert_eq!(previous.len(), 1);
+        assert_eq!(previous["1700000000"].title, "スレ");
+        assert_eq!(previous["1700000000"].response_count, 10);
+        assert!(store.subject_snapshot("test").unwrap().is_empty());
+        assert_eq!(store.subject_snapshot("other").unwrap().len(), 1);
+    }
+}
@@ -0,0 +1,326 @@
+use rusqlite::params;
+use serde::Serialize;
+use std::collections::HashSet;
+#[cfg(feature = "tauri")]
+use tauri::{AppHandle, Emitter, State};
+#[cfg(feature = "tauri")]
+use tauri_plugin_notification::NotificationExt;
+
+use super::log_store::LogStore;
+use crate::thread::body::{parse_anchors, plain_text};
+use crate::thread::dat::ResponseItem;
+
+// 受信箱とデスクトップ通知に載せる本文の長さ (文字数)
+const SNIPPET_CHARS: usize = 100;
+
+// 受信箱の1件 (自分の書き込みにアンカーしたレス)
+#[derive(Debug, Serialize, Clone)]
+pub struct ReplyNotice {
+    pub id: i64,
+    pub board: String,
+    pub thread_id: String,
+    pub thread_title: String, // ログDBにスレッドが無ければ空
+    pub number: u32,          // 返信のレス番号
+    pub my_number: u32,       // アンカーされた自分のレス番号
+    pub author: String,
+    pub content: String, // 本文 (タグを除いた先頭部分)
+    pub created_at: String,
+    pub detected_at: i64,
+    pub is_read: bool,
+}
+
+fn snippet(content: &str) -> String {
+    let text = plain_text(content);
+    let text = text.trim();
+    match text.char_indices().nth(SNIPPET_CHARS) {
+        Some((index, _)) => format!("{}…", &text[..index]),
+        None => text.to_string(),
+    }
+}
+
+// 自分のレスより後にあり、自分のレスにアンカーしているレスなら、そのうち最初のアンカー先を返す
+fn anchored_my_number(response: &ResponseItem, my_numbers: &HashSet<u32>) -> Option<u32> {
+    if response.is_mine {
+        return None;
+    }
+    let number = response.id.parse::<u32>().ok()?;
+    parse_anchors(&response.content)
+        .into_iter()
+        .find(|anchor| *anchor < number && my_numbers.contains(anchor))
+}
+
+// 自分の書き込みへの返信があるか (NG の判定をする前に確かめる)
+pub(crate) fn has_replies(responses: &[ResponseItem], my_numbers: &HashSet<u32>) -> bool {
+    !my_numbers.is_empty()
+        && responses
+            .iter()
+            .any(|response| anchored_my_number(response, my_numbers).is_some())
+}
+
+impl LogStore {
+    // 自分の書き込みへの返信を受信箱に入れる。受信箱に新しく入った数を返す
+    // muted (スレッドのレスをすべて判定した、NGになるレスの番号) に含まれるレスは入れない
+    pub fn collect_replies(
+        &self,
+        board: &str,
+        thread_id: &str,
+        responses: &[ResponseItem],
+        my_numbers: &HashSet<u32>,
+        muted: &HashSet<u32>,
+    ) -> Result<usize, String> {
+        if !has_replies(responses, my_numbers) {
+            return Ok(0);
+        }
+
+        let detected_at = chrono::Utc::now().timestamp();
+        let conn = self.conn()?;
+        let mut inserted = 0;
+        for response in responses {
+            let Some(my_number) = anchored_my_number(response, my_numbers) else {
+                continue;
+            };
+            let number = response.id.parse::<u32>().unwrap_or_default();
+            if muted.contains(&number) {
+                continue;
+            }
+            inserted += conn
+                .execute(
+                    "INSERT OR IGNORE INTO reply_inbox
+                        (board, thread_id, number, my_number, author, content, created_at, detected_at)
+                     VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)",
+                    params![
+                        board,
+                        thread_id,
+                        number,
+                        my_number,
+                        plain_text(&response.author),
+                        snippet(&response.content),
+                        response.created_at,
+                        detected_at
+                    ],
+                )
+                .map_err(|e| format!("返信の保存に失敗しました: {}", e))?;
+        }
+        Ok(inserted)
+    }
+
+    // 受信箱 (新しい順、最大 limit 件)
+    pub fn list_replies(&self, limit: usize) -> Result<Vec<ReplyNotice>, String> {
+        self.query_replies("1 = 1", limit)
+    }
+
+    fn query_replies(&self, condition: &str, limit: usize) -> Result<Vec<ReplyNotice>, String> {
+        let conn = self.conn()?;
+        let mut stmt = conn
+            .prepare(&format!(
+                "SELECT r.id, r.board, r.thread_id, COALESCE(t.title, ''), r.number, r.my_number,
+                        r.author, r.content, r.created_at, r.detected_at, r.is_read
+                 FROM reply_inbox r
+                 LEFT JOIN threads t ON t.board = r.board AND t.thread_id = r.thread_id
+                 WHERE {}
+                 ORDER BY r.detected_at DESC, r.thread_id DESC, r.number DESC
+                 LIMIT ?1",
+                condition
+            ))
+            .map_err(|e| e.to_string())?;
+        let rows = stmt
+            .query_map(params![limit as i64], |row| {
+                Ok(ReplyNotice {
+                    id: row.get(0)?,
+                    board: row.get(1)?,
+                    thread_id: row.get(2)?,
+                    thread_title: row.get(3)?,
+                    number: row.get(4)?,
+                    my_number: row.get(5)?,
+                    author: row.get(6)?,
+                    content: row.get(7)?,
+                    created_at: row.get(8)?,
+                    detected_at: row.get(9)?,
+                    is_read: row.get(10)?,
+                })
+            })
+            .map_err(|e| e.to_string())?;
+        rows.collect::<Result<Vec<_>, _>>()
+            .map_err(|e| format!("受信箱の読み込みに失敗しました: {}", e))
+    }
+
+    // まだ通知していない返信を取り出し、通知済みにする
+    pub fn take_unnotified_replies(&self) -> Result<Vec<ReplyNotice>, String> {
+        let replies = self.query_replies("r.is_notified = 0", i64::MAX as usize)?;
+        // 読み出したあとに入った返信は次の通知に回す
+        if let Some(max_id) = replies.iter().map(|reply| reply.id).max() {
+            self.conn()?
+                .execute(
+                    "UPDATE reply_inbox SET is_notified = 1 WHERE is_notified = 0 AND id <= ?1",
+                    params![max_id],
+                )
+                .map_err(|e| format!("受信箱の更新に失敗しました: {}", e))?;
+        }
+        Ok(replies)
+    }
+
+    // ids を既読にする (None ならすべて)
+    pub fn mark_replies_read(&self, ids: Option<&[i64]>) -> Result<(), String> {
+        let conn = self.conn()?;
+        let result = match ids {
+            Some(ids) => ids.iter().try_for_each(|id| {
+                conn.execute(
+                    "UPDATE reply_inbox SET is_read = 1 WHERE id = ?1",
+                    params![id],
+                )
+                .map(|_| ())
+            }),
+            None => conn
+                .execute("UPDATE reply_inbox SET is_read = 1 WHERE is_read = 0", [])
+                .map(|_| ()),
+        };
+        result.map_err(|e| format!("受信箱の更新に失敗しました: {}", e))
+    }
+
+    pub fn unread_reply_count(&self) -> Result<usize, String> {
+        self.conn()?
+            .query_row(
+                "SELECT COUNT(*) FROM reply_inbox WHERE is_read = 0",
+                [],
+                |row| row.get::<_, i64>(0),
+            )
+            .map(|count| count as usize)
+            .map_err(|e| format!("受信箱の読み込みに失敗しました: {}", e))
+    }
+}
+
+// 受信箱に新しく入った返信をデスクトップ通知で知らせ、メインウィンドウに未読数を送る
+// スレッドの更新やお気に入りの巡回のあとに呼ぶ。失敗しても更新を妨げないよう、ログを出すだけにする
+#[cfg(feature = "tauri")]
+pub(crate) fn notify_new_replies(app_handle: &AppHandle, store: &LogStore) {
+    let replies = match store.take_unnotified_replies() {
+        Ok(replies) => replies,
+        Err(e) => {
+            eprintln!("[Rust notify_new_replies] {}", e);
+            return;
+        }
+    };
+    if replies.is_empty() {
+        return;
+    }
+    eprintln!(
+        "[Rust notify_new_replies] 自分の書き込みへの返信が {} 件あります",
+        replies.len()
+    );
+
+    // 返信ごとに通知する (まとめると、どのスレッドのどのレスへの返信か分からなくなるため)
+    for reply in &replies {
+        let title = if reply.thread_title.is_empty() {
+            format!(">>{} への返信", reply.my_number)
+        } else {
+            format!("{} (>>{} への返信)", reply.thread_title, reply.my_number)
+        };
+        if let Err(e) = app_handle
+            .notification()
+            .builder()
+            .title(title)
+            .body(format!("{}: {}", reply.number, reply.content))
+            .show()
+        {
+            eprintln!(
+                "[Rust notify_new_replies] デスクトップ通知に失敗しました: {}",
+                e
+            );
+        }
+    }
+
+    match store.unread_reply_count() {
+        Ok(unread) => {
+            if let Err(e) = app_handle.emit_to("main", "replies-updated", unread) {
+                eprintln!(
+                    "[Rust notify_new_replies] 未読数の送信に失敗しました: {}",
+                    e
+                );
+            }
+        }
+        Err(e) => eprintln!("[Rust notify_new_replies] {}", e),
+    }
+}
+
+// 自分の書き込みへの返信の受信箱 (新しい順、最大 limit 件)
+#[cfg(feature = "tauri")]
+#[tauri::command]
+pub async fn get_reply_inbox(
+    limit: Option<usize>,
+    store: State<'_, LogStore>,
+) -> Result<Vec<ReplyNotice>, String> {
+    store.list_replies(limit.unwrap_or(200))
+}
+
+#[cfg(feature = "tauri")]
+#[tauri::command]
+pub async fn get_unread_reply_count(store: State<'_, LogStore>) -> Result<usize, String> {
+    store.unread_reply_count()
+}
+
+// 返信を既読にする (ids を省略するとすべて)
+#[cfg(feature = "tauri")]
+#[tauri::command]
+pub async fn mark_replies_read(
+    ids: Option<Vec<i64>>,
+    store: State<'_, LogStore>,
+) -> Result<usize, String> {
+    store.mark_replies_read(ids.as_deref())?;
+    store.unread_reply_count()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ng::filter::{load_ng_filter, muted_numbers};
+    use crate::ng::rules::{NgOptions, NgRuleInput, NgScope, NgTarget};
+    use crate::thread::dat::parse_dat;
+    use std::path::Path;
+
+    const DAT: &str = "名無し<><>2024/01/01 ID:a<> 自分の書き込み <>スレ\n\
+名無し<><>2024/01/01 ID:b<> &gt;&gt;1 返信 <>\n\
+名無し<><>2024/01/01 ID:c<> &gt;&gt;1 荒らし <>\n\
+名無し<><>2024/01/01 ID:d<> &gt;&gt;3 連鎖 <>\n\
+名無し<><>2024/01/01 ID:e<> &gt;&gt;1 もう一つの返信 <>\n";
+
+    #[test]
+    fn muted_replies_are_not_collected() {
+        let store = LogStore::open(Path::new(":memory:")).unwrap();
+        let responses = parse_dat(DAT);
+        let my_numbers = HashSet::from([1]);
+        let muted = HashSet::from([3, 5]);
+        let inserted = store
+            .collect_replies("news", "1700000000", &responses, &my_numbers, &muted)
+            .unwrap();
+        assert_eq!(inserted, 1);
+        let replies = store.list_replies(10).unwrap();
+        assert_eq!(replies.len(), 1);
+        assert_eq!((replies[0].number, replies[0].my_number), (2, 1));
+    }
+
+    // 印を付けずに判定した結果は、印を付けて判定した結果と同じ (連鎖NGも含む)
+    #[test]
+    fn find_muted_matches_applied_filter() {
+        let store = LogStore::open(Path::new(":memory:")).unwrap();
+        store
+            .add_ng_rule(&NgRuleInput {
+                target: NgTarget::Word,
+                pattern: "荒らし".to_string(),
+                scope: NgScope::Global,
+                duration_secs: None,
+                folds: Default::default(),
+            })
+            .unwrap();
+        store
+            .set_ng_options(&NgOptions {
+                chain_enabled: true,
+                chain_depth: 1,
+            })
+            .unwrap();
+        let mut responses = parse_dat(DAT);
+        let muted = load_ng_filter(&store, "news", "1700000000").find_muted(&responses);
+        load_ng_filter(&store, "news", "1700000000").apply(&mut responses);
+        assert_eq!(muted, HashSet::from([3, 4]));
+        assert_eq!(muted, muted_numbers(&responses));
+    }
+}
@@ -0,0 +1,616 @@
+use html_escape::encode_text;
+use rusqlite::{params, Connection, Transaction};
+use serde::{Deserialize, Serialize};
+#[cfg(feature = "tauri")]
+use tauri::State;
+
+use super::log_store::LogStore;
+use crate::thread::body::plain_text;
+use crate::thread::dat::ResponseItem;
+use crate::thread::normalize::{normalize, NormalizeOptions};
+
+// 検索結果の既定の件数と上限
+const DEFAULT_SEARCH_LIMIT: usize = 100;
+const MAX_SEARCH_LIMIT: usize = 1000;
+// スニペットとして切り出す、一致箇所の前後の文字数
+const SNIPPET_BEFORE: usize = 30;
+const SNIPPET_LENGTH: usize = 120;
+// 検索語を指定しないときに探す列
+const DEFAULT_COLUMNS: &str = "{body name user_id}";
+
+// フロントエンドから受け取る検索条件
+#[derive(Debug, Deserialize, Clone)]
+pub struct SearchQuery {
+    pub query: String, // 例: 猫 "黒い猫" OR 犬 -子猫 name:名無し id:AbCd
+    #[serde(default)]
+    pub board: Option<String>,
+    #[serde(default)]
+    pub from_date: Option<String>, // "YYYY-MM-DD" (この日を含む)
+    #[serde(default)]
+    pub to_date: Option<String>, // "YYYY-MM-DD" (この日を含む)
+    #[serde(default)]
+    pub limit: Option<usize>,
+}
+
+// 検索結果の1件 (関連度の高い順に返す)
+#[derive(Debug, Serialize, Clone)]
+pub struct SearchHit {
+    pub board: String,
+    pub thread_id: String,
+    pub thread_title: String,
+    pub number: u32,
+    pub author: String,
+    pub user_id: Option<String>,
+    pub created_at: String,
+    pub snippet: String, // HTMLエスケープ済み。一致箇所は <mark> で囲む
+    pub score: f64,      // bm25 のスコア (小さいほど関連度が高い)
+}
+
+// 検索索引 (response_search) を追加したスキーマのバージョン (log_store.rs の MIGRATIONS の v8)
+pub(crate) const SEARCH_INDEX_VERSION: usize = 8;
+
+// ===== トークン化 =====
+// 日本語は単語の区切りがないため、英数字以外の連続は2文字ずつ (bigram) に分ける。
+// 1文字だけの検索語は前方一致で探すので、索引には連続の末尾の1文字も入れておく。
+// 英数字の連続はそのまま1つのトークンにし、検索では前方一致で探す (id:AbCd で ID の先頭が一致するレス)
+
+// 正規化済みの文字列を、英数字の連続とそれ以外の文字の連続に分ける
+fn split_runs(normalized: &str) -> Vec<(bool, Vec<char>)> {
+    let mut runs: Vec<(bool, Vec<char>)> = Vec::new();
+    for c in normalized.chars() {
+        if !c.is_alphanumeric() {
+            if runs.last().is_some_and(|(_, run)| !run.is_empty()) {
+                runs.push((false, Vec::new()));
+            }
+            continue;
+        }
+        let is_ascii = c.is_ascii_alphanumeric();
+        match runs.last_mut() {
+            Some((run_is_ascii, run)) if run.is_empty() || *run_is_ascii == is_ascii => {
+                *run_is_ascii = is_ascii;
+                run.push(c);
+            }
+            _ => runs.push((is_ascii, vec![c])),
+        }
+    }
+    runs.retain(|(_, run)| !run.is_empty());
+    runs
+}
+
+fn bigrams(run: &[char]) -> Vec<String> {
+    run.windows(2).map(|pair| pair.iter().collect()).collect()
+}
+
+// 索引に入れる、空白区切りのトークン列
+pub(crate) fn index_tokens(text: &str) -> String {
+    let normalized = normalize(text, &NormalizeOptions::all());
+    let mut tokens: Vec<String> = Vec::new();
+    for (is_ascii, run) in split_runs(&normalized) {
+        if is_ascii {
+            tokens.push(run.iter().collect());
+            continue;
+        }
+        tokens.extend(bigrams(&run));
+        if let Some(last) = run.last() {
+            tokens.push(last.to_string());
+        }
+    }
+    tokens.join(" ")
+}
+
+// 検索語1つを FTS5 の式にする。英数字は前方一致の単語、日本語は bigram のフレーズ、
+// 1文字だけの日本語は前方一致にする。検索に使える文字がなければ None
+fn term_expression(text: &str) -> Option<String> {
+    let normalized = normalize(text, &NormalizeOptions::all());
+    let parts: Vec<String> = split_runs(&normalized)
+        .into_iter()
+        .map(|(is_ascii, run)| {
+            if is_ascii || run.len() == 1 {
+                format!("\"{}\"*", run.iter().collect::<String>())
+            } else {
+                format!("\"{}\"", bigrams(&run).join(" "))
+            }
+        })
+        .collect();
+    if parts.is_empty() {
+        None
+    } else {
+        Some(parts.join(" AND "))
+    }
+}
+
+// ===== 検索式の解析 =====
+
+#[derive(Debug, Clone)]
+struct Term {
+    columns: &'static str,
+    text: String,
+    negated: bool,
+}
+
+enum Token {
+    Term(Term),
+    Or,
+}
+
+// 空白区切りの検索語、"フレーズ"、OR、NOT / -除外、name: / id: / body: の列指定を読む
+fn tokenize_query(query: &str) -> Vec<Token> {
+    let mut tokens = Vec::new();
+    let mut chars = query.chars().peekable();
+    let mut negate_next = false;
+    loop {
+        while chars.peek().is_some_and(|c| c.is_whitespace()) {
+            chars.next();
+        }
+        if chars.peek().is_none() {
+            break;
+        }
+        let mut negated = std::mem::take(&mut negate_next);
+        if chars.peek() == Some(&'-') {
+            chars.next();
+            negated = true;
+        }
+
+        let mut word = String::new();
+        let mut quoted = false;
+        while let Some(&c) = chars.peek() {
+            if c == '"' {
+                chars.next();
+                quoted = true;
+                for c in chars.by_ref() {
+                    if c == '"' {
+                        break;
+                    }
+                    word.push(c);
+                }
+                break;
+            }
+            if c.is_whitespace() {
+                break;
+            }
+            word.push(c);
+            chars.next();
+        }
+
+        if !quoted {
+            match word.as_str() {
+                "OR" | "|" => {
+                    tokens.push(Token::Or);
+                    continue;
+                }
+                "AND" => continue,
+                "NOT" => {
+                    negate_next = true;
+                    continue;
+                }
+                _ => {}
+            }
+        }
+
+        let (columns, text) = match word.split_once(':') {
+            Some(("name", rest)) => ("name", rest.to_string()),
+            Some(("id" | "ID", rest)) => ("user_id", rest.to_string()),
+            Some(("body", rest)) => ("body", rest.to_string()),
+            _ => (DEFAULT_COLUMNS, word),
+        };
+        tokens.push(Token::Term(Term {
+            columns,
+            text,
+            negated,
+        }));
+    }
+    tokens
+}
+
+// 検索式を FTS5 の MATCH 式に変換する。戻り値は (MATCH 式, 強調表示する語)
+fn build_match_expression(query: &str) -> Result<(String, Vec<String>), String> {
+    let mut groups: Vec<Vec<String>> = vec![Vec::new()]; // OR で区切った AND のまとまり
+    let mut excluded: Vec<String> = Vec::new();
+    let mut highlights: Vec<String> = Vec::new();
+
+    for token in tokenize_query(query) {
+        let term = match token {
+            Token::Or => {
+                if groups.last().is_some_and(|group| !group.is_empty()) {
+                    groups.push(Vec::new());
+                }
+                continue;
+            }
+            Token::Term(term) => term,
+        };
+        let Some(expression) = term_expression(&term.text) else {
+            continue;
+        };
+        let expression = format!("{} : ({})", term.columns, expression);
+        if term.negated {
+            excluded.push(expression);
+        } else {
+            highlights.push(term.text.trim().to_string());
+            if let Some(group) = groups.last_mut() {
+                group.push(expression);
+            }
+        }
+    }
+
+    groups.retain(|group| !group.is_empty());
+    if groups.is_empty() {
+        return Err("検索語を入力してください (除外する語だけでは検索できません)。".to_string());
+    }
+    let positive = groups
+        .iter()
+        .map(|group| format!("({})", group.join(" AND ")))
+        .collect::<Vec<_>>()
+        .join(" OR ");
+    let mut expression = format!("({})", positive);
+    if !excluded.is_empty() {
+        expression = format!("{} NOT ({})", expression, excluded.join(" OR "));
+    }
+    Ok((expression, highlights))
+}
+
+// ===== スニペット =====
+
+// 本文から一致箇所の周辺を切り出し、一致箇所を <mark> で囲む
+fn build_snippet(text: &str, highlights: &[String]) -> String {
+    let chars: Vec<char> = text
+        .chars()
+        .map(|c| if c == '\n' { ' ' } else { c })
+        .collect();
+    // 1文字ずつ正規化し、正規化後の各文字が元のどの文字から来たかを覚えておく
+    let options = NormalizeOptions::all();
+    let mut normalized: Vec<char> = Vec::new();
+    let mut origin: Vec<usize> = Vec::new();
+    for (index, c) in chars.iter().enumerate() {
+        for normalized_char in normalize(&c.to_string(), &options).chars() {
+            normalized.push(normalized_char);
+            origin.push(index);
+        }
+    }
+
+    let mut ranges: Vec<(usize, usize)> = Vec::new(); // 元の文字の [開始, 終了)
+    for highlight in highlights {
+        let needle: Vec<char> = normalize(highlight, &options).chars().collect();
+        if needle.is_empty() || needle.len() > normalized.len() {
+            continue;
+        }
+        for start in 0..=(normalized.len() - needle.len()) {
+            if normalized[start..start + needle.len()] == needle[..] {
+                ranges.push((origin[start], origin[start + needle.len() - 1] + 1));
+            }
+        }
+    }
+    ranges.sort();
+    let mut merged: Vec<(usize, usize)> = Vec::new();
+    for (start, end) in ranges {
+        match merged.last_mut() {
+            Some((_, last_end)) if start <= *last_end => *last_end = (*last_end).max(end),
+            _ => merged.push((start, end)),
+        }
+    }
+
+    let window_start = merged
+        .first()
+        .map(|(start, _)| start.saturating_sub(SNIPPET_BEFORE))
+        .unwrap_or(0);
+    let window_end = (window_start + SNIPPET_LENGTH).min(chars.len());
+
+    let mut snippet = String::new();
+    if window_start > 0 {
+        snippet.push('…');
+    }
+    let mut position = window_start;
+    for (start, end) in merged {
+        if end <= window_start || start >= window_end {
+            continue;
+        }
+        let (start, end) = (start.max(position), end.min(window_end));
+        let before: String = chars[position..start].iter().collect();
+        let marked: String = chars[start..end].iter().collect();
+        snippet.push_str(&encode_text(&before));
+        snippet.push_str("<mark>");
+        snippet.push_str(&encode_text(&marked));
+        snippet.push_str("</mark>");
+        position = end;
+    }
+    let rest: String = chars[position..window_end].iter().collect();
+    snippet.push_str(&encode_text(&rest));
+    if window_end < chars.len() {
+        snippet.push('…');
+    }
+    snippet
+}
+
+// ===== 索引の更新と検索 =====
+
+// スレッドのレスを索引に入れ直す (save_thread のトランザクション内で呼ぶ)
+pub(crate) fn index_responses(
+    tx: &Transaction<'_>,
+    board: &str,
+    thread_id: &str,
+    responses: &[ResponseItem],
+) -> Result<(), String> {
+    tx.execute(
+        "DELETE FROM response_search WHERE board = ?1 AND thread_id = ?2",
+        params![board, thread_id],
+    )
+    .map_err(|e| e.to_string())?;
+    let mut stmt = tx
+        .prepare(
+            "INSERT INTO response_search (board, thread_id, number, body, name, user_id)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+        )
+        .map_err(|e| e.to_string())?;
+    for (index, response) in responses.iter().enumerate() {
+        stmt.execute(params![
+            board,
+            thread_id,
+            (index + 1) as i64,
+            index_tokens(&plain_text(&response.content)),
+            index_tokens(&plain_text(&response.author)),
+            response
+                .parsed_user_id
+                .as_deref()
+                .map(index_tokens)
+                .unwrap_or_default(),
+        ])
+        .map_err(|e| format!("検索索引の更新に失敗しました: {}", e))?;
+    }
+    Ok(())
+}
+
+// 索引を追加する前 (SEARCH_INDEX_VERSION より前) のログDBを開いたときは、保存済みのレスから索引を作る
+// migrated_from はマイグレーション前のスキーマのバージョン (それ以降のログDBでは何もしない)
+pub(crate) fn ensure_search_index(
+    conn: &mut Connection,
+    migrated_from: usize,
+) -> Result<(), String> {
+    if migrated_from >= SEARCH_INDEX_VERSION {
+        return Ok(());
+    }
+    let indexed: i64 = conn
+        .query_row("SELECT COUNT(*) FROM response_search", [], |row| row.get(0))
+        .map_err(|e| e.to_string())?;
+    let stored: i64 = conn
+        .query_row("SELECT COUNT(*) FROM responses", [], |row| row.get(0))
+        .map_err(|e| e.to_string())?;
+    if indexed > 0 || stored == 0 {
+        return Ok(());
+    }
+    eprintln!(
+        "[Rust LogStore] 保存済みの {} 個のレスから検索索引を作成します。",
+        stored
+    );
+    let rows: Vec<(String, String, i64, String, String, Option<String>)> = {
+        let mut stmt = conn
+            .prepare(
+                "SELECT board, thread_id, number, content, author, parsed_user_id FROM responses",
+            )
+            .map_err(|e| e.to_string())?;
+        let rows = stmt
+            .query_map([], |row| {
+                Ok((
+                    row.get(0)?,
+                    row.get(1)?,
+                    row.get(2)?,
+                    row.get(3)?,
+                    row.get(4)?,
+                    row.get(5)?,
+                ))
+            })
+            .map_err(|e| e.to_string())?;
+        rows.collect::<Result<Vec<_>, _>>()
+            .map_err(|e| e.to_string())?
+    };
+    let tx = conn.transaction().map_err(|e| e.to_string())?;
+    {
+        let mut stmt = tx
+            .prepare(
+                "INSERT INTO response_search (board, thread_id, number, body, name, user_id)
+                 VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+            )
+            .map_err(|e| e.to_string())?;
+        for (board, thread_id, number, content, author, user_id) in rows {
+            stmt.execute(params![
+                board,
+                thread_id,
+                number,
+                index_tokens(&plain_text(&content)),
+                index_tokens(&plain_text(&author)),
+                user_id.as_deref().map(index_tokens).unwrap_or_default(),
+            ])
+            .map_err(|e| format!("検索索引の作成に失敗しました: {}", e))?;
+        }
+    }
+    tx.commit().map_err(|e| e.to_string())
+}
+
+// 日付の条件 ("YYYY-MM-DD") を、レスの日時 ("2024/01/01(月) 00:00:00.00") の先頭10文字と比べる形にする
+fn to_dat_date(date: Option<&str>) -> Result<Option<String>, String> {
+    let Some(date) = date.map(str::trim).filter(|d| !d.is_empty()) else {
+        return Ok(None);
+    };
+    chrono::NaiveDate::parse_from_str(date, "%Y-%m-%d")
+        .map(|date| Some(date.format("%Y/%m/%d").to_string()))
+        .map_err(|_| {
+            format!(
+                "日付の形式が不正です (YYYY-MM-DD で指定してください): {}",
+                date
+            )
+        })
+}
+
+impl LogStore {
+    pub fn search(&self, query: &SearchQuery) -> Result<Vec<SearchHit>, String> {
+        let (match_expression, highlights) = build_match_expression(&query.query)?;
+        let from_date = to_dat_date(query.from_date.as_deref())?;
+        let to_date = to_dat_date(query.to_date.as_deref())?;
+        let limit = query
+            .limit
+            .unwrap_or(DEFAULT_SEARCH_LIMIT)
+            .min(MAX_SEARCH_LIMIT);
+
+        let conn = self.conn()?;
+        let mut stmt = conn
+            .prepare(
+                "SELECT s.board, s.thread_id, s.number, COALESCE(t.title, ''),
+                        r.author, r.parsed_user_id, r.created_at, r.content,
+                        bm25(response_search, 0.0, 0.0, 0.0, 1.0, 2.0, 2.0) AS score
+                 FROM response_search s
+                 JOIN responses r
+                   ON r.board = s.board AND r.thread_id = s.thread_id AND r.number = s.number
+                 LEFT JOIN threads t ON t.board = s.board AND t.thread_id = s.thread_id
+                 WHERE response_search MATCH ?1
+                   AND (?2 IS NULL OR s.board = ?2)
+                   AND (?3 IS NULL OR substr(r.created_at, 1, 10) >= ?3)
+                   AND (?4 IS NULL OR substr(r.created_at, 1, 10) <= ?4)
+                 ORDER BY score
+                 LIMIT ?5",
+            )
+            .map_err(|e| e.to_string())?;
+        let rows = stmt
+            .query_map(
+                params![
+                    match_expression,
+                    query.board.as_deref().filter(|b| !b.is_empty()),
+                    from_date,
+                    to_date,
+                    limit as i64
+                ],
+                |row| {
+                    let content: String = row.get(7)?;
+                    Ok(SearchHit {
+                        board: row.get(0)?,
+                        thread_id: row.get(1)?,
+                        thread_title: row.get(3)?,
+                        number: row.get(2)?,
+                        author: plain_text(&row.get::<_, String>(4)?),
+                        user_id: row.get(5)?,
+                        created_at: row.get(6)?,
+                        snippet: build_snippet(&plain_text(&content), &highlights),
+                        score: row.get(8)?,
+                    })
+                },
+            )
+            .map_err(|e| format!("検索に失敗しました: {}", e))?;
+        rows.collect::<Result<Vec<_>, _>>()
+            .map_err(|e| format!("検索に失敗しました: {}", e))
+    }
+}
+
+#[cfg(feature = "tauri")]
+#[tauri::command]
+pub async fn search_logs(
+    query: SearchQuery,
+    store: State<'_, LogStore>,
+) -> Result<Vec<SearchHit>, String> {
+    eprintln!("[Rust search_logs] ログを検索します: {:?}", query);
+    let hits = store.search(&query)?;
+    eprintln!("[Rust search_logs] {} 件見つかりました。", hits.len());
+    Ok(hits)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::thread::dat::parse_dat;
+    use std::path::Path;
+
+    fn query(text: &str) -> SearchQuery {
+        SearchQuery {
+            query: text.to_string(),
+            board: None,
+            from_date: None,
+            to_date: None,
+            limit: None,
+        }
+    }
+
+    fn sample_store() -> LogStore {
+        let store = LogStore::open(Path::new(":memory:")).unwrap();
+        let dat = "名無し<><>2024/01/01(月) 00:00:00.00 ID:AbCd1234<> 黒い猫が好きです <br> Tulip &amp; rose <>猫スレ\n\
+                   コテ<><>2024/02/01(木) 00:00:01.00 ID:xyz98765<> 犬派です <>\n";
+        store
+            .save_thread(
+                "board",
+                "1000000001",
+                "猫スレ",
+                dat.as_bytes(),
+                &parse_dat(dat),
+            )
+            .unwrap();
+        store
+    }
+
+    #[test]
+    fn index_tokens_splits_japanese_into_bigrams() {
+        assert_eq!(index_tokens("黒い猫"), "黒い い猫 猫");
+        assert_eq!(index_tokens("Tulip & rose"), "tulip rose");
+        assert_eq!(index_tokens("猫cat"), "猫 cat");
+    }
+
+    #[test]
+    fn match_expression_handles_operators_and_columns() {
+        let (expression, highlights) =
+            build_match_expression("黒い猫 OR 犬 -子猫 id:AbCd").unwrap();
+        assert_eq!(
+            expression,
+            "(({body name user_id} : (\"黒い い猫\")) OR ({body name user_id} : (\"犬\"*) AND user_id : (\"abcd\"*))) NOT ({body name user_id} : (\"子猫\"))"
+        );
+        assert_eq!(highlights, vec!["黒い猫", "犬", "AbCd"]);
+        assert!(build_match_expression("-猫").is_err());
+        assert!(build_match_expression("  ").is_err());
+    }
+
+    #[test]
+    fn quoted_term_and_column_are_normalized() {
+        let (expression, highlights) = build_match_expression("\"黒い猫\" name:コテ").unwrap();
+        assert_eq!(
+            expression,
+            "(({body name user_id} : (\"黒い い猫\") AND name : (\"こて\")))"
+        );
+        assert_eq!(highlights, vec!["黒い猫", "コテ"]);
+    }
+
+    #[test]
+    fn snippet_marks_matches_and_escapes_html() {
+        assert_eq!(
+            build_snippet("黒い猫 & <b>", &["猫".to_string()]),
+            "黒い<mark>猫</mark> &amp; &lt;b&gt;"
+        );
+        // 全角・半角の違いは正規化して比べ、元の文字を囲む
+        assert_eq!(
+            build_snippet("ＴＵＬＩＰが咲いた", &["tulip".to_string()]),
+            "<mark>ＴＵＬＩＰ</mark>が咲いた"
+        );
+        let long = format!("{}猫{}", "あ".repeat(50), "い".repeat(200));
+        let snippet = build_snippet(&long, &["猫".to_string()]);
+        assert!(snippet.starts_with('…') && snippet.ends_with('…'));
+        assert_eq!(
+            snippet.chars().filter(|c| *c == 'あ').count(),
+            SNIPPET_BEFORE
+        );
+    }
+
+    #[test]
+    fn id_search_matches_prefix() {
+        let store = sample_store();
+        let hits = store.search(&query("id:AbCd")).unwrap();
+        assert_eq!(hits.len(), 1);
+        assert_eq!(hits[0].number, 1);
+        assert_eq!(store.search(&query("id:xyz")).unwrap()[0].number, 2);
+        assert!(store.search(&query("id:bcd")).unwrap().is_empty());
+    }
+
+    #[test]
+    fn malformed_dates_are_rejected() {
+        let store = sample_store();
+        let mut dated = query("猫 OR 犬");
+        dated.from_date = Some("2024-02-01".to_string());
+        assert_eq!(store.search(&dated).unwrap().len(), 1);
+        dated.from_date = Some("2024/02/01".to_string());
+        assert!(store.search(&dated).is_err());
+        dated.from_date = None;
+        dated.to_date = Some("2024-13-01".to_string());
+        assert!(store.search(&dated).is_err());
+    }
+}
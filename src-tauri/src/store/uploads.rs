@@ -0,0 +1,94 @@
+use rusqlite::{params, OptionalExtension};
+use serde::Serialize;
+
+use super::log_store::LogStore;
+
+// アップロードした画像の履歴の1件
+#[derive(Debug, Serialize, Clone, PartialEq, Eq)]
+pub struct ImageUpload {
+    pub id: i64,
+    pub api_url: String, // アップロードした API (削除は同じ API に送る)
+    pub url: String,
+    pub delete_hash: String,
+    pub file_name: String,
+    pub width: u32,
+    pub height: u32,
+    pub size: u64, // アップロードしたバイト数
+    pub uploaded_at: i64,
+}
+
+fn upload_from_row(row: &rusqlite::Row) -> rusqlite::Result<ImageUpload> {
+    Ok(ImageUpload {
+        id: row.get(0)?,
+        api_url: row.get(1)?,
+        url: row.get(2)?,
+        delete_hash: row.get(3)?,
+        file_name: row.get(4)?,
+        width: row.get(5)?,
+        height: row.get(6)?,
+        size: row.get::<_, i64>(7)? as u64,
+        uploaded_at: row.get(8)?,
+    })
+}
+
+impl LogStore {
+    // id は無視して保存し、保存した行の id を入れて返す
+    pub fn record_image_upload(&self, upload: &ImageUpload) -> Result<ImageUpload, String> {
+        let conn = self.conn()?;
+        conn.execute(
+            "INSERT INTO image_uploads
+                (api_url, url, delete_hash, file_name, width, height, size, uploaded_at)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)",
+            params![
+                upload.api_url,
+                upload.url,
+                upload.delete_hash,
+                upload.file_name,
+                upload.width,
+                upload.height,
+                upload.size as i64,
+                upload.uploaded_at
+            ],
+        )
+        .map_err(|e| format!("アップロード履歴の保存に失敗しました: {}", e))?;
+        Ok(ImageUpload {
+            id: conn.last_insert_rowid(),
+            ..upload.clone()
+        })
+    }
+
+    pub fn get_image_upload(&self, id: i64) -> Result<Option<ImageUpload>, String> {
+        self.conn()?
+            .query_row(
+                "SELECT id, api_url, url, delete_hash, file_name, width, height, size, uploaded_at
+                 FROM image_uploads WHERE id = ?1",
+                params![id],
+                upload_from_row,
+            )
+            .optional()
+            .map_err(|e| format!("アップロード履歴の読み込みに失敗しました: {}", e))
+    }
+
+    // アップロード履歴 (新しい順)
+    pub fn list_image_uploads(&self, limit: usize) -> Result<Vec<ImageUpload>, String> {
+        let conn = self.conn()?;
+        let mut stmt = conn
+            .prepare(
+                "SELECT id, api_url, url, delete_hash, file_name, width, height, size, uploaded_at
+                 FROM image_uploads ORDER BY uploaded_at DESC, id DESC LIMIT ?1",
+            )
+            .map_err(|e| e.to_string())?;
+        let rows = stmt
+            .query_map(params![limit as i64], upload_from_row)
+            .map_err(|e| e.to_string())?;
+        rows.collect::<Result<Vec<_>, _>>()
+            .map_err(|e| format!("アップロード履歴の読み込みに失敗しました: {}", e))
+    }
+
+    pub fn remove_image_upload(&self, id: i64) -> Result<(), String> {
+        self.conn()?
+            .execute("DELETE FROM image_uploads WHERE id = ?1", params![id])
+            .map_err(|e| format!("アップロード履歴の削除に失敗しました: {}", e))?;
+        Ok(())
+    }
+}
@@ -0,0 +1,108 @@
+use encoding_rs::SHIFT_JIS;
+use serde::{Deserialize, Serialize};
+use std::borrow::Cow;
+
+// 掲示板のURLやIDをまとめたもの
+// 今のところ対応している板は tulipplantation のみ
+
+pub const BOARD_BASE_URL: &str = "https://tulipplantation.com";
+pub const BOARD_ID: &str = "tulipplantation";
+pub const BOARD_ENCODING: BoardEncoding = BoardEncoding::Utf8;
+
+// 板の文字コード (書き込みフォームの送信と、bbs.cgi の応答の読み取りに使う)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum BoardEncoding {
+    ShiftJis,
+    Utf8,
+}
+
+impl BoardEncoding {
+    // Shift_JIS で表せない文字は &#NNNN; (数値文字参照) になる
+    pub fn encode<'a>(&self, text: &'a str) -> Cow<'a, [u8]> {
+        match self {
+            BoardEncoding::ShiftJis => SHIFT_JIS.encode(text).0,
+            BoardEncoding::Utf8 => Cow::Borrowed(text.as_bytes()),
+        }
+    }
+
+    pub fn decode(&self, bytes: &[u8]) -> String {
+        match self {
+            BoardEncoding::ShiftJis => SHIFT_JIS.decode(bytes).0.into_owned(),
+            BoardEncoding::Utf8 => String::from_utf8_lossy(bytes).into_owned(),
+        }
+    }
+}
+
+// subject.json (スレッド一覧) の URL
+pub fn subject_url() -> String {
+    board_subject_url(BOARD_BASE_URL, BOARD_ID)
+}
+
+// dat の URL を組み立てる
+pub fn dat_url(thread_id: &str) -> Result<String, String> {
+    board_dat_url(BOARD_BASE_URL, BOARD_ID, thread_id)
+}
+
+// 任意のサーバー・板の subject.json の URL (BoardClient 用)
+pub fn board_subject_url(base_url: &str, board_id: &str) -> String {
+    format!("{}/{}/subject.json", base_url, board_id)
+}
+
+// 板の設定 (SETTING.TXT) の URL
+pub fn board_setting_url(base_url: &str, board_id: &str) -> String {
+    format!("{}/{}/SETTING.TXT", base_url, board_id)
+}
+
+// 書き込み (bbs.cgi) の URL。サーバーごとに1つ
+pub fn board_bbs_cgi_url(base_url: &str) -> String {
+    format!("{}/test/bbs.cgi", base_url)
+}
+
+// スレッドを開く (read.cgi) URL。書き込み時の Referer に使う
+pub fn board_read_cgi_url(base_url: &str, board_id: &str, thread_id: &str) -> String {
+    format!("{}/test/read.cgi/{}/{}/", base_url, board_id, thread_id)
+}
+
+// 任意のサーバー・板の dat の URL (BoardClient 用)
+pub fn board_dat_url(base_url: &str, board_id: &str, thread_id: &str) -> Result<String, String> {
+    if thread_id.is_empty() {
+        return Err("スレッドIDが指定されていません。".to_string());
+    }
+    // スレッドキーは9桁以上の数字 (UNIX時刻)。数字でないとフォルダ名を切り出せない
+    if thread_id.len() < 9 || !thread_id.bytes().all(|b| b.is_ascii_digit()) {
+        return Err(format!("スレッドID '{}' が不正です。", thread_id));
+    }
+    let dir_prefix = &thread_id[0..4];
+    Ok(format!(
+        "{}/{}/thread/{}/{}.dat",
+        base_url, board_id, dir_prefix, thread_id
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn dat_url_uses_first_four_digits_as_folder() {
+        assert_eq!(
+            board_dat_url("https://example.com", "news", "1700000000").unwrap(),
+            "https://example.com/news/thread/1700/1700000000.dat"
+        );
+    }
+
+    #[test]
+    fn dat_url_rejects_non_numeric_or_short_ids() {
+        for thread_id in [
+            "",
+            "あい",
+            "あいうえお",
+            "1234",
+            "17000000a0",
+            "１７００００００００",
+        ] {
+            assert!(board_dat_url("https://example.com", "news", thread_id).is_err());
+        }
+    }
+}
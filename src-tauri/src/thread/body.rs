@@ -0,0 +1,112 @@
+use html_escape::decode_html_entities;
+use regex::Regex;
+use std::sync::OnceLock;
+
+// 1つのアンカーの範囲指定 (>>1-1000 など) で展開するレス数の上限
+const MAX_ANCHOR_RANGE: u32 = 100;
+
+// 本文 (dat の HTML) から、NG 判定や検索に使うプレーンテキストを作る
+// <br> は改行に、その他のタグは取り除き、HTMLエンティティをデコードする
+pub fn plain_text(content: &str) -> String {
+    let mut text = String::with_capacity(content.len());
+    let mut rest = content;
+    while let Some(tag_start) = rest.find('<') {
+        text.push_str(&rest[..tag_start]);
+        let after = &rest[tag_start..];
+        let tag_end = match after.find('>') {
+            Some(pos) => pos,
+            None => {
+                // 閉じていない '<' はそのまま文字として扱う
+                text.push_str(after);
+                rest = "";
+                break;
+            }
+        };
+        let tag = &after[1..tag_end];
+        if tag
+            .trim()
+            .trim_end_matches('/')
+            .trim()
+            .eq_ignore_ascii_case("br")
+        {
+            text.push('\n');
+        }
+        rest = &after[tag_end + 1..];
+    }
+    text.push_str(rest);
+    decode_html_entities(text.trim()).into_owned()
+}
+
+// 本文中のアンカー (>>1, >>1-3, >>1,5 など) が指すレス番号を、出現順・重複なしで返す
+// > が1つだけのもの (>5人いた などの引用) はアンカーとみなさない
+pub fn parse_anchors(content: &str) -> Vec<u32> {
+    static ANCHOR_RE: OnceLock<Regex> = OnceLock::new();
+    static NUMBER_RE: OnceLock<Regex> = OnceLock::new();
+    let anchor_re = ANCHOR_RE.get_or_init(|| {
+        Regex::new(r"(?:>>|＞＞|>＞|＞>)\s*(\d+(?:\s*-\s*\d+)?(?:\s*,\s*\d+(?:\s*-\s*\d+)?)*)")
+            .expect("アンカーの正規表現が不正です")
+    });
+    let number_re = NUMBER_RE
+        .get_or_init(|| Regex::new(r"(\d+)(?:\s*-\s*(\d+))?").expect("正規表現が不正です"));
+
+    let text = plain_text(content);
+    let mut anchors: Vec<u32> = Vec::new();
+    for anchor in anchor_re.captures_iter(&text) {
+        for part in number_re.captures_iter(&anchor[1]) {
+            let Ok(start) = part[1].parse::<u32>() else {
+                continue;
+            };
+            let end = part
+                .get(2)
+                .and_then(|m| m.as_str().parse::<u32>().ok())
+                .unwrap_or(start)
+                .max(start)
+                .min(start.saturating_add(MAX_ANCHOR_RANGE - 1));
+            for number in start..=end {
+                if number > 0 && !anchors.contains(&number) {
+                    anchors.push(number);
+                }
+            }
+        }
+    }
+    anchors
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn single_quote_mark_is_not_an_anchor() {
+        assert!(parse_anchors("&gt;1").is_empty());
+        assert!(parse_anchors("&gt;5人いた <br> ＞3 もそう").is_empty());
+    }
+
+    #[test]
+    fn anchor_ranges_and_lists_are_expanded() {
+        assert_eq!(parse_anchors("&gt;&gt;1-3"), vec![1, 2, 3]);
+        assert_eq!(parse_anchors("&gt;&gt;1,5"), vec![1, 5]);
+        assert_eq!(
+            parse_anchors("&gt;&gt;2 &gt;&gt;2 &gt;&gt;4, 3-3"),
+            vec![2, 4, 3]
+        );
+    }
+
+    #[test]
+    fn full_width_anchor_marks_are_accepted() {
+        assert_eq!(parse_anchors("＞＞1"), vec![1]);
+        assert_eq!(parse_anchors(">＞2 と ＞>3"), vec![2, 3]);
+        assert_eq!(
+            parse_anchors("<a href=\"../test/read.cgi/b/1/4\">&gt;&gt;4</a>"),
+            vec![4]
+        );
+    }
+
+    #[test]
+    fn huge_ranges_are_capped() {
+        assert_eq!(
+            parse_anchors("&gt;&gt;1-100000").len(),
+            MAX_ANCHOR_RANGE as usize
+        );
+    }
+}
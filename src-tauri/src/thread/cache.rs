@@ -0,0 +1,86 @@
+use std::collections::{HashMap, VecDeque};
+use std::sync::{Arc, Mutex};
+
+use super::dat::ResponseItem;
+
+// メモリ上に保持しておくスレッド数の上限 (古いものから捨てる)
+const MAX_CACHED_THREADS: usize = 32;
+
+// パース済みスレッドのキャッシュ (Tauri の State として管理する)
+// 範囲指定でレスを取り出すコマンドは、毎回ダウンロードせずにここから切り出す
+#[derive(Default)]
+pub struct ThreadCache {
+    inner: Mutex<CacheInner>,
+}
+
+#[derive(Default)]
+struct CacheInner {
+    threads: HashMap<String, Arc<Vec<ResponseItem>>>,
+    order: VecDeque<String>, // 先頭ほど古い
+}
+
+impl ThreadCache {
+    pub fn get(&self, thread_id: &str) -> Option<Arc<Vec<ResponseItem>>> {
+        let mut inner = self.inner.lock().ok()?;
+        let responses = inner.threads.get(thread_id).cloned()?;
+        inner.touch(thread_id);
+        Some(responses)
+    }
+
+    pub fn insert(&self, thread_id: &str, responses: Vec<ResponseItem>) -> Arc<Vec<ResponseItem>> {
+        let responses = Arc::new(responses);
+        if let Ok(mut inner) = self.inner.lock() {
+            inner
+                .threads
+                .insert(thread_id.to_string(), Arc::clone(&responses));
+            inner.touch(thread_id);
+            while inner.order.len() > MAX_CACHED_THREADS {
+                if let Some(oldest) = inner.order.pop_front() {
+                    inner.threads.remove(&oldest);
+                }
+            }
+        }
+        responses
+    }
+
+    // ログDBのスレッドが書き換わったときに呼ぶ (次に読むときはログDBか取得し直したものを使う)
+    pub fn invalidate(&self, thread_id: &str) {
+        if let Ok(mut inner) = self.inner.lock() {
+            inner.threads.remove(thread_id);
+            inner.order.retain(|id| id != thread_id);
+        }
+    }
+}
+
+impl CacheInner {
+    // 最近使ったスレッドを末尾へ移動する
+    fn touch(&mut self, thread_id: &str) {
+        self.order.retain(|id| id != thread_id);
+        self.order.push_back(thread_id.to_string());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn invalidate_drops_only_that_thread() {
+        let cache = ThreadCache::default();
+        cache.insert("1000000001", Vec::new());
+        cache.insert("1000000002", Vec::new());
+        cache.invalidate("1000000001");
+        assert!(cache.get("1000000001").is_none());
+        assert!(cache.get("1000000002").is_some());
+    }
+
+    #[test]
+    fn oldest_thread_is_evicted() {
+        let cache = ThreadCache::default();
+        for n in 0..=MAX_CACHED_THREADS {
+            cache.insert(&n.to_string(), Vec::new());
+        }
+        assert!(cache.get("0").is_none());
+        assert!(cache.get(&MAX_CACHED_THREADS.to_string()).is_some());
+    }
+}
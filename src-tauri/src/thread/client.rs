@@ -0,0 +1,115 @@
+use super::board::{
+    board_bbs_cgi_url, board_dat_url, board_read_cgi_url, board_setting_url, board_subject_url,
+    BoardEncoding, BOARD_BASE_URL, BOARD_ENCODING, BOARD_ID,
+};
+use super::dat::{decode_dat_bytes, parse_dat, parse_dat_title, ResponseItem};
+use super::fetch::{fetch_dat_at, FetchedDat};
+use super::setting::{fetch_setting, BoardSetting};
+use super::subject::{fetch_subject, SubjectEntry};
+
+// 1つの板に対するクライアント (Tauri を使わずにスレッド一覧や dat を取得する)
+// Default はアプリが対応している板。テストでは new でローカルのサーバーを指せる
+// 通信には Cookie を共有する HTTP クライアント (PostSession::http) を渡す
+#[derive(Debug, Clone)]
+pub struct BoardClient {
+    base_url: String, // 例: "https://tulipplantation.com" (末尾の / は付けない)
+    board_id: String,
+    encoding: BoardEncoding, // 書き込みフォームの文字コード
+}
+
+impl Default for BoardClient {
+    fn default() -> Self {
+        Self::new(BOARD_BASE_URL, BOARD_ID).with_encoding(BOARD_ENCODING)
+    }
+}
+
+// 取得してパースしたスレッド
+#[derive(Debug, Clone)]
+pub struct FetchedThread {
+    pub title: Option<String>,
+    pub responses: Vec<ResponseItem>,
+    pub dat: FetchedDat, // 取得した dat そのもの (差分取得やログ保存に使う)
+}
+
+impl BoardClient {
+    pub fn new(base_url: &str, board_id: &str) -> Self {
+        Self {
+            base_url: base_url.trim_end_matches('/').to_string(),
+            board_id: board_id.to_string(),
+            encoding: BoardEncoding::Utf8,
+        }
+    }
+
+    pub fn with_encoding(mut self, encoding: BoardEncoding) -> Self {
+        self.encoding = encoding;
+        self
+    }
+
+    pub fn base_url(&self) -> &str {
+        &self.base_url
+    }
+
+    pub fn board_id(&self) -> &str {
+        &self.board_id
+    }
+
+    pub fn encoding(&self) -> BoardEncoding {
+        self.encoding
+    }
+
+    pub fn bbs_cgi_url(&self) -> String {
+        board_bbs_cgi_url(&self.base_url)
+    }
+
+    pub fn read_cgi_url(&self, thread_id: &str) -> String {
+        board_read_cgi_url(&self.base_url, &self.board_id, thread_id)
+    }
+
+    pub fn subject_url(&self) -> String {
+        board_subject_url(&self.base_url, &self.board_id)
+    }
+
+    pub fn setting_url(&self) -> String {
+        board_setting_url(&self.base_url, &self.board_id)
+    }
+
+    pub fn dat_url(&self, thread_id: &str) -> Result<String, String> {
+        board_dat_url(&self.base_url, &self.board_id, thread_id)
+    }
+
+    // スレッド一覧 (subject.json) を取得する
+    pub async fn fetch_subject(&self, http: &reqwest::Client) -> Result<Vec<SubjectEntry>, String> {
+        fetch_subject(http, &self.subject_url()).await
+    }
+
+    // 板の設定 (SETTING.TXT) を取得する
+    pub async fn fetch_setting(&self, http: &reqwest::Client) -> Result<BoardSetting, String> {
+        fetch_setting(http, &self.setting_url()).await
+    }
+
+    // dat を取得する。previous を渡すと差分だけを取得して連結する
+    pub async fn fetch_dat(
+        &self,
+        http: &reqwest::Client,
+        thread_id: &str,
+        previous: Option<Vec<u8>>,
+    ) -> Result<FetchedDat, String> {
+        fetch_dat_at(http, &self.dat_url(thread_id)?, previous).await
+    }
+
+    // dat を取得してパースする
+    pub async fn fetch_thread(
+        &self,
+        http: &reqwest::Client,
+        thread_id: &str,
+        previous: Option<Vec<u8>>,
+    ) -> Result<FetchedThread, String> {
+        let dat = self.fetch_dat(http, thread_id, previous).await?;
+        let (text, _) = decode_dat_bytes(&dat.bytes);
+        Ok(FetchedThread {
+            title: parse_dat_title(&text),
+            responses: parse_dat(&text),
+            dat,
+        })
+    }
+}
@@ -0,0 +1,375 @@
+use chrono::{NaiveDate, TimeZone};
+use chrono_tz::Asia::Tokyo;
+use encoding_rs::SHIFT_JIS;
+use regex::Regex;
+use serde::Serialize;
+use std::collections::HashMap;
+use std::sync::OnceLock;
+
+use crate::ng::filter::NgMatch;
+
+// レスポンスアイテムの構造体
+#[derive(Debug, Serialize, Clone)] // フロントエンドに渡すので Serialize は必須
+pub struct ResponseItem {
+    pub id: String,           // レス番号 (例: "1", "2")
+    pub author: String,       // 名前欄
+    pub mail: String,         // メール欄
+    pub created_at: String,   // パースされた日付部分の文字列
+    pub user_id_info: String, // "ID:xxxx主" のような、表示用のID文字列全体
+    pub content: String,      // 本文 (HTMLが含まれる)
+
+    // ★★★ IDカウンター用に新しいフィールドを追加 ★★★
+    pub parsed_user_id: Option<String>, // パースされた実際のID部分 (例: "R780OCsAQ")、IDがない場合は None
+    pub id_occurrence_count: u32,       // このレスが、このIDによる何回目の投稿か
+    pub id_total_count: u32,            // このIDがこのスレッドで行った総投稿数
+
+    // NGルールに一致した場合はそのルール。レス番号やアンカーを保つため、レス自体は消さない
+    pub ng: Option<NgMatch>,
+
+    // 自分の書き込み (書き込み履歴と照合できたレス)
+    pub is_mine: bool,
+}
+
+#[derive(Debug)]
+struct TempResponseData {
+    name: String,
+    mail: String,
+    date_str: String,               // パースされた日付部分
+    user_id_info: String,           // IDなどを含む文字列全体
+    parsed_user_id: Option<String>, // パースされた実際のID
+    body: String,
+}
+
+// dat 全体をパースして ResponseItem の一覧を返す
+pub fn parse_dat(content_str: &str) -> Vec<ResponseItem> {
+    let mut temp_responses: Vec<TempResponseData> = Vec::new();
+    let mut id_total_counts: HashMap<String, u32> = HashMap::new();
+
+    // 1回目のパース: 基本情報抽出とIDの総出現回数のカウント
+    for line in content_str.lines() {
+        if let Some(temp_res) = parse_dat_line(line) {
+            if let Some(ref id) = temp_res.parsed_user_id {
+                *id_total_counts.entry(id.clone()).or_insert(0) += 1;
+            }
+            temp_responses.push(temp_res);
+        }
+    }
+
+    let mut final_responses: Vec<ResponseItem> = Vec::new();
+    let mut id_current_occurrences: HashMap<String, u32> = HashMap::new();
+
+    // 2回目の処理: ResponseItem の作成と、IDの現在の出現回数のカウント
+    for (index, temp_res) in temp_responses.into_iter().enumerate() {
+        let mut occurrence = 0;
+        let mut total = 0;
+
+        if let Some(ref parsed_id_val) = temp_res.parsed_user_id {
+            let current_count_for_id = id_current_occurrences
+                .entry(parsed_id_val.clone())
+                .or_insert(0);
+            *current_count_for_id += 1;
+            occurrence = *current_count_for_id;
+            total = *id_total_counts.get(parsed_id_val).unwrap_or(&0); // total_counts には必ずあるはず
+        }
+
+        final_responses.push(temp_res.into_response_item(index + 1, occurrence, total));
+    }
+
+    final_responses
+}
+
+// ファイルから読んだ dat を文字列にする。UTF-8 として正しくなければ Shift_JIS とみなす
+// 戻り値の2つ目は判定したエンコーディング名
+pub fn decode_dat_bytes(bytes: &[u8]) -> (String, &'static str) {
+    match std::str::from_utf8(bytes) {
+        Ok(text) => (text.to_string(), "UTF-8"),
+        Err(_) => {
+            let (text, _, _) = SHIFT_JIS.decode(bytes);
+            (text.into_owned(), "Shift_JIS")
+        }
+    }
+}
+
+// dat の1行目の5番目の要素 (スレッドタイトル) を取り出す
+pub fn parse_dat_title(content_str: &str) -> Option<String> {
+    let first_line = content_str.lines().next()?;
+    let title = first_line.splitn(5, "<>").nth(4)?.trim();
+    if title.is_empty() {
+        None
+    } else {
+        Some(title.to_string())
+    }
+}
+
+// 受信途中の dat を少しずつパースするためのパーサー
+// 行の途中で切れたバイト列は次のチャンクが来るまで保持する。
+// ID の総投稿数はスレッド全体を読むまで確定しないため、途中のレスには
+// その時点までの出現回数を総数として入れ、最後に id_total_counts() で確定値を返す。
+#[derive(Debug, Default)]
+pub struct DatStreamParser {
+    pending: Vec<u8>,
+    next_number: usize,
+    id_counts: HashMap<String, u32>,
+}
+
+impl DatStreamParser {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    // 受信したチャンクを追加し、完結した行から作れたレスを返す
+    // 改行は今回のチャンクの分だけを探し、処理した行はまとめて一度だけ取り除く
+    pub fn push(&mut self, chunk: &[u8]) -> Vec<ResponseItem> {
+        let mut pending = std::mem::take(&mut self.pending);
+        let search_from = pending.len();
+        pending.extend_from_slice(chunk);
+        let mut responses = Vec::new();
+        let mut line_start = 0;
+        for newline_pos in search_from..pending.len() {
+            if pending[newline_pos] != b'\n' {
+                continue;
+            }
+            if let Some(item) = self.parse_line_bytes(&pending[line_start..newline_pos]) {
+                responses.push(item);
+            }
+            line_start = newline_pos + 1;
+        }
+        pending.drain(..line_start);
+        self.pending = pending;
+        responses
+    }
+
+    // 末尾に改行がないまま残った行を処理する
+    pub fn finish(&mut self) -> Vec<ResponseItem> {
+        let rest = std::mem::take(&mut self.pending);
+        self.parse_line_bytes(&rest).into_iter().collect()
+    }
+
+    // スレッド全体での ID ごとの総投稿数
+    pub fn id_total_counts(&self) -> &HashMap<String, u32> {
+        &self.id_counts
+    }
+
+    pub fn response_count(&self) -> usize {
+        self.next_number
+    }
+
+    fn parse_line_bytes(&mut self, line_bytes: &[u8]) -> Option<ResponseItem> {
+        // 改行で区切ってから文字コードを判定するので、チャンクの境目で文字が切れていても正しく読める
+        let (line, _) = decode_dat_bytes(line_bytes);
+        let temp_res = parse_dat_line(line.trim_end_matches(['\r', '\n']))?;
+
+        let mut occurrence = 0;
+        if let Some(ref parsed_id_val) = temp_res.parsed_user_id {
+            let count = self.id_counts.entry(parsed_id_val.clone()).or_insert(0);
+            *count += 1;
+            occurrence = *count;
+        }
+        self.next_number += 1;
+        Some(temp_res.into_response_item(self.next_number, occurrence, occurrence))
+    }
+}
+
+// ID ごとの何回目の投稿かと総投稿数を数え直す (ログDBから読んだレスなど、パースを経ないレス向け)
+pub fn count_id_occurrences(responses: &mut [ResponseItem]) {
+    let mut id_counts: HashMap<String, u32> = HashMap::new();
+    for response in responses.iter_mut() {
+        if let Some(ref parsed_id_val) = response.parsed_user_id {
+            let count = id_counts.entry(parsed_id_val.clone()).or_insert(0);
+            *count += 1;
+            response.id_occurrence_count = *count;
+        }
+    }
+    apply_id_total_counts(responses, &id_counts);
+}
+
+// ストリーミングで集めたレスの ID 総投稿数を確定値で上書きする
+pub fn apply_id_total_counts(
+    responses: &mut [ResponseItem],
+    id_total_counts: &HashMap<String, u32>,
+) {
+    for response in responses.iter_mut() {
+        if let Some(ref parsed_id_val) = response.parsed_user_id {
+            response.id_total_count = *id_total_counts.get(parsed_id_val).unwrap_or(&0);
+        }
+    }
+}
+
+impl TempResponseData {
+    fn into_response_item(self, number: usize, occurrence: u32, total: u32) -> ResponseItem {
+        ResponseItem {
+            id: number.to_string(),
+            author: self.name,
+            mail: self.mail,
+            created_at: self.date_str,
+            user_id_info: self.user_id_info,
+            content: self.body,
+            parsed_user_id: self.parsed_user_id, // これも渡す
+            id_occurrence_count: occurrence,
+            id_total_count: total,
+            ng: None,
+            is_mine: false,
+        }
+    }
+}
+
+// dat の1行 (名前<>メール<>日付とID<>本文<>スレタイ) をパースする
+fn parse_dat_line(line: &str) -> Option<TempResponseData> {
+    if line.trim().is_empty() {
+        return None;
+    }
+    let parts: Vec<&str> = line.splitn(5, "<>").collect();
+    if parts.len() < 4 {
+        /* ... パース失敗ログ ... */
+        return None;
+    }
+    let name = parts[0].to_string();
+    let mail = parts[1].to_string();
+    let date_and_id_full = parts[2].to_string();
+    let body = parts[3].to_string();
+
+    let mut date_str = date_and_id_full.clone();
+    let mut user_id_info_str = "".to_string();
+    if let Some(id_pos) = date_and_id_full.rfind(" ID:") {
+        date_str = date_and_id_full[..id_pos].trim().to_string();
+        user_id_info_str = date_and_id_full[id_pos..].trim().to_string();
+    } else { /* ... 他の heuristic ... */
+    }
+
+    let parsed_id = parse_actual_id_from_info_str(&user_id_info_str);
+
+    Some(TempResponseData {
+        name,
+        mail,
+        date_str,
+        user_id_info: user_id_info_str,
+        parsed_user_id: parsed_id,
+        body,
+    })
+}
+
+// 名前欄からトリップ (◆の後ろの部分) を取り出す
+// 名前欄は "名無し </b>◆AbCdEfGhIj <b>" のようにタグを含むことがある
+pub fn parse_trip(author: &str) -> Option<String> {
+    let trip_start = author.find('◆')? + '◆'.len_utf8();
+    let after_mark = &author[trip_start..];
+    let trip_end = after_mark
+        .find(|c: char| c.is_whitespace() || c == '<')
+        .unwrap_or(after_mark.len());
+    let trip = &after_mark[..trip_end];
+    if trip.is_empty() {
+        None
+    } else {
+        Some(trip.to_string())
+    }
+}
+
+// レスの日付 ("2024/05/01(水) 12:34:56.78" など、日本時間) を Unixタイムスタンプにする
+pub fn parse_response_timestamp(created_at: &str) -> Option<i64> {
+    static DATE_RE: OnceLock<Regex> = OnceLock::new();
+    let caps = DATE_RE
+        .get_or_init(|| {
+            Regex::new(r"(\d{4})/(\d{1,2})/(\d{1,2})\D*?(\d{1,2}):(\d{2})(?::(\d{2}))?")
+                .expect("日付の正規表現が不正です")
+        })
+        .captures(created_at)?;
+    let number = |index: usize| caps.get(index).map_or(Some(0), |m| m.as_str().parse().ok());
+    let date = NaiveDate::from_ymd_opt(number(1)? as i32, number(2)?, number(3)?)?;
+    let datetime = date.and_hms_opt(number(4)?, number(5)?, number(6)?)?;
+    Tokyo
+        .from_local_datetime(&datetime)
+        .single()
+        .map(|datetime| datetime.timestamp())
+}
+
+fn parse_actual_id_from_info_str(user_id_info_str: &str) -> Option<String> {
+    if let Some(id_start_idx) = user_id_info_str.find("ID:") {
+        let after_id_colon = &user_id_info_str[id_start_idx + 3..];
+        // ID部分の終わりを見つける (スペース、(、[ などが区切りになることが多い)
+        let id_end_idx = after_id_colon
+            .find(|c: char| !c.is_alphanumeric() && c != '-' && c != '_')
+            .unwrap_or(after_id_colon.len());
+        let actual_id = after_id_colon[..id_end_idx].to_string();
+        if !actual_id.is_empty() {
+            return Some(actual_id);
+        }
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const LINE_1: &str =
+        "名無しさん<>sage<>2024/05/01(水) 12:34:56.78 ID:AbCd1234<> 最初のレス <>テストスレ\n";
+    const LINE_2: &str = "名無しさん<><>2024/05/01(水) 12:35:00.00 ID:AbCd1234<> 二つ目 <>\n";
+
+    fn push_chunks(parser: &mut DatStreamParser, chunks: &[&[u8]]) -> Vec<ResponseItem> {
+        let mut responses = Vec::new();
+        for chunk in chunks {
+            responses.extend(parser.push(chunk));
+        }
+        responses.extend(parser.finish());
+        responses
+    }
+
+    #[test]
+    fn stream_parser_keeps_line_split_across_chunks() {
+        let dat = format!("{}{}", LINE_1, LINE_2);
+        let split = LINE_1.len() + 10;
+        let mut parser = DatStreamParser::new();
+        assert_eq!(parser.push(&dat.as_bytes()[..5]).len(), 0);
+        let first = parser.push(&dat.as_bytes()[5..split]);
+        assert_eq!(first.len(), 1);
+        assert_eq!(first[0].content, " 最初のレス ");
+        let rest = parser.push(&dat.as_bytes()[split..]);
+        assert_eq!(rest.len(), 1);
+        assert_eq!(rest[0].id, "2");
+        assert_eq!(rest[0].id_occurrence_count, 2);
+        assert!(parser.finish().is_empty());
+        assert_eq!(parser.id_total_counts().get("AbCd1234"), Some(&2));
+    }
+
+    #[test]
+    fn stream_parser_decodes_shift_jis_split_inside_character() {
+        let (bytes, _, _) = SHIFT_JIS.encode(LINE_1);
+        // 「名」の1バイト目と2バイト目の間で切る
+        let split = 1;
+        assert!(bytes[0] >= 0x81);
+        let mut parser = DatStreamParser::new();
+        let responses = push_chunks(&mut parser, &[&bytes[..split], &bytes[split..]]);
+        assert_eq!(responses.len(), 1);
+        assert_eq!(responses[0].author, "名無しさん");
+        assert_eq!(responses[0].content, " 最初のレス ");
+    }
+
+    #[test]
+    fn stream_parser_handles_crlf_split_between_chunks() {
+        let dat = format!("{}{}", LINE_1, LINE_2).replace('\n', "\r\n");
+        let split = dat.find('\r').unwrap() + 1;
+        let mut parser = DatStreamParser::new();
+        assert!(parser.push(&dat.as_bytes()[..split]).is_empty());
+        let responses = push_chunks(&mut parser, &[&dat.as_bytes()[split..]]);
+        assert_eq!(responses.len(), 2);
+        assert_eq!(responses[0].user_id_info, "ID:AbCd1234");
+        assert_eq!(responses[1].content, " 二つ目 ");
+    }
+
+    #[test]
+    fn stream_parser_matches_parse_dat_byte_by_byte() {
+        let dat = format!("{}{}{}", LINE_1, LINE_2, LINE_2.trim_end());
+        let mut parser = DatStreamParser::new();
+        let chunks: Vec<&[u8]> = dat.as_bytes().chunks(1).collect();
+        let mut streamed = push_chunks(&mut parser, &chunks);
+        apply_id_total_counts(&mut streamed, parser.id_total_counts());
+        let parsed = parse_dat(&dat);
+        assert_eq!(streamed.len(), 3);
+        for (streamed, parsed) in streamed.iter().zip(&parsed) {
+            assert_eq!(streamed.id, parsed.id);
+            assert_eq!(streamed.content, parsed.content);
+            assert_eq!(streamed.id_occurrence_count, parsed.id_occurrence_count);
+            assert_eq!(streamed.id_total_count, parsed.id_total_count);
+        }
+    }
+}
@@ -0,0 +1,374 @@
+use encoding_rs::SHIFT_JIS;
+use html_escape::{encode_double_quoted_attribute, encode_text};
+use regex::{Captures, Regex};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::Path;
+#[cfg(feature = "tauri")]
+use tauri::State;
+
+use super::board::{dat_url, BOARD_ID};
+use super::body::plain_text;
+use super::cache::ThreadCache;
+use super::dat::{decode_dat_bytes, parse_dat_title, ResponseItem};
+use super::responses::{cached_or_download, download_image_as_data_url};
+use crate::ng::filter::load_ng_filter;
+#[cfg(feature = "tauri")]
+use crate::post::submit::PostSession;
+use crate::store::log_store::LogStore;
+
+// NGを適用した場合に、NGレスの代わりに出力する文字列
+const MUTED_TEXT: &str = "あぼーん";
+
+#[derive(Debug, Deserialize, Clone, Copy)]
+#[serde(rename_all = "snake_case")]
+pub enum ExportFormat {
+    Html,     // 画像を埋め込んだ単独のHTML
+    Markdown, // Wiki などに貼り付ける用
+    Json,     // ResponseItem の一覧とメタデータ
+    Dat,      // 取得したままの dat
+}
+
+// JSON で書き出す内容
+#[derive(Debug, Serialize)]
+struct ExportedThread<'a> {
+    board: &'a str,
+    thread_id: &'a str,
+    title: &'a str,
+    source_url: String,
+    exported_at: String,
+    ng_applied: bool,
+    response_count: usize,
+    responses: &'a [ResponseItem],
+}
+
+// スレッドを指定した形式でファイルに書き出す。戻り値は書き出したパス
+#[cfg(feature = "tauri")]
+#[tauri::command]
+pub async fn export_thread(
+    thread_id: String,
+    format: ExportFormat,
+    path: String,
+    apply_ng: bool,
+    session: State<'_, PostSession>,
+    cache: State<'_, ThreadCache>,
+    store: State<'_, LogStore>,
+) -> Result<String, String> {
+    eprintln!(
+        "[Rust export_thread] スレッドを書き出します (ID: {}, 形式: {:?}, NG適用: {}): {}",
+        thread_id, format, apply_ng, path
+    );
+    let written = export_thread_to_file(
+        session.http(),
+        &thread_id,
+        format,
+        Path::new(&path),
+        apply_ng,
+        &cache,
+        &store,
+    )
+    .await?;
+    eprintln!(
+        "[Rust export_thread] {} バイトを書き出しました: {}",
+        written, path
+    );
+    Ok(path)
+}
+
+// export_thread の本体 (Tauri なしでも使える)。戻り値は書き出したバイト数
+pub async fn export_thread_to_file(
+    http: &reqwest::Client,
+    thread_id: &str,
+    format: ExportFormat,
+    output_path: &Path,
+    apply_ng: bool,
+    cache: &ThreadCache,
+    store: &LogStore,
+) -> Result<usize, String> {
+    if let Some(parent) = output_path.parent().filter(|p| !p.as_os_str().is_empty()) {
+        if !parent.is_dir() {
+            return Err(format!(
+                "書き出し先のフォルダ '{}' が存在しません。",
+                parent.display()
+            ));
+        }
+    }
+
+    // 保存済みのログがなければ取得する (取得したものはログDBに保存される)
+    let responses = cached_or_download(http, thread_id, cache, store).await?;
+    let dat = store
+        .load_dat(BOARD_ID, thread_id)?
+        .ok_or_else(|| format!("スレッド {} のログが見つかりません。", thread_id))?;
+    let title = parse_dat_title(&decode_dat_bytes(&dat).0).unwrap_or_default();
+
+    let mut responses = responses.as_ref().clone();
+    if apply_ng {
+        load_ng_filter(store, BOARD_ID, thread_id).apply(&mut responses);
+    }
+
+    let bytes: Vec<u8> = match format {
+        ExportFormat::Dat if apply_ng => mute_dat_lines(&dat, &responses),
+        ExportFormat::Dat => dat,
+        ExportFormat::Json => {
+            if apply_ng {
+                blank_muted_responses(&mut responses);
+            }
+            let exported = ExportedThread {
+                board: BOARD_ID,
+                thread_id,
+                title: &title,
+                source_url: dat_url(thread_id)?,
+                exported_at: chrono::Local::now().to_rfc3339(),
+                ng_applied: apply_ng,
+                response_count: responses.len(),
+                responses: &responses,
+            };
+            serde_json::to_vec_pretty(&exported)
+                .map_err(|e| format!("JSONへの変換に失敗しました: {}", e))?
+        }
+        ExportFormat::Markdown => render_markdown(thread_id, &title, &responses)?.into_bytes(),
+        ExportFormat::Html => render_html(http, thread_id, &title, &responses)
+            .await?
+            .into_bytes(),
+    };
+
+    std::fs::write(output_path, &bytes).map_err(|e| {
+        format!(
+            "'{}' への書き出しに失敗しました: {}",
+            output_path.display(),
+            e
+        )
+    })?;
+    Ok(bytes.len())
+}
+
+// NGレスの本文・名前・メール欄を消す (レス番号と一致したNGは残す)
+fn blank_muted_responses(responses: &mut [ResponseItem]) {
+    for response in responses.iter_mut().filter(|r| r.ng.is_some()) {
+        response.author = String::new();
+        response.mail = String::new();
+        response.content = MUTED_TEXT.to_string();
+    }
+}
+
+// NGレスの行を「あぼーん」に置き換えた dat を作る
+// レス番号がずれないよう行は消さず、1行目のスレッドタイトルと元の文字コードは残す
+fn mute_dat_lines(dat: &[u8], responses: &[ResponseItem]) -> Vec<u8> {
+    if responses.iter().all(|r| r.ng.is_none()) {
+        return dat.to_vec();
+    }
+    let (text, encoding) = decode_dat_bytes(dat);
+    let mut numbered = responses.iter();
+    let mut muted = String::with_capacity(text.len());
+    for (index, line) in text.split_inclusive('\n').enumerate() {
+        let body = line.trim_end_matches(['\r', '\n']);
+        // parse_dat と同じく、空行や要素の足りない行はレスとして数えない
+        let is_response = !body.trim().is_empty() && body.splitn(5, "<>").count() >= 4;
+        if !is_response || numbered.next().is_none_or(|r| r.ng.is_none()) {
+            muted.push_str(line);
+            continue;
+        }
+        let title = if index == 0 {
+            body.splitn(5, "<>").nth(4).unwrap_or_default()
+        } else {
+            ""
+        };
+        muted.push_str(&format!("{0}<>{0}<>{0}<>{0}<>{1}", MUTED_TEXT, title));
+        muted.push_str(&line[body.len()..]);
+    }
+    match encoding {
+        "Shift_JIS" => SHIFT_JIS.encode(&muted).0.into_owned(),
+        _ => muted.into_bytes(),
+    }
+}
+
+fn img_src_regex() -> Regex {
+    Regex::new(r#"(?i)(<img\b[^>]*?\bsrc=")([^"]+)(")"#).expect("正規表現が不正です")
+}
+
+// Markdown として特別な意味を持つ行頭の文字 (アンカーの >> など) をエスケープする
+fn escape_markdown_line(line: &str) -> String {
+    let trimmed = line.trim_start();
+    match trimmed.chars().next() {
+        Some('>' | '#' | '-' | '*' | '+' | '|') => format!("\\{}", trimmed),
+        _ => trimmed.to_string(),
+    }
+}
+
+fn render_markdown(
+    thread_id: &str,
+    title: &str,
+    responses: &[ResponseItem],
+) -> Result<String, String> {
+    let img_src = img_src_regex();
+    let mut markdown = format!("# {}\n\n", title);
+    markdown.push_str(&format!("- 取得元: {}\n", dat_url(thread_id)?));
+    markdown.push_str(&format!("- レス数: {}\n\n", responses.len()));
+
+    for response in responses {
+        if response.ng.is_some() {
+            markdown.push_str(&format!("**{}** {}\n\n", response.id, MUTED_TEXT));
+            continue;
+        }
+        markdown.push_str(&format!(
+            "**{}** {} {} {}\n\n",
+            response.id,
+            plain_text(&response.author),
+            response.created_at,
+            response.user_id_info
+        ));
+        let body = plain_text(&response.content);
+        let lines: Vec<String> = body.lines().map(escape_markdown_line).collect();
+        // 行末の空白2つで改行にする
+        markdown.push_str(&lines.join("  \n"));
+        markdown.push_str("\n\n");
+        for image in img_src.captures_iter(&response.content) {
+            markdown.push_str(&format!("![]({})\n\n", &image[2]));
+        }
+    }
+    Ok(markdown)
+}
+
+async fn render_html(
+    http: &reqwest::Client,
+    thread_id: &str,
+    title: &str,
+    responses: &[ResponseItem],
+) -> Result<String, String> {
+    let img_src = img_src_regex();
+
+    // 画像は data URL にして埋め込む (取得に失敗したものは元のURLのまま)
+    let mut embedded: HashMap<String, String> = HashMap::new();
+    for response in responses.iter().filter(|r| r.ng.is_none()) {
+        for image in img_src.captures_iter(&response.content) {
+            let url = image[2].to_string();
+            if embedded.contains_key(&url) || !url.starts_with("http") {
+                continue;
+            }
+            match download_image_as_data_url(http, &url).await {
+                Ok(data_url) => {
+                    embedded.insert(url, data_url);
+                }
+                Err(e) => eprintln!("[Rust export_thread] 画像を埋め込めませんでした: {}", e),
+            }
+        }
+    }
+
+    let mut html = String::new();
+    html.push_str("<!DOCTYPE html>\n<html lang=\"ja\">\n<head>\n<meta charset=\"UTF-8\" />\n");
+    html.push_str(&format!("<title>{}</title>\n", encode_text(title)));
+    html.push_str(
+        "<style>
+body { font-family: sans-serif; max-width: 960px; margin: 20px auto; padding: 0 10px; }
+.response { border-bottom: 1px solid #e0e0e0; padding: 10px 0; }
+.response-header { font-size: 0.85em; color: #555; margin-bottom: 6px; }
+.response-author { font-weight: bold; color: #2e7d32; }
+.response-content { line-height: 1.6; word-break: break-word; }
+.response-content img { max-width: 100%; }
+.muted { color: #999; }
+</style>\n</head>\n<body>\n",
+    );
+    html.push_str(&format!("<h1>{}</h1>\n", encode_text(title)));
+    let source_url = dat_url(thread_id)?;
+    html.push_str(&format!(
+        "<p>取得元: <a href=\"{}\">{}</a> / レス数: {}</p>\n",
+        encode_double_quoted_attribute(&source_url),
+        encode_text(&source_url),
+        responses.len()
+    ));
+
+    for response in responses {
+        html.push_str(&format!(
+            "<div class=\"response\" id=\"res{}\">\n",
+            response.id
+        ));
+        if response.ng.is_some() {
+            html.push_str(&format!(
+                "<div class=\"response-header muted\">{} {}</div>\n</div>\n",
+                response.id, MUTED_TEXT
+            ));
+            continue;
+        }
+        // 名前欄と本文は dat の HTML をそのまま使う
+        html.push_str(&format!(
+            "<div class=\"response-header\">{} <span class=\"response-author\">{}</span> {} {}</div>\n",
+            response.id,
+            response.author,
+            encode_text(&response.created_at),
+            encode_text(&response.user_id_info)
+        ));
+        let content = img_src.replace_all(&response.content, |caps: &Captures| {
+            let src = embedded
+                .get(&caps[2])
+                .cloned()
+                .unwrap_or_else(|| caps[2].to_string());
+            format!("{}{}{}", &caps[1], src, &caps[3])
+        });
+        html.push_str(&format!(
+            "<div class=\"response-content\">{}</div>\n</div>\n",
+            content
+        ));
+    }
+    html.push_str("</body>\n</html>\n");
+    Ok(html)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ng::filter::NgMatch;
+    use crate::ng::rules::NgTarget;
+    use crate::thread::dat::parse_dat;
+
+    const DAT: &str = "名無し<>sage<>2024/01/01(月) 00:00:00.00 ID:aaa<> 1 <>テストスレ\n\
+名無し<><>2024/01/01(月) 00:00:01.00 ID:bbb<> 2 <>\n\
+名無し<><>2024/01/01(月) 00:00:02.00 ID:ccc<> 3 <>\n";
+
+    fn mute(responses: &mut [ResponseItem], number: usize) {
+        responses[number - 1].ng = Some(NgMatch {
+            rule_id: 1,
+            target: NgTarget::Word,
+            pattern: "x".to_string(),
+            chain_depth: 0,
+            chain_from: None,
+        });
+    }
+
+    #[test]
+    fn dat_export_replaces_muted_lines_and_keeps_numbering() {
+        let mut responses = parse_dat(DAT);
+        mute(&mut responses, 1);
+        mute(&mut responses, 3);
+        let muted = String::from_utf8(mute_dat_lines(DAT.as_bytes(), &responses)).unwrap();
+        let lines: Vec<&str> = muted.lines().collect();
+        assert_eq!(lines.len(), 3);
+        assert_eq!(
+            lines[0],
+            "あぼーん<>あぼーん<>あぼーん<>あぼーん<>テストスレ"
+        );
+        assert_eq!(lines[1], DAT.lines().nth(1).unwrap());
+        assert_eq!(lines[2], "あぼーん<>あぼーん<>あぼーん<>あぼーん<>");
+        assert_eq!(parse_dat_title(&muted).as_deref(), Some("テストスレ"));
+    }
+
+    #[test]
+    fn dat_export_keeps_shift_jis_encoding() {
+        let (sjis, _, _) = SHIFT_JIS.encode(DAT);
+        let mut responses = parse_dat(DAT);
+        mute(&mut responses, 2);
+        let muted = mute_dat_lines(&sjis, &responses);
+        let (text, encoding) = decode_dat_bytes(&muted);
+        assert_eq!(encoding, "Shift_JIS");
+        assert_eq!(
+            text.lines().nth(1),
+            Some("あぼーん<>あぼーん<>あぼーん<>あぼーん<>")
+        );
+        assert_eq!(text.lines().next(), DAT.lines().next());
+    }
+
+    #[test]
+    fn dat_export_without_muted_responses_is_unchanged() {
+        let responses = parse_dat(DAT);
+        assert_eq!(mute_dat_lines(DAT.as_bytes(), &responses), DAT.as_bytes());
+    }
+}
@@ -0,0 +1,270 @@
+use reqwest::{header, StatusCode};
+
+use super::board::dat_url;
+
+// 差分取得で dat がどう変わったか
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DatUpdate {
+    Unchanged, // 新着なし
+    Appended,  // 末尾に新着レスが追加された
+    Replaced,  // 全体を取り直した (初回取得、あぼーん検出など)
+}
+
+#[derive(Debug, Clone)]
+pub struct FetchedDat {
+    pub bytes: Vec<u8>,
+    pub update: DatUpdate,
+}
+
+// fetch_dat のエラーが dat落ち (サーバーにスレッドが無い) によるものか
+pub fn is_dat_dropped(err: &str) -> bool {
+    err.starts_with(StatusCode::NOT_FOUND.as_str()) || err.starts_with(StatusCode::GONE.as_str())
+}
+
+// dat を取得するリクエストを送り、成功したレスポンスを返す
+pub async fn request_dat(
+    http: &reqwest::Client,
+    dat_file_url: &str,
+) -> Result<reqwest::Response, String> {
+    let response = match http.get(dat_file_url).send().await {
+        /* ... HTTP GET ... */ Ok(r) => r,
+        Err(e) => return Err(e.to_string()),
+    };
+    if !response.status().is_success() {
+        /* ... HTTPエラー処理 ... */
+        return Err(response.status().to_string());
+    }
+    Ok(response)
+}
+
+// dat を取得する。保存済みの dat (previous) があれば Range で差分だけを取得して連結する
+pub async fn fetch_dat(
+    http: &reqwest::Client,
+    thread_id: &str,
+    previous: Option<Vec<u8>>,
+) -> Result<FetchedDat, String> {
+    fetch_dat_at(http, &dat_url(thread_id)?, previous).await
+}
+
+// URL を指定して dat を取得する (fetch_dat の本体。BoardClient からも使う)
+pub async fn fetch_dat_at(
+    http: &reqwest::Client,
+    dat_file_url: &str,
+    previous: Option<Vec<u8>>,
+) -> Result<FetchedDat, String> {
+    let previous = match previous {
+        // 末尾が改行で終わっていない dat は差分の継ぎ目が分からないので取り直す
+        Some(previous) if previous.last() == Some(&b'\n') => previous,
+        _ => return fetch_full_dat(http, dat_file_url).await,
+    };
+
+    // 1バイト手前から要求し、先頭が改行であれば保存済みの部分は変わっていないとみなす
+    let response = http
+        .get(dat_file_url)
+        .header(header::RANGE, format!("bytes={}-", previous.len() - 1))
+        .send()
+        .await
+        .map_err(|e| e.to_string())?;
+
+    match response.status() {
+        StatusCode::PARTIAL_CONTENT => {
+            let body = response.bytes().await.map_err(|e| e.to_string())?;
+            if body.first() != Some(&b'\n') {
+                eprintln!(
+                    "[Rust fetch_dat] 保存済みの dat と一致しないため取り直します: {}",
+                    dat_file_url
+                );
+                return fetch_full_dat(http, dat_file_url).await;
+            }
+            if body.len() == 1 {
+                return Ok(FetchedDat {
+                    bytes: previous,
+                    update: DatUpdate::Unchanged,
+                });
+            }
+            let mut bytes = previous;
+            bytes.extend_from_slice(&body[1..]);
+            Ok(FetchedDat {
+                bytes,
+                update: DatUpdate::Appended,
+            })
+        }
+        // Range を無視して全体が返ってきた
+        StatusCode::OK => {
+            let bytes = response.bytes().await.map_err(|e| e.to_string())?.to_vec();
+            let update = if bytes == previous {
+                DatUpdate::Unchanged
+            } else {
+                DatUpdate::Replaced
+            };
+            Ok(FetchedDat { bytes, update })
+        }
+        // 保存済みより短くなっている (削除などで dat が作り直された)
+        StatusCode::RANGE_NOT_SATISFIABLE => fetch_full_dat(http, dat_file_url).await,
+        status => Err(status.to_string()),
+    }
+}
+
+async fn fetch_full_dat(http: &reqwest::Client, dat_file_url: &str) -> Result<FetchedDat, String> {
+    let response = request_dat(http, dat_file_url).await?;
+    let bytes = response.bytes().await.map_err(|e| e.to_string())?.to_vec();
+    Ok(FetchedDat {
+        bytes,
+        update: DatUpdate::Replaced,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::post::cookies::PersistentCookieJar;
+    use crate::post::submit::PostSession;
+    use crate::test_server::{Response, TestServer};
+    use std::sync::Arc;
+
+    const SAVED: &[u8] = b"a<><>d<> 1 <>title\nb<><>d<> 2 <>\n";
+    const NEW_LINE: &[u8] = b"c<><>d<> 3 <>\n";
+
+    fn full_dat() -> Vec<u8> {
+        [SAVED, NEW_LINE].concat()
+    }
+
+    fn http() -> reqwest::Client {
+        reqwest::Client::new()
+    }
+
+    // Range で要求された位置から返すサーバー
+    async fn range_server(dat: Vec<u8>) -> TestServer {
+        TestServer::start(move |request| match request.header("range") {
+            Some(range) => {
+                let start: usize = range
+                    .trim_start_matches("bytes=")
+                    .trim_end_matches('-')
+                    .parse()
+                    .unwrap();
+                if start >= dat.len() {
+                    Response::new(416, "")
+                } else {
+                    Response::new(206, dat[start..].to_vec())
+                }
+            }
+            None => Response::new(200, dat.clone()),
+        })
+        .await
+    }
+
+    #[tokio::test]
+    async fn range_request_appends_new_lines() {
+        let server = range_server(full_dat()).await;
+        let url = format!("{}/dat/1.dat", server.url());
+        let fetched = fetch_dat_at(&http(), &url, Some(SAVED.to_vec()))
+            .await
+            .unwrap();
+        assert_eq!(fetched.update, DatUpdate::Appended);
+        assert_eq!(fetched.bytes, full_dat());
+        let requests = server.requests();
+        assert_eq!(requests.len(), 1);
+        assert_eq!(requests[0].method, "GET");
+        assert_eq!(requests[0].path, "/dat/1.dat");
+        let expected_range = format!("bytes={}-", SAVED.len() - 1);
+        assert_eq!(requests[0].header("range"), Some(expected_range.as_str()));
+    }
+
+    #[tokio::test]
+    async fn range_request_with_only_newline_is_unchanged() {
+        let server = range_server(SAVED.to_vec()).await;
+        let url = format!("{}/dat/1.dat", server.url());
+        let fetched = fetch_dat_at(&http(), &url, Some(SAVED.to_vec()))
+            .await
+            .unwrap();
+        assert_eq!(fetched.update, DatUpdate::Unchanged);
+        assert_eq!(fetched.bytes, SAVED);
+    }
+
+    #[tokio::test]
+    async fn mismatched_range_refetches_whole_dat() {
+        // 保存済みの dat と中身が違う (あぼーんなどで書き換わった) 場合
+        let mut changed = b"x".to_vec();
+        changed.extend_from_slice(&full_dat());
+        let server = range_server(changed.clone()).await;
+        let url = format!("{}/dat/1.dat", server.url());
+        let fetched = fetch_dat_at(&http(), &url, Some(SAVED.to_vec()))
+            .await
+            .unwrap();
+        assert_eq!(fetched.update, DatUpdate::Replaced);
+        assert_eq!(fetched.bytes, changed);
+        let requests = server.requests();
+        assert_eq!(requests.len(), 2);
+        assert_eq!(requests[1].header("range"), None);
+    }
+
+    #[tokio::test]
+    async fn full_response_ignoring_range_is_compared() {
+        let dat = full_dat();
+        let server = TestServer::start(move |_| Response::new(200, dat.clone())).await;
+        let url = format!("{}/dat/1.dat", server.url());
+        let unchanged = fetch_dat_at(&http(), &url, Some(full_dat())).await.unwrap();
+        assert_eq!(unchanged.update, DatUpdate::Unchanged);
+        let replaced = fetch_dat_at(&http(), &url, Some(SAVED.to_vec()))
+            .await
+            .unwrap();
+        assert_eq!(replaced.update, DatUpdate::Replaced);
+        assert_eq!(replaced.bytes, full_dat());
+    }
+
+    #[tokio::test]
+    async fn range_not_satisfiable_refetches_whole_dat() {
+        // 保存済みより短くなった dat
+        let server = range_server(NEW_LINE.to_vec()).await;
+        let url = format!("{}/dat/1.dat", server.url());
+        let fetched = fetch_dat_at(&http(), &url, Some(SAVED.to_vec()))
+            .await
+            .unwrap();
+        assert_eq!(fetched.update, DatUpdate::Replaced);
+        assert_eq!(fetched.bytes, NEW_LINE);
+        assert_eq!(server.requests().len(), 2);
+    }
+
+    #[tokio::test]
+    async fn previous_without_trailing_newline_is_refetched() {
+        let server = range_server(full_dat()).await;
+        let url = format!("{}/dat/1.dat", server.url());
+        let fetched = fetch_dat_at(&http(), &url, Some(SAVED[..SAVED.len() - 1].to_vec()))
+            .await
+            .unwrap();
+        assert_eq!(fetched.update, DatUpdate::Replaced);
+        assert_eq!(server.requests()[0].header("range"), None);
+    }
+
+    #[tokio::test]
+    async fn missing_dat_is_reported_as_dropped() {
+        let server = TestServer::start(|_| Response::new(404, "")).await;
+        let url = format!("{}/dat/1.dat", server.url());
+        let err = fetch_dat_at(&http(), &url, Some(SAVED.to_vec()))
+            .await
+            .unwrap_err();
+        assert!(is_dat_dropped(&err), "{}", err);
+    }
+
+    // 書き込み確認などで受け取った Cookie は、同じクライアントでの dat の取得でも送る
+    #[tokio::test]
+    async fn shared_session_sends_cookies_with_dat_requests() {
+        let server = TestServer::start(|request| {
+            let mut response = Response::new(200, SAVED.to_vec());
+            if request.header("cookie").is_none() {
+                response
+                    .headers
+                    .push(("Set-Cookie".to_string(), "yuki=akari; Path=/".to_string()));
+            }
+            response
+        })
+        .await;
+        let session = PostSession::new(Arc::new(PersistentCookieJar::in_memory("test"))).unwrap();
+        let url = format!("{}/dat/1.dat", server.url());
+        fetch_dat_at(session.http(), &url, None).await.unwrap();
+        fetch_dat_at(session.http(), &url, None).await.unwrap();
+        let requests = server.requests();
+        assert_eq!(requests[0].header("cookie"), None);
+        assert_eq!(requests[1].header("cookie"), Some("yuki=akari"));
+    }
+}
@@ -0,0 +1,98 @@
+use serde::Serialize;
+use std::path::Path;
+#[cfg(feature = "tauri")]
+use tauri::State;
+
+use super::board::BOARD_ID;
+use super::dat::{decode_dat_bytes, parse_dat, parse_dat_title, ResponseItem};
+use crate::ng::filter::load_ng_filter;
+use crate::store::log_store::LogStore;
+
+// ディスク上の dat ファイルを読み込んだ結果
+// オンラインのスレッドと同じ ResponseItem で返すので、フロントエンドは同じ描画処理を使える
+#[derive(Debug, Serialize, Clone)]
+pub struct LocalThread {
+    pub path: String,
+    pub thread_id: String, // ファイル名から拡張子を除いたもの (通常はスレッドのキー)
+    pub title: String,
+    pub encoding: String, // "UTF-8" または "Shift_JIS"
+    pub responses: Vec<ResponseItem>,
+}
+
+// コマンドライン引数で渡された dat ファイル
+// 起動直後はまだフロントエンドが準備できていないので、フロントエンドから取りに来てもらう
+#[cfg(feature = "tauri")]
+#[derive(Debug, Default)]
+pub struct StartupDat(std::sync::Mutex<Option<String>>);
+
+#[cfg(feature = "tauri")]
+impl StartupDat {
+    // 引数のうち拡張子が .dat のものを最初の1つだけ覚えておく
+    pub fn from_args(args: impl Iterator<Item = String>) -> Self {
+        let path = args.skip(1).find(|arg| {
+            Path::new(arg)
+                .extension()
+                .is_some_and(|ext| ext.eq_ignore_ascii_case("dat"))
+        });
+        if let Some(path) = &path {
+            eprintln!("[Rust StartupDat] 起動時に開く dat ファイル: {}", path);
+        }
+        StartupDat(std::sync::Mutex::new(path))
+    }
+}
+
+// dat ファイルを読み込んでパースする (HTTP 取得やログDBを経由しない)
+pub fn read_local_dat(path: &Path) -> Result<LocalThread, String> {
+    let bytes = std::fs::read(path).map_err(|e| format!("{}: {}", path.display(), e))?;
+    let (text, encoding) = decode_dat_bytes(&bytes);
+    let responses = parse_dat(&text);
+    if responses.is_empty() {
+        return Err(format!(
+            "{}: dat として読み取れるレスがありません",
+            path.display()
+        ));
+    }
+    let thread_id = path
+        .file_stem()
+        .map(|stem| stem.to_string_lossy().into_owned())
+        .unwrap_or_default();
+    Ok(LocalThread {
+        path: path.to_string_lossy().into_owned(),
+        title: parse_dat_title(&text).unwrap_or_else(|| thread_id.clone()),
+        thread_id,
+        encoding: encoding.to_string(),
+        responses,
+    })
+}
+
+// dat ファイルを読み込み、オンラインのスレッドと同じく NG を適用する
+pub fn read_local_dat_with_ng(path: &Path, store: &LogStore) -> Result<LocalThread, String> {
+    let mut thread = read_local_dat(path)?;
+    load_ng_filter(store, BOARD_ID, &thread.thread_id).apply(&mut thread.responses);
+    Ok(thread)
+}
+
+// ローカルの dat ファイルを開く。NG はオンラインのスレッドと同じく表示時に適用する
+// 調べるためだけに開くことを想定し、ログDBや既読情報には保存しない
+#[cfg(feature = "tauri")]
+#[tauri::command]
+pub async fn open_local_dat(
+    path: String,
+    store: State<'_, LogStore>,
+) -> Result<LocalThread, String> {
+    eprintln!("[Rust open_local_dat] dat ファイルを開きます: {}", path);
+    let thread = read_local_dat_with_ng(Path::new(&path), &store)?;
+    eprintln!(
+        "[Rust open_local_dat] {} 個のレスを読み込みました ({})",
+        thread.responses.len(),
+        thread.encoding
+    );
+    Ok(thread)
+}
+
+// 起動時にコマンドライン引数で渡された dat ファイルのパスを返す (2回目以降は None)
+#[cfg(feature = "tauri")]
+#[tauri::command]
+pub fn take_startup_dat(startup: State<'_, StartupDat>) -> Option<String> {
+    startup.0.lock().ok().and_then(|mut path| path.take())
+}
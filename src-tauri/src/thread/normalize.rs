@@ -0,0 +1,173 @@
+use serde::{Deserialize, Serialize};
+use unicode_normalization::UnicodeNormalization;
+
+// NG・スレタイNG・全文検索で使う文字列の正規化の種類
+// NGルールはルールごとに、どの正規化を使うかを選べる
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, Default, PartialEq, Eq, Hash)]
+#[serde(default)]
+pub struct NormalizeOptions {
+    pub nfkc: bool,       // 互換文字の統一 (全角英数→半角、半角カナ→全角、① → 1 など)
+    pub kana: bool,       // カタカナをひらがなに揃える
+    pub case: bool,       // 大文字・小文字を区別しない
+    pub zero_width: bool, // ゼロ幅文字を取り除く
+}
+
+const FOLD_NFKC: i64 = 1;
+const FOLD_KANA: i64 = 1 << 1;
+const FOLD_CASE: i64 = 1 << 2;
+const FOLD_ZERO_WIDTH: i64 = 1 << 3;
+
+impl NormalizeOptions {
+    // すべての正規化を行う (全文検索用)
+    pub fn all() -> Self {
+        Self {
+            nfkc: true,
+            kana: true,
+            case: true,
+            zero_width: true,
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        *self == Self::default()
+    }
+
+    // ログDBには1つの整数として保存する
+    pub fn to_bits(self) -> i64 {
+        let mut bits = 0;
+        if self.nfkc {
+            bits |= FOLD_NFKC;
+        }
+        if self.kana {
+            bits |= FOLD_KANA;
+        }
+        if self.case {
+            bits |= FOLD_CASE;
+        }
+        if self.zero_width {
+            bits |= FOLD_ZERO_WIDTH;
+        }
+        bits
+    }
+
+    pub fn from_bits(bits: i64) -> Self {
+        Self {
+            nfkc: bits & FOLD_NFKC != 0,
+            kana: bits & FOLD_KANA != 0,
+            case: bits & FOLD_CASE != 0,
+            zero_width: bits & FOLD_ZERO_WIDTH != 0,
+        }
+    }
+}
+
+// ゼロ幅スペース・結合子、単語結合子、BOM、ソフトハイフン
+fn is_zero_width(c: char) -> bool {
+    matches!(
+        c,
+        '\u{200B}' | '\u{200C}' | '\u{200D}' | '\u{2060}' | '\u{FEFF}' | '\u{00AD}'
+    )
+}
+
+// カタカナ (ァ〜ヶ、ヽヾ) をひらがなに変換する
+fn fold_kana(c: char) -> char {
+    match c {
+        '\u{30A1}'..='\u{30F6}' | '\u{30FD}' | '\u{30FE}' => {
+            char::from_u32(c as u32 - 0x60).unwrap_or(c)
+        }
+        _ => c,
+    }
+}
+
+// 指定された正規化を、ゼロ幅文字の除去 → NFKC → かな → 大文字小文字 の順に行う
+pub fn normalize(text: &str, options: &NormalizeOptions) -> String {
+    if options.is_empty() {
+        return text.to_string();
+    }
+    let mut normalized: String = if options.zero_width {
+        text.chars().filter(|&c| !is_zero_width(c)).collect()
+    } else {
+        text.to_string()
+    };
+    if options.nfkc {
+        normalized = normalized.nfkc().collect();
+    }
+    if options.kana {
+        normalized = normalized.chars().map(fold_kana).collect();
+    }
+    if options.case {
+        normalized = normalized.to_lowercase();
+    }
+    normalized
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn strips_zero_width_characters() {
+        let options = NormalizeOptions {
+            zero_width: true,
+            ..Default::default()
+        };
+        assert_eq!(
+            normalize("ア\u{200B}ホ\u{200D}\u{FEFF}だ\u{00AD}", &options),
+            "アホだ"
+        );
+        assert_eq!(normalize("\u{2060}\u{200C}", &options), "");
+    }
+
+    #[test]
+    fn applies_nfkc() {
+        let options = NormalizeOptions {
+            nfkc: true,
+            ..Default::default()
+        };
+        assert_eq!(normalize("ＡＢＣ１２３", &options), "ABC123");
+        assert_eq!(normalize("ﾃｽﾄ", &options), "テスト");
+        assert_eq!(normalize("①㍻", &options), "1平成");
+    }
+
+    #[test]
+    fn folds_katakana_to_hiragana() {
+        let options = NormalizeOptions {
+            kana: true,
+            ..Default::default()
+        };
+        assert_eq!(normalize("カタカナヴヽヾ", &options), "かたかなゔゝゞ");
+        // 長音記号・中点・半角カナはそのまま (半角カナは NFKC と組み合わせる)
+        assert_eq!(normalize("ラーメン・ﾗｰﾒﾝ", &options), "らーめん・ﾗｰﾒﾝ");
+        let with_nfkc = NormalizeOptions {
+            nfkc: true,
+            ..options
+        };
+        assert_eq!(normalize("ﾗｰﾒﾝ", &with_nfkc), "らーめん");
+    }
+
+    #[test]
+    fn folds_case() {
+        let options = NormalizeOptions {
+            case: true,
+            ..Default::default()
+        };
+        assert_eq!(normalize("AbC ΣΑ", &options), "abc σα");
+        // NFKC の後に小文字にするので全角英字も揃う
+        assert_eq!(normalize("ＡＢＣ", &NormalizeOptions::all()), "abc");
+        assert_eq!(normalize("ＡＢＣ", &options), "ａｂｃ");
+    }
+
+    #[test]
+    fn no_options_keeps_text() {
+        let text = "ＡＢＣ\u{200B}カナ";
+        assert_eq!(normalize(text, &NormalizeOptions::default()), text);
+    }
+
+    #[test]
+    fn options_round_trip_through_bits() {
+        for bits in 0..16 {
+            assert_eq!(NormalizeOptions::from_bits(bits).to_bits(), bits);
+        }
+        assert_eq!(NormalizeOptions::all().to_bits(), 15);
+        assert!(NormalizeOptions::from_bits(0).is_empty());
+    }
+}
@@ -1,154 +1,502 @@
 use base64::{engine::general_purpose::STANDARD as Base64Standard, Engine as _};
 use serde::Serialize;
-use std::collections::HashMap;
-
-// レスポンスアイテムの構造体
-#[derive(Debug, Serialize, Clone)] // フロントエンドに渡すので Serialize は必須
-pub struct ResponseItem {
-    id: String,           // レス番号 (例: "1", "2")
-    author: String,       // 名前欄
-    mail: String,         // メール欄
-    created_at: String,   // パースされた日付部分の文字列
-    user_id_info: String, // "ID:xxxx主" のような、表示用のID文字列全体
-    content: String,      // 本文 (HTMLが含まれる)
-
-    // ★★★ IDカウンター用に新しいフィールドを追加 ★★★
-    parsed_user_id: Option<String>, // パースされた実際のID部分 (例: "R780OCsAQ")、IDがない場合は None
-    id_occurrence_count: u32,       // このレスが、このIDによる何回目の投稿か
-    id_total_count: u32,            // このIDがこのスレッドで行った総投稿数
-}
-
-#[derive(Debug)]
-struct TempResponseData {
-    name: String,
-    mail: String,
-    date_str: String,               // パースされた日付部分
-    user_id_info: String,           // IDなどを含む文字列全体
-    parsed_user_id: Option<String>, // パースされた実際のID
-    body: String,
+use std::collections::{HashMap, HashSet};
+use std::sync::Arc;
+#[cfg(feature = "tauri")]
+use tauri::ipc::Channel;
+#[cfg(feature = "tauri")]
+use tauri::{AppHandle, State};
+
+use super::board::{dat_url, BOARD_ID};
+use super::cache::ThreadCache;
+#[cfg(feature = "tauri")]
+use super::dat::{apply_id_total_counts, DatStreamParser};
+use super::dat::{decode_dat_bytes, parse_dat, parse_dat_title, ResponseItem};
+use super::fetch::fetch_dat;
+#[cfg(feature = "tauri")]
+use super::fetch::request_dat;
+#[cfg(feature = "tauri")]
+use crate::ng::filter::{load_ng_filter, muted_numbers};
+#[cfg(feature = "tauri")]
+use crate::post::submit::PostSession;
+use crate::store::log_store::LogStore;
+use crate::store::my_posts::MyPostMarker;
+#[cfg(feature = "tauri")]
+use crate::store::replies::notify_new_replies;
+
+// fetch_thread_content_stream がチャンネルに流すイベント
+// フロントエンドでは { event: "responses", data: {...} } の形で受け取る
+#[derive(Debug, Serialize, Clone)]
+#[serde(rename_all = "snake_case", tag = "event", content = "data")]
+pub enum ThreadStreamEvent {
+    Started {
+        thread_id: String,
+        content_length: Option<u64>, // Content-Length がない場合は None
+    },
+    Progress {
+        received_bytes: u64,
+        content_length: Option<u64>,
+    },
+    Responses {
+        responses: Vec<ResponseItem>,
+    },
+    Finished {
+        total_responses: usize,
+        id_total_counts: HashMap<String, u32>, // IDごとの確定した総投稿数
+    },
+}
+
+// get_responses 系コマンドの戻り値
+#[derive(Debug, Serialize, Clone)]
+pub struct ResponsePage {
+    thread_id: String,
+    total: usize,                 // スレッド全体のレス数
+    responses: Vec<ResponseItem>, // 要求された範囲のレス (レス番号順)
 }
 
+#[cfg(feature = "tauri")]
 #[tauri::command]
-pub async fn fetch_thread_content(thread_id: String) -> Result<Vec<ResponseItem>, String> {
-    if thread_id.is_empty() {
-        /* ... エラー処理 ... */
-        return Err("スレッドIDが指定されていません。".to_string());
-    }
-    let dir_prefix = if thread_id.len() >= 4 {
-        &thread_id[0..4]
-    } else {
-        /* ... エラー処理 ... */
-        return Err("スレッドIDが短すぎます".to_string());
-    };
-    let dat_file_url = format!(
-        /* ... URL生成 ... */
-        "https://tulipplantation.com/tulipplantation/thread/{}/{}.dat",
-        dir_prefix, thread_id
-    );
-    println!(
+pub async fn fetch_thread_content(
+    thread_id: String,
+    app_handle: AppHandle,
+    session: State<'_, PostSession>,
+    cache: State<'_, ThreadCache>,
+    store: State<'_, LogStore>,
+) -> Result<Vec<ResponseItem>, String> {
+    let (mut final_responses, my_posts) =
+        download_through_store(session.http(), &thread_id, &store).await?;
+    // キャッシュには NG 適用前のレスを入れる (ルールが変わっても取得し直さずに済むように)
+    cache.insert(&thread_id, final_responses.clone());
+    load_ng_filter(&store, BOARD_ID, &thread_id).apply(&mut final_responses);
+    // 返信の受信箱には、表示用に判定した NG の結果をそのまま使う
+    let muted = muted_numbers(&final_responses);
+    finish_my_posts(&store, &thread_id, my_posts, &final_responses, Some(&muted));
+    notify_new_replies(&app_handle, &store);
+    Ok(final_responses)
+}
+
+// dat をダウンロードしてパースし、ログDBに保存する。保存済みのログがあれば差分だけを取得する。
+// ダウンロードに失敗した場合 (オフライン、dat落ちなど) は保存済みのログを返す
+// 自分の書き込みに印を付け、自分の書き込みへの返信を受信箱に入れる
+pub async fn load_thread_through_store(
+    http: &reqwest::Client,
+    thread_id: &str,
+    store: &LogStore,
+) -> Result<Vec<ResponseItem>, String> {
+    let (responses, my_posts) = download_through_store(http, thread_id, store).await?;
+    finish_my_posts(store, thread_id, my_posts, &responses, None);
+    Ok(responses)
+}
+
+// load_thread_through_store の本体。自分の書き込みの照合は、返信を受信箱に入れる前の状態で返す
+// (呼び出し側で NG を判定する場合に、その結果を finish_my_posts に渡せるように)
+async fn download_through_store(
+    http: &reqwest::Client,
+    thread_id: &str,
+    store: &LogStore,
+) -> Result<(Vec<ResponseItem>, Option<MyPostMarker>), String> {
+    let dat_file_url = dat_url(thread_id)?;
+    eprintln!(
         "[Rust fetch_thread_content] スレッド内容を取得します (ID: {}): {}",
         thread_id, dat_file_url
     );
 
-    let client = reqwest::Client::new();
-    let response = match client.get(&dat_file_url).send().await {
-        /* ... HTTP GET ... */ Ok(r) => r,
-        Err(e) => return Err(e.to_string()),
+    let previous = store.load_dat(BOARD_ID, thread_id)?;
+    let dat_bytes = match fetch_dat(http, thread_id, previous).await {
+        Ok(fetched) => fetched.bytes,
+        Err(fetch_err) => {
+            eprintln!(
+                "[Rust fetch_thread_content] 取得に失敗しました ({})。保存済みのログを探します。",
+                fetch_err
+            );
+            return match store.load_responses(BOARD_ID, thread_id)? {
+                Some(mut responses) => {
+                    let my_posts = mark_my_posts(store, thread_id, &mut responses);
+                    eprintln!(
+                        "[Rust fetch_thread_content] 保存済みのログから {} 個のレスを読み込みました (スレッドID: {})",
+                        responses.len(),
+                        thread_id
+                    );
+                    Ok((responses, my_posts))
+                }
+                None => Err(fetch_err),
+            };
+        }
     };
-    if !response.status().is_success() {
-        /* ... HTTPエラー処理 ... */
-        return Err(response.status().to_string());
+
+    let (content_str, _) = decode_dat_bytes(&dat_bytes);
+    let mut final_responses = parse_dat(&content_str);
+    let my_posts = mark_my_posts(store, thread_id, &mut final_responses);
+    save_to_store(store, thread_id, &content_str, &dat_bytes, &final_responses);
+
+    eprintln!(
+        "[Rust fetch_thread_content] {} 個のレスを処理完了 (スレッドID: {})",
+        final_responses.len(),
+        thread_id
+    );
+    Ok((final_responses, my_posts))
+}
+
+// ログDBへの保存と取得レス数の記録。失敗しても表示を妨げないよう、ログを出すだけにする
+pub(crate) fn save_to_store(
+    store: &LogStore,
+    thread_id: &str,
+    content_str: &str,
+    dat_bytes: &[u8],
+    responses: &[ResponseItem],
+) {
+    let title = parse_dat_title(content_str).unwrap_or_default();
+    if let Err(e) = store.save_thread(BOARD_ID, thread_id, &title, dat_bytes, responses) {
+        eprintln!(
+            "[Rust fetch_thread_content] ログDBへの保存に失敗しました (スレッドID: {}): {}",
+            thread_id, e
+        );
     }
-    let content_str = match response.text().await {
-        /* ... UTF-8としてテキスト取得 ... */ Ok(t) => t,
-        Err(e) => return Err(e.to_string()),
-    };
+    // 取得したレス数を既読情報に記録する
+    if let Err(e) = store.record_seen(BOARD_ID, thread_id, responses.len() as u32) {
+        eprintln!(
+            "[Rust fetch_thread_content] 取得レス数の記録に失敗しました (スレッドID: {}): {}",
+            thread_id, e
+        );
+    }
+}
 
-    let mut temp_responses: Vec<TempResponseData> = Vec::new();
-    let mut id_total_counts: HashMap<String, u32> = HashMap::new();
+// 自分の書き込みに印を付ける。照合の結果は finish_my_posts で記録する
+fn mark_my_posts(
+    store: &LogStore,
+    thread_id: &str,
+    responses: &mut [ResponseItem],
+) -> Option<MyPostMarker> {
+    let mut my_posts = load_my_post_marker(store, thread_id)?;
+    my_posts.apply(responses);
+    Some(my_posts)
+}
+
+// 新しく照合できた書き込みと自分への返信を記録する
+// muted は判定済みの NG になったレスの番号 (None ならここで判定する)
+// 失敗しても表示を妨げないよう、ログを出すだけにする
+fn finish_my_posts(
+    store: &LogStore,
+    thread_id: &str,
+    my_posts: Option<MyPostMarker>,
+    responses: &[ResponseItem],
+    muted: Option<&HashSet<u32>>,
+) {
+    if let Some(Err(e)) =
+        my_posts.map(|my_posts| my_posts.finish(store, BOARD_ID, thread_id, responses, muted))
+    {
+        eprintln!(
+            "[Rust mark_my_posts] 書き込み履歴との照合に失敗しました (スレッドID: {}): {}",
+            thread_id, e
+        );
+    }
+}
+
+// 受信しながら印を付ける場合は、照合の状態を最後まで持ち回る
+fn load_my_post_marker(store: &LogStore, thread_id: &str) -> Option<MyPostMarker> {
+    store
+        .my_post_marker(BOARD_ID, thread_id)
+        .map_err(|e| {
+            eprintln!(
+                "[Rust mark_my_posts] 書き込み履歴の読み込みに失敗しました (スレッドID: {}): {}",
+                thread_id, e
+            )
+        })
+        .ok()
+}
+
+// キャッシュにあればそれを、なければ取得してキャッシュに入れたものを返す
+pub(crate) async fn cached_or_download(
+    http: &reqwest::Client,
+    thread_id: &str,
+    cache: &ThreadCache,
+    store: &LogStore,
+) -> Result<Arc<Vec<ResponseItem>>, String> {
+    if let Some(responses) = cache.get(thread_id) {
+        return Ok(responses);
+    }
+    let responses = load_thread_through_store(http, thread_id, store).await?;
+    Ok(cache.insert(thread_id, responses))
+}
+
+// レス番号 from〜to (1始まり、両端を含む) を返す
+#[cfg(feature = "tauri")]
+#[tauri::command]
+pub async fn get_responses(
+    thread_id: String,
+    from: usize,
+    to: usize,
+    session: State<'_, PostSession>,
+    cache: State<'_, ThreadCache>,
+    store: State<'_, LogStore>,
+) -> Result<ResponsePage, String> {
+    if from == 0 || from > to {
+        return Err(format!("レス番号の範囲が不正です: {}-{}", from, to));
+    }
+    let responses = cached_or_download(session.http(), &thread_id, &cache, &store).await?;
+    let start = (from - 1).min(responses.len());
+    let end = to.min(responses.len());
+    Ok(build_page(thread_id, &responses, start, end, &store))
+}
 
-    // 1回目のパース: 基本情報抽出とIDの総出現回数のカウント
-    for line in content_str.lines() {
-        if line.trim().is_empty() {
-            continue;
+// 末尾の count 件を返す
+#[cfg(feature = "tauri")]
+#[tauri::command]
+pub async fn get_last_responses(
+    thread_id: String,
+    count: usize,
+    session: State<'_, PostSession>,
+    cache: State<'_, ThreadCache>,
+    store: State<'_, LogStore>,
+) -> Result<ResponsePage, String> {
+    let responses = cached_or_download(session.http(), &thread_id, &cache, &store).await?;
+    let start = responses.len().saturating_sub(count);
+    Ok(build_page(
+        thread_id,
+        &responses,
+        start,
+        responses.len(),
+        &store,
+    ))
+}
+
+// レス番号 since より後のレスをすべて返す (差分表示用)
+#[cfg(feature = "tauri")]
+#[tauri::command]
+pub async fn get_responses_since(
+    thread_id: String,
+    since: usize,
+    session: State<'_, PostSession>,
+    cache: State<'_, ThreadCache>,
+    store: State<'_, LogStore>,
+) -> Result<ResponsePage, String> {
+    let responses = cached_or_download(session.http(), &thread_id, &cache, &store).await?;
+    let start = since.min(responses.len());
+    Ok(build_page(
+        thread_id,
+        &responses,
+        start,
+        responses.len(),
+        &store,
+    ))
+}
+
+#[cfg(feature = "tauri")]
+fn build_page(
+    thread_id: String,
+    responses: &[ResponseItem],
+    start: usize,
+    end: usize,
+    store: &LogStore,
+) -> ResponsePage {
+    // 連鎖NGは前のレスの判定に依存するため、先頭から判定してから切り出す
+    let mut head = responses[..end].to_vec();
+    load_ng_filter(store, BOARD_ID, &thread_id).apply(&mut head);
+    ResponsePage {
+        thread_id,
+        total: responses.len(),
+        responses: head.split_off(start),
+    }
+}
+
+// 大きなスレッド向け: 受信しながらパースし、レスをまとめてチャンネルへ送る
+#[cfg(feature = "tauri")]
+#[tauri::command]
+pub async fn fetch_thread_content_stream(
+    thread_id: String,
+    on_event: Channel<ThreadStreamEvent>,
+    app_handle: AppHandle,
+    session: State<'_, PostSession>,
+    cache: State<'_, ThreadCache>,
+    store: State<'_, LogStore>,
+) -> Result<(), String> {
+    let dat_file_url = dat_url(&thread_id)?;
+    eprintln!(
+        "[Rust fetch_thread_content_stream] スレッド内容をストリーミング取得します (ID: {}): {}",
+        thread_id, dat_file_url
+    );
+
+    let mut response = match request_dat(session.http(), &dat_file_url).await {
+        Ok(r) => r,
+        Err(fetch_err) => {
+            let result = stream_from_store(&thread_id, &on_event, &cache, &store, fetch_err);
+            notify_new_replies(&app_handle, &store);
+            return result;
         }
-        let parts: Vec<&str> = line.splitn(5, "<>").collect();
-        if parts.len() >= 4 {
-            let name = parts[0].to_string();
-            let mail = parts[1].to_string();
-            let date_and_id_full = parts[2].to_string();
-            let body = parts[3].to_string();
-
-            let mut date_str = date_and_id_full.clone();
-            let mut user_id_info_str = "".to_string();
-            if let Some(id_pos) = date_and_id_full.rfind(" ID:") {
-                date_str = date_and_id_full[..id_pos].trim().to_string();
-                user_id_info_str = date_and_id_full[id_pos..].trim().to_string();
-            } else { /* ... 他の heuristic ... */
-            }
+    };
 
-            let parsed_id = parse_actual_id_from_info_str(&user_id_info_str);
-            if let Some(ref id) = parsed_id {
-                *id_total_counts.entry(id.clone()).or_insert(0) += 1;
-            }
+    let content_length = response.content_length();
+    send_stream_event(
+        &on_event,
+        ThreadStreamEvent::Started {
+            thread_id: thread_id.clone(),
+            content_length,
+        },
+    )?;
 
-            temp_responses.push(TempResponseData {
-                name,
-                mail,
-                date_str,
-                user_id_info: user_id_info_str,
-                parsed_user_id: parsed_id,
-                body,
-            });
-        } else { /* ... パース失敗ログ ... */
+    let mut ng_filter = load_ng_filter(&store, BOARD_ID, &thread_id);
+    let mut muted: HashSet<u32> = HashSet::new();
+    let mut my_posts = load_my_post_marker(&store, &thread_id);
+    let mut parser = DatStreamParser::new();
+    let mut all_responses: Vec<ResponseItem> = Vec::new();
+    let mut dat_bytes: Vec<u8> = Vec::new();
+    let mut received_bytes: u64 = 0;
+    loop {
+        let chunk = match response.chunk().await {
+            Ok(Some(chunk)) => chunk,
+            Ok(None) => break,
+            Err(e) => return Err(e.to_string()),
+        };
+        received_bytes += chunk.len() as u64;
+        dat_bytes.extend_from_slice(&chunk);
+
+        let mut responses = parser.push(&chunk);
+        if !responses.is_empty() {
+            if let Some(my_posts) = my_posts.as_mut() {
+                my_posts.apply(&mut responses);
+            }
+            all_responses.extend(responses.iter().cloned());
+            ng_filter.apply(&mut responses);
+            muted.extend(muted_numbers(&responses));
+            send_stream_event(&on_event, ThreadStreamEvent::Responses { responses })?;
         }
+        send_stream_event(
+            &on_event,
+            ThreadStreamEvent::Progress {
+                received_bytes,
+                content_length,
+            },
+        )?;
     }
 
-    let mut final_responses: Vec<ResponseItem> = Vec::new();
-    let mut id_current_occurrences: HashMap<String, u32> = HashMap::new();
-
-    // 2回目の処理: ResponseItem の作成と、IDの現在の出現回数のカウント
-    for (index, temp_res) in temp_responses.into_iter().enumerate() {
-        let mut occurrence = 0;
-        let mut total = 0;
-
-        if let Some(ref parsed_id_val) = temp_res.parsed_user_id {
-            let current_count_for_id = id_current_occurrences
-                .entry(parsed_id_val.clone())
-                .or_insert(0);
-            *current_count_for_id += 1;
-            occurrence = *current_count_for_id;
-            total = *id_total_counts.get(parsed_id_val).unwrap_or(&0); // total_counts には必ずあるはず
+    let mut rest = parser.finish();
+    if !rest.is_empty() {
+        if let Some(my_posts) = my_posts.as_mut() {
+            my_posts.apply(&mut rest);
         }
+        all_responses.extend(rest.iter().cloned());
+        ng_filter.apply(&mut rest);
+        muted.extend(muted_numbers(&rest));
+        send_stream_event(&on_event, ThreadStreamEvent::Responses { responses: rest })?;
+    }
+    send_stream_event(
+        &on_event,
+        ThreadStreamEvent::Finished {
+            total_responses: parser.response_count(),
+            id_total_counts: parser.id_total_counts().clone(),
+        },
+    )?;
+
+    apply_id_total_counts(&mut all_responses, parser.id_total_counts());
+    // 返信の受信箱には、送信するときに判定した NG の結果を使う
+    finish_my_posts(&store, &thread_id, my_posts, &all_responses, Some(&muted));
+    let (content_str, _) = decode_dat_bytes(&dat_bytes);
+    save_to_store(&store, &thread_id, &content_str, &dat_bytes, &all_responses);
+    cache.insert(&thread_id, all_responses);
+    notify_new_replies(&app_handle, &store);
+
+    eprintln!(
+        "[Rust fetch_thread_content_stream] {} 個のレスを送信完了 (スレッドID: {}, {} bytes)",
+        parser.response_count(),
+        thread_id,
+        received_bytes
+    );
+    Ok(())
+}
+
+#[cfg(feature = "tauri")]
+// 取得に失敗したときは保存済みのログを一度に流す
+fn stream_from_store(
+    thread_id: &str,
+    on_event: &Channel<ThreadStreamEvent>,
+    cache: &ThreadCache,
+    store: &LogStore,
+    fetch_err: String,
+) -> Result<(), String> {
+    eprintln!(
+        "[Rust fetch_thread_content_stream] 取得に失敗しました ({})。保存済みのログを探します。",
+        fetch_err
+    );
+    let mut responses = match store.load_responses(BOARD_ID, thread_id)? {
+        Some(responses) => responses,
+        None => return Err(fetch_err),
+    };
+    let my_posts = mark_my_posts(store, thread_id, &mut responses);
 
-        final_responses.push(ResponseItem {
-            id: (index + 1).to_string(),
-            author: temp_res.name,
-            mail: temp_res.mail,
-            created_at: temp_res.date_str,
-            user_id_info: temp_res.user_id_info,
-            content: temp_res.body,
-            parsed_user_id: temp_res.parsed_user_id, // これも渡す
-            id_occurrence_count: occurrence,
-            id_total_count: total,
-        });
+    let mut id_total_counts: HashMap<String, u32> = HashMap::new();
+    for response in &responses {
+        if let Some(ref parsed_id_val) = response.parsed_user_id {
+            id_total_counts.insert(parsed_id_val.clone(), response.id_total_count);
+        }
     }
+    let total_responses = responses.len();
+    let mut filtered = responses.clone();
+    load_ng_filter(store, BOARD_ID, thread_id).apply(&mut filtered);
+    finish_my_posts(
+        store,
+        thread_id,
+        my_posts,
+        &responses,
+        Some(&muted_numbers(&filtered)),
+    );
 
-    println!(
-        "[Rust fetch_thread_content] {} 個のレスを処理完了 (スレッドID: {})",
-        final_responses.len(),
-        thread_id
+    send_stream_event(
+        on_event,
+        ThreadStreamEvent::Started {
+            thread_id: thread_id.to_string(),
+            content_length: None,
+        },
+    )?;
+    send_stream_event(
+        on_event,
+        ThreadStreamEvent::Responses {
+            responses: filtered,
+        },
+    )?;
+    send_stream_event(
+        on_event,
+        ThreadStreamEvent::Finished {
+            total_responses,
+            id_total_counts,
+        },
+    )?;
+    cache.insert(thread_id, responses);
+
+    eprintln!(
+        "[Rust fetch_thread_content_stream] 保存済みのログから {} 個のレスを送信しました (スレッドID: {})",
+        total_responses, thread_id
     );
-    Ok(final_responses)
+    Ok(())
 }
 
+#[cfg(feature = "tauri")]
+fn send_stream_event(
+    channel: &Channel<ThreadStreamEvent>,
+    event: ThreadStreamEvent,
+) -> Result<(), String> {
+    channel
+        .send(event)
+        .map_err(|e| format!("チャンネルへの送信に失敗しました: {}", e))
+}
+
+#[cfg(feature = "tauri")]
 #[tauri::command]
-pub async fn fetch_image_as_base64(url: String) -> Result<String, String> {
-    println!("[Rust fetch_image_as_base64] 画像を取得します: {}", url);
+pub async fn fetch_image_as_base64(
+    url: String,
+    session: State<'_, PostSession>,
+) -> Result<String, String> {
+    download_image_as_data_url(session.http(), &url).await
+}
 
-    let client = reqwest::Client::new();
-    let response = match client
-        .get(&url)
+// 画像をダウンロードして data URL (data:image/...;base64,...) にする
+pub(crate) async fn download_image_as_data_url(
+    http: &reqwest::Client,
+    url: &str,
+) -> Result<String, String> {
+    eprintln!("[Rust fetch_image_as_base64] 画像を取得します: {}", url);
+
+    let response = match http
+        .get(url)
         // Imgurが特定のUser-Agentを要求する可能性は低いですが、念のため一般的なものを設定するのも一手
         // .header(reqwest::header::USER_AGENT, "Mozilla/5.0 (Windows NT 10.0; Win64; x64) AppleWebKit/537.36 (KHTML, like Gecko) Chrome/90.0.4430.93 Safari/537.36")
         .send()
@@ -196,18 +544,3 @@ pub async fn fetch_image_as_base64(url: String) -> Result<String, String> {
 
     Ok(data_url)
 }
-
-fn parse_actual_id_from_info_str(user_id_info_str: &str) -> Option<String> {
-    if let Some(id_start_idx) = user_id_info_str.find("ID:") {
-        let after_id_colon = &user_id_info_str[id_start_idx + 3..];
-        // ID部分の終わりを見つける (スペース、(、[ などが区切りになることが多い)
-        let id_end_idx = after_id_colon
-            .find(|c: char| !c.is_alphanumeric() && c != '-' && c != '_')
-            .unwrap_or(after_id_colon.len());
-        let actual_id = after_id_colon[..id_end_idx].to_string();
-        if !actual_id.is_empty() {
-            return Some(actual_id);
-        }
-    }
-    None
-}
@@ -0,0 +1,67 @@
+use serde::Serialize;
+
+use super::dat::decode_dat_bytes;
+
+// SETTING.TXT (板の設定) のうち、書き込みの前に確かめる制限
+// 値はどれもバイト数 (板の文字コードで数える)。書かれていない項目は None
+#[derive(Debug, Serialize, Clone, Default, PartialEq, Eq)]
+pub struct BoardSetting {
+    pub title: Option<String>,            // BBS_TITLE
+    pub noname_name: Option<String>,      // BBS_NONAME_NAME (名無しの名前)
+    pub subject_count: Option<usize>,     // BBS_SUBJECT_COUNT (スレッドタイトル)
+    pub name_count: Option<usize>,        // BBS_NAME_COUNT
+    pub mail_count: Option<usize>,        // BBS_MAIL_COUNT
+    pub message_count: Option<usize>,     // BBS_MESSAGE_COUNT (本文)
+    pub max_message_lines: Option<usize>, // BBS_LINE_NUMBER から求めた本文の行数の上限
+}
+
+// SETTING.TXT の本文をパースする (KEY=VALUE が1行に1つ)
+pub fn parse_setting_txt(text: &str) -> BoardSetting {
+    let mut setting = BoardSetting::default();
+    for line in text.lines() {
+        let Some((key, value)) = line.split_once('=') else {
+            continue;
+        };
+        let value = value.trim();
+        let count = || value.parse::<usize>().ok().filter(|&n| n > 0);
+        match key.trim() {
+            "BBS_TITLE" => setting.title = Some(value.to_string()),
+            "BBS_NONAME_NAME" => setting.noname_name = Some(value.to_string()),
+            "BBS_SUBJECT_COUNT" => setting.subject_count = count(),
+            "BBS_NAME_COUNT" => setting.name_count = count(),
+            "BBS_MAIL_COUNT" => setting.mail_count = count(),
+            "BBS_MESSAGE_COUNT" => setting.message_count = count(),
+            // 2ch 互換の掲示板では、本文の行数の上限は BBS_LINE_NUMBER の2倍
+            "BBS_LINE_NUMBER" => setting.max_message_lines = count().map(|n| n * 2),
+            _ => {}
+        }
+    }
+    setting
+}
+
+// SETTING.TXT を取得してパースする (Shift_JIS の板が多いので文字コードは自動で判別する)
+pub async fn fetch_setting(
+    http: &reqwest::Client,
+    setting_url: &str,
+) -> Result<BoardSetting, String> {
+    let response = http
+        .get(setting_url)
+        .send()
+        .await
+        .map_err(|e| format!("リクエストに失敗しました (URL: {}): {}", setting_url, e))?;
+    if !response.status().is_success() {
+        return Err(format!(
+            "HTTPエラー: {} (URL: {})",
+            response.status(),
+            setting_url
+        ));
+    }
+    let bytes = response.bytes().await.map_err(|e| {
+        format!(
+            "レスポンスの読み取りに失敗しました (URL: {}): {}",
+            setting_url, e
+        )
+    })?;
+    let (text, _) = decode_dat_bytes(&bytes);
+    Ok(parse_setting_txt(&text))
+}
@@ -0,0 +1,79 @@
+use html_escape::decode_html_entities;
+use serde::{Deserialize, Deserializer, Serialize};
+
+// subject.json (スレッド一覧) の1件
+// サーバーからは { "thread": ..., "title": ..., "number": ..., "date": ... } の形で届く
+#[derive(Deserialize, Serialize, Debug, Clone)]
+pub struct SubjectEntry {
+    // "thread" フィールドが数値または文字列の場合に対応するため、カスタムデシリアライザを使用
+    #[serde(
+        rename = "thread",
+        deserialize_with = "deserialize_thread_id_to_string"
+    )]
+    pub thread_id: String, // スレッドIDを文字列として統一
+    pub title: String, // HTMLエンティティはデコード済み
+    #[serde(rename = "number")]
+    pub response_count: u32, // レス数
+    pub date: i64,     // Unixタイムスタンプ (最終更新日時など)
+}
+
+// "thread" フィールドのカスタムデシリアライザ
+// JSON内で数値でも文字列でも送られてくる可能性がある "thread" IDを常にStringとして読み込む
+fn deserialize_thread_id_to_string<'de, D>(deserializer: D) -> Result<String, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    #[derive(Deserialize)]
+    #[serde(untagged)] // 型が一致するものを試す
+    enum ThreadIdValue {
+        Str(String),
+        Num(serde_json::Number), // 数値をまずは serde_json::Number で受ける
+    }
+
+    match ThreadIdValue::deserialize(deserializer)? {
+        ThreadIdValue::Str(s) => Ok(s),
+        ThreadIdValue::Num(n) => Ok(n.to_string()), // 数値を文字列に変換
+    }
+}
+
+// subject.json の本文をパースする
+pub fn parse_subject_json(json: &str) -> Result<Vec<SubjectEntry>, String> {
+    let mut entries: Vec<SubjectEntry> =
+        serde_json::from_str(json).map_err(|e| format!("JSONのパースに失敗しました: {}", e))?;
+    for entry in &mut entries {
+        entry.title = decode_html_entities(&entry.title).into_owned();
+    }
+    Ok(entries)
+}
+
+// subject.json を取得してパースする
+pub async fn fetch_subject(
+    http: &reqwest::Client,
+    json_url: &str,
+) -> Result<Vec<SubjectEntry>, String> {
+    let response = match http.get(json_url).send().await {
+        Ok(response) => response,
+        Err(e) => {
+            let err_msg = format!("リクエストに失敗しました (URL: {}): {}", json_url, e);
+            eprintln!("[Rust fetch_subject] {}", err_msg);
+            return Err(err_msg);
+        }
+    };
+    if !response.status().is_success() {
+        let err_msg = format!("HTTPエラー: {} (URL: {})", response.status(), json_url);
+        eprintln!("[Rust fetch_subject] {}", err_msg);
+        return Err(err_msg);
+    }
+
+    let body = response.text().await.map_err(|e| {
+        format!(
+            "レスポンスの読み取りに失敗しました (URL: {}): {}",
+            json_url, e
+        )
+    })?;
+    parse_subject_json(&body).map_err(|e| {
+        let err_msg = format!("{} (URL: {})", e, json_url);
+        eprintln!("[Rust fetch_subject] {}", err_msg);
+        err_msg
+    })
+}
@@ -1,46 +1,70 @@
 use chrono::DateTime;
 use chrono_tz::Asia::Tokyo;
-use html_escape::decode_html_entities;
-use serde::{Deserialize, Deserializer, Serialize};
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
 
-// APIから直接受け取るJSONの各要素に対応する構造体
-#[derive(Deserialize, Debug, Clone)]
-struct ApiThreadItem {
-    // "thread" フィールドが数値または文字列の場合に対応するため、カスタムデシリアライザを使用
-    #[serde(deserialize_with = "deserialize_thread_id_to_string")]
-    thread: String, // スレッドIDを文字列として統一
-    title: String,
-    number: u32, // レス数
-    date: i64,   // Unixタイムスタンプ (最終更新日時など)
-}
+#[cfg(feature = "tauri")]
+use tauri::State;
+
+use super::board::{subject_url, BOARD_ID};
+use super::normalize::{normalize, NormalizeOptions};
+use super::subject::{fetch_subject, SubjectEntry};
+use crate::ng::title::{compile_regex, TitleFilter};
+#[cfg(feature = "tauri")]
+use crate::post::submit::PostSession;
+use crate::store::log_store::LogStore;
 
 // フロントエンドに渡すためのスレッド情報の構造体 (既存のものを確認・使用)
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct ThreadItem {
-    id: String,          // スレッドID
-    title: String,       // タイトル
-    response_count: u32, // レス数
-    created_at: String,  // フォーマットされた日時文字列 (ここではAPIの 'date' を使用)
-    date: i64,
+    pub id: String,          // スレッドID
+    pub title: String,       // タイトル
+    pub response_count: u32, // レス数
+    pub created_at: String,  // フォーマットされた日時文字列 (ここではAPIの 'date' を使用)
+    pub date: i64,
+
+    // 既読情報 (ログDBの read_state から付与)
+    #[serde(default)]
+    pub unread_count: Option<u32>, // 未読レス数。一度も開いていないスレッドは None
+    #[serde(default)]
+    pub is_new_thread: bool, // 前回の一覧取得時には無かったスレッド
+    #[serde(default)]
+    pub last_read_at: Option<String>, // 最後に読んだ日時 (フォーマット済み)
+    #[serde(default)]
+    pub is_pinned: bool, // ピン留めされたスレッド (一覧の先頭に並ぶ)
+    #[serde(default)]
+    pub momentum: f64, // 勢い (スレッド作成からの1日あたりのレス数)
+    #[serde(default)]
+    pub change: ThreadChange, // 前回の一覧取得時からの変化
 }
 
-// "thread" フィールドのカスタムデシリアライザ
-// JSON内で数値でも文字列でも送られてくる可能性がある "thread" IDを常にStringとして読み込む
-fn deserialize_thread_id_to_string<'de, D>(deserializer: D) -> Result<String, D::Error>
-where
-    D: Deserializer<'de>,
-{
-    #[derive(Deserialize)]
-    #[serde(untagged)] // 型が一致するものを試す
-    enum ThreadIdValue {
-        Str(String),
-        Num(serde_json::Number), // 数値をまずは serde_json::Number で受ける
-    }
+// 前回の一覧取得時と比べたスレッドの変化
+// フロントエンドでは { kind: "updated", new_responses: 3 } の形で受け取る
+#[derive(Debug, Serialize, Deserialize, Clone, Default, PartialEq, Eq)]
+#[serde(rename_all = "snake_case", tag = "kind")]
+pub enum ThreadChange {
+    New,
+    Updated {
+        new_responses: u32,
+    },
+    #[default]
+    Unchanged,
+}
 
-    match ThreadIdValue::deserialize(deserializer)? {
-        ThreadIdValue::Str(s) => Ok(s),
-        ThreadIdValue::Num(n) => Ok(n.to_string()), // 数値を文字列に変換
-    }
+// 前回の一覧にはあったが、今回の一覧から消えたスレッド (dat落ち)
+#[derive(Debug, Serialize, Clone)]
+pub struct DroppedThread {
+    pub id: String,
+    pub title: String,
+    pub response_count: u32, // 最後に一覧で見たときのレス数
+}
+
+// fetch_threads / query_threads の戻り値
+#[derive(Debug, Serialize, Clone)]
+pub struct ThreadList {
+    pub threads: Vec<ThreadItem>, // スレタイNGを除いた一覧 (ピン留めが先頭)
+    pub hidden_count: usize,      // スレタイNGで非表示にしたスレッド数
+    pub dropped_threads: Vec<DroppedThread>, // 前回の一覧から消えたスレッド
 }
 
 // Unixタイムスタンプ (i64) を "YYYY/MM/DD HH:MM" 形式のJST日時文字列に変換するヘルパー関数
@@ -55,61 +79,634 @@ fn format_timestamp_from_i64(timestamp_secs: i64) -> String {
     "日付不明".to_string()
 }
 
+// query_threads の並び替えの基準
+#[derive(Debug, Deserialize, Clone, Copy, Default, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum ThreadSortKey {
+    #[default]
+    Server, // サーバーから返ってきた順のまま
+    CreatedAt,
+    LastUpdate,
+    ResponseCount,
+    UnreadCount,
+    Momentum,
+}
+
+// query_threads の条件
+#[derive(Debug, Deserialize, Clone, Default)]
+#[serde(default)]
+pub struct ThreadQuery {
+    pub sort: ThreadSortKey,
+    pub descending: bool,
+    pub title: Option<String>, // タイトルの絞り込み (空なら絞り込まない)
+    pub title_is_regex: bool,
+    pub refresh: bool, // true ならサーバーから取得し直す。false なら前回の一覧を使う
+}
+
+// 最後に取得したスレッド一覧 (並び替え・絞り込みのたびに取得し直さないため)
+#[derive(Default)]
+pub struct ThreadListCache {
+    last: std::sync::Mutex<Option<ThreadList>>,
+}
+
+impl ThreadListCache {
+    pub fn get(&self) -> Option<ThreadList> {
+        self.last.lock().ok()?.clone()
+    }
+
+    pub fn set(&self, list: &ThreadList) {
+        if let Ok(mut last) = self.last.lock() {
+            *last = Some(list.clone());
+        }
+    }
+}
+
+// スレッドキー (作成時刻のUnixタイムスタンプ) から勢いを計算する
+fn calculate_momentum(thread_id: &str, response_count: u32, now: i64) -> f64 {
+    let Ok(created) = thread_id.parse::<i64>() else {
+        return 0.0;
+    };
+    // 作成直後に値が跳ね上がらないよう、経過時間は最低1分とみなす
+    let elapsed_secs = (now - created).max(60) as f64;
+    response_count as f64 * 86400.0 / elapsed_secs
+}
+
+#[cfg(feature = "tauri")]
+#[tauri::command]
+pub async fn fetch_threads(
+    session: State<'_, PostSession>,
+    store: State<'_, LogStore>,
+    list_cache: State<'_, ThreadListCache>,
+) -> Result<ThreadList, String> {
+    let list = load_thread_list(session.http(), &store).await?;
+    list_cache.set(&list);
+    Ok(list)
+}
+
+// スレッド一覧を並び替え・絞り込みして返す (ピン留めしたスレッドは常に先頭)
+#[cfg(feature = "tauri")]
 #[tauri::command]
-pub async fn fetch_threads() -> Result<Vec<ThreadItem>, String> {
-    let json_url = "https://tulipplantation.com/tulipplantation/subject.json";
-    println!(
+pub async fn query_threads(
+    query: ThreadQuery,
+    session: State<'_, PostSession>,
+    store: State<'_, LogStore>,
+    list_cache: State<'_, ThreadListCache>,
+) -> Result<ThreadList, String> {
+    let mut list = match list_cache.get() {
+        Some(list) if !query.refresh => list,
+        _ => {
+            let list = load_thread_list(session.http(), &store).await?;
+            list_cache.set(&list);
+            list
+        }
+    };
+    apply_thread_query(&mut list, &query)?;
+    Ok(list)
+}
+
+// 一覧をタイトルで絞り込み、並び替える (ピン留めしたスレッドは常に先頭)
+pub fn apply_thread_query(list: &mut ThreadList, query: &ThreadQuery) -> Result<(), String> {
+    if let Some(title) = query.title.as_deref().filter(|t| !t.trim().is_empty()) {
+        let options = NormalizeOptions::all();
+        if query.title_is_regex {
+            // compile_regex はパターンの文字も正規化するので、タイトルも同じく正規化して比べる
+            let regex =
+                compile_regex(title, &options).map_err(|e| format!("正規表現が不正です: {}", e))?;
+            list.threads
+                .retain(|thread| regex.is_match(&normalize(&thread.title, &options)));
+        } else {
+            let needle = normalize(title.trim(), &options);
+            list.threads
+                .retain(|thread| normalize(&thread.title, &options).contains(&needle));
+        }
+    }
+
+    sort_threads(&mut list.threads, query.sort, query.descending);
+    Ok(())
+}
+
+fn sort_threads(threads: &mut [ThreadItem], sort: ThreadSortKey, descending: bool) {
+    if sort != ThreadSortKey::Server {
+        threads.sort_by(|a, b| {
+            let ordering = match sort {
+                ThreadSortKey::Server => std::cmp::Ordering::Equal,
+                ThreadSortKey::CreatedAt => {
+                    a.id.parse::<i64>()
+                        .unwrap_or(0)
+                        .cmp(&b.id.parse::<i64>().unwrap_or(0))
+                }
+                ThreadSortKey::LastUpdate => a.date.cmp(&b.date),
+                ThreadSortKey::ResponseCount => a.response_count.cmp(&b.response_count),
+                // 一度も開いていないスレッドは未読0件より後ろ (昇順では前)
+                ThreadSortKey::UnreadCount => a.unread_count.cmp(&b.unread_count),
+                ThreadSortKey::Momentum => a.momentum.total_cmp(&b.momentum),
+            };
+            if descending {
+                ordering.reverse()
+            } else {
+                ordering
+            }
+        });
+    }
+    // 安定ソートなので、並び替えた順を保ったままピン留めを先頭へ
+    threads.sort_by_key(|thread| !thread.is_pinned);
+}
+
+// subject.json を取得し、既読情報・前回からの変化・スレタイNG・ピン留めを反映した一覧を返す
+pub async fn load_thread_list(
+    http: &reqwest::Client,
+    store: &LogStore,
+) -> Result<ThreadList, String> {
+    let json_url = subject_url();
+    eprintln!(
         "[Rust fetch_threads] スレッド一覧を取得します: {}",
         json_url
     );
+    let entries = fetch_subject(http, &json_url).await?;
+    Ok(build_thread_list(entries, store, true))
+}
 
-    let client = reqwest::Client::new();
-    let api_items_result = match client.get(json_url).send().await {
-        // ... (HTTPリクエストとエラーハンドリング部分は変更なし) ...
-        Ok(response) => {
-            if response.status().is_success() {
-                response.json::<Vec<ApiThreadItem>>().await
-            } else {
-                let err_msg = format!("HTTPエラー: {} (URL: {})", response.status(), json_url);
-                eprintln!("[Rust fetch_threads] {}", err_msg);
-                return Err(err_msg);
-            }
-        }
+// 取得済みの subject.json から一覧を作る
+// update_snapshot が false の場合は前回の一覧と比べるだけで置き換えない
+// (CLI などから見たときに、アプリの「前回の更新からの変化」を変えないため)
+pub fn build_thread_list(
+    entries: Vec<SubjectEntry>,
+    store: &LogStore,
+    update_snapshot: bool,
+) -> ThreadList {
+    // SubjectEntry (タイトルはデコード済み) からフロントエンド用の ThreadItem に変換する
+    let now = chrono::Utc::now().timestamp();
+    let mut threads: Vec<ThreadItem> = entries
+        .into_iter()
+        .map(|entry| ThreadItem {
+            momentum: calculate_momentum(&entry.thread_id, entry.response_count, now),
+            id: entry.thread_id,
+            title: entry.title,
+            response_count: entry.response_count,
+            created_at: format_timestamp_from_i64(entry.date),
+            date: entry.date,
+            unread_count: None,
+            is_new_thread: false,
+            last_read_at: None,
+            is_pinned: false,
+            change: ThreadChange::Unchanged,
+        })
+        .collect();
+
+    // ログDBの既読情報と前回からの変化を付与する (失敗しても一覧自体は返す)
+    let dropped_threads = match annotate_read_states(&mut threads, store, update_snapshot) {
+        Ok(dropped) => dropped,
         Err(e) => {
-            let err_msg = format!("リクエストに失敗しました (URL: {}): {}", json_url, e);
-            eprintln!("[Rust fetch_threads] {}", err_msg);
-            return Err(err_msg);
+            eprintln!("[Rust fetch_threads] 既読情報の付与に失敗しました: {}", e);
+            Vec::new()
         }
     };
 
-    let api_items = match api_items_result {
-        Ok(items) => items,
+    let hidden_count = apply_title_ng_and_pins(&mut threads, store);
+
+    eprintln!(
+        "[Rust fetch_threads] {} 個のスレッドを取得・変換しました (スレタイNGで非表示: {} 個)。",
+        threads.len(),
+        hidden_count
+    );
+    ThreadList {
+        threads,
+        hidden_count,
+        dropped_threads,
+    }
+}
+
+// スレタイNGに一致したスレッドを取り除き、ピン留めしたスレッドを先頭へ移す
+// ピン留めしたスレッドはスレタイNGに一致しても残す。戻り値は取り除いた数
+fn apply_title_ng_and_pins(threads: &mut Vec<ThreadItem>, store: &LogStore) -> usize {
+    let title_filter = match store.list_title_ng_rules(BOARD_ID) {
+        Ok(rules) => TitleFilter::new(&rules),
         Err(e) => {
-            let err_msg = format!(
-                "JSONのパースまたはリクエスト内容のエラー (URL: {}): {}",
-                json_url, e
-            );
-            eprintln!("[Rust fetch_threads] {}", err_msg);
-            return Err(err_msg);
+            eprintln!("[Rust fetch_threads] {}", e);
+            TitleFilter::default()
         }
     };
+    let pinned = store.pinned_threads(BOARD_ID).unwrap_or_else(|e| {
+        eprintln!("[Rust fetch_threads] {}", e);
+        Vec::new()
+    });
+
+    let before = threads.len();
+    threads.retain(|thread| pinned.contains(&thread.id) || !title_filter.is_match(&thread.title));
+    let hidden_count = before - threads.len();
+
+    for thread in threads.iter_mut() {
+        thread.is_pinned = pinned.contains(&thread.id);
+    }
+    // 安定ソートなので、ピン留め以外は元の順番のまま
+    threads.sort_by_key(|thread| {
+        pinned
+            .iter()
+            .position(|id| id == &thread.id)
+            .unwrap_or(usize::MAX)
+    });
+    hidden_count
+}
+
+// 既読情報と前回の一覧からの変化を付け、今回の一覧を次回の比較用に保存する
+// 戻り値は前回の一覧から消えたスレッド
+fn annotate_read_states(
+    threads: &mut [ThreadItem],
+    store: &LogStore,
+    update_snapshot: bool,
+) -> Result<Vec<DroppedThread>, String> {
+    let read_states = store.read_states(BOARD_ID)?;
+    let snapshot: Vec<(String, String, u32)> = threads
+        .iter()
+        .map(|thread| {
+            (
+                thread.id.clone(),
+                thread.title.clone(),
+                thread.response_count,
+            )
+        })
+        .collect();
+    let previous = if update_snapshot {
+        store.replace_subject_snapshot(BOARD_ID, &snapshot)?
+    } else {
+        store.subject_snapshot(BOARD_ID)?
+    };
 
-    // ApiThreadItem からフロントエンド用の ThreadItem に変換する際にタイトルをデコード
-    let threads: Vec<ThreadItem> = api_items
+    for thread in threads.iter_mut() {
+        if let Some(read_state) = read_states.get(&thread.id) {
+            thread.unread_count = Some(thread.response_count.saturating_sub(read_state.last_read));
+            thread.last_read_at = read_state.last_read_at.map(format_timestamp_from_i64);
+        }
+        // 初回 (比較対象が無い) はすべて新着扱いにしない
+        thread.change = match previous.get(&thread.id) {
+            _ if previous.is_empty() => ThreadChange::Unchanged,
+            None => ThreadChange::New,
+            Some(before) if thread.response_count > before.response_count => {
+                ThreadChange::Updated {
+                    new_responses: thread.response_count - before.response_count,
+                }
+            }
+            Some(_) => ThreadChange::Unchanged,
+        };
+        thread.is_new_thread = thread.change == ThreadChange::New;
+    }
+
+    let current: HashSet<&str> = threads.iter().map(|thread| thread.id.as_str()).collect();
+    let mut dropped: Vec<DroppedThread> = previous
         .into_iter()
-        .map(|api_item| ThreadItem {
-            id: api_item.thread,
-            // ★★★ HTMLエンティティをデコード ★★★
-            title: decode_html_entities(&api_item.title).into_owned(),
-            response_count: api_item.number,
-            created_at: format_timestamp_from_i64(api_item.date),
-            date: api_item.date,
+        .filter(|(id, _)| !current.contains(id.as_str()))
+        .map(|(id, before)| DroppedThread {
+            id,
+            title: before.title,
+            response_count: before.response_count,
         })
         .collect();
+    dropped.sort_by(|a, b| b.id.cmp(&a.id)); // 新しいスレッドから
+    Ok(dropped)
+}
 
-    println!(
-        "[Rust fetch_threads] {} 個のスレッドを取得・変換しました。",
-        threads.len()
-    );
-    Ok(threads)
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn item(id: &str, title: &str, response_count: u32, date: i64) -> ThreadItem {
+        ThreadItem {
+            id: id.to_string(),
+            title: title.to_string(),
+            response_count,
+            created_at: format_timestamp_from_i64(date),
+            date,
+            unread_count: None,
+            is_new_thread: false,
+            last_read_at: None,
+            is_pinned: false,
+            momentum: 0.0,
+            change: ThreadChange::Unchanged,
+        }
+    }
+
+    fn thread_list() -> ThreadList {
+        let mut threads = vec![
+            item("1700000000", "Rustスレ part1", 100, 1700100000),
+            item("1700050000", "ラスト質問スレ", 10, 1700090000),
+            item("1700080000", "雑談スレ", 500, 1700099000),
+            item("1690000000", "ＲＵＳＴの本スレ", 1000, 1700000000),
+        ];
+        for (thread, (unread, momentum)) in threads.iter_mut().zip([
+            (Some(3), 2.0),
+            (None, 0.5),
+            (Some(0), 40.0),
+            (Some(10), 8.0),
+        ]) {
+            thread.unread_count = unread;
+            thread.momentum = momentum;
+        }
+        ThreadList {
+            threads,
+            hidden_count: 0,
+            dropped_threads: Vec::new(),
+        }
+    }
+
+    fn ids(list: &ThreadList) -> Vec<&str> {
+        list.threads
+            .iter()
+            .map(|thread| thread.id.as_str())
+            .collect()
+    }
+
+    fn query(sort: ThreadSortKey, descending: bool) -> ThreadQuery {
+        ThreadQuery {
+            sort,
+            descending,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn sorts_by_each_key() {
+        for (sort, descending, expected) in [
+            (
+                ThreadSortKey::Server,
+                true,
+                ["1700000000", "1700050000", "1700080000", "1690000000"],
+            ),
+            (
+                ThreadSortKey::CreatedAt,
+                false,
+                ["1690000000", "1700000000", "1700050000", "1700080000"],
+            ),
+            (
+                ThreadSortKey::LastUpdate,
+                true,
+                ["1700000000", "1700080000", "1700050000", "1690000000"],
+            ),
+            (
+                ThreadSortKey::ResponseCount,
+                true,
+                ["1690000000", "1700080000", "1700000000", "1700050000"],
+            ),
+            // 一度も開いていないスレッドは未読0件より前 (降順では最後)
+            (
+                ThreadSortKey::UnreadCount,
+                false,
+                ["1700050000", "1700080000", "1700000000", "1690000000"],
+            ),
+            (
+                ThreadSortKey::Momentum,
+                true,
+                ["1700080000", "1690000000", "1700000000", "1700050000"],
+            ),
+        ] {
+            let mut list = thread_list();
+            apply_thread_query(&mut list, &query(sort, descending)).unwrap();
+            assert_eq!(ids(&list), expected, "{:?}", sort);
+        }
+    }
+
+    #[test]
+    fn pinned_threads_stay_first_in_any_order() {
+        let mut list = thread_list();
+        list.threads[1].is_pinned = true;
+        list.threads[3].is_pinned = true;
+        apply_thread_query(&mut list, &query(ThreadSortKey::ResponseCount, false)).unwrap();
+        assert_eq!(
+            ids(&list),
+            ["1700050000", "1690000000", "1700000000", "1700080000"]
+        );
+    }
+
+    #[test]
+    fn filters_titles_with_normalization_or_regex() {
+        let mut list = thread_list();
+        let title_query = |title: &str, title_is_regex: bool| ThreadQuery {
+            title: Some(title.to_string()),
+            title_is_regex,
+            ..Default::default()
+        };
+        // 全角・大文字小文字を揃えて比べる
+        apply_thread_query(&mut list, &title_query("rust", false)).unwrap();
+        assert_eq!(ids(&list), ["1700000000", "1690000000"]);
+
+        let mut list = thread_list();
+        apply_thread_query(&mut list, &title_query("^(rust|らすと)", true)).unwrap();
+        assert_eq!(ids(&list), ["1700000000", "1700050000", "1690000000"]);
+
+        let mut list = thread_list();
+        apply_thread_query(&mut list, &title_query("  ", false)).unwrap();
+        assert_eq!(list.threads.len(), 4);
+        assert!(apply_thread_query(&mut list, &title_query("(", true)).is_err());
+    }
+
+    #[test]
+    fn momentum_is_responses_per_day() {
+        let created = 1_700_000_000;
+        assert_eq!(
+            calculate_momentum("1700000000", 100, created + 86400),
+            100.0
+        );
+        assert_eq!(calculate_momentum("1700000000", 50, created + 43200), 100.0);
+        // 作成直後は1分経ったものとみなす
+        assert_eq!(calculate_momentum("1700000000", 1, created), 1440.0);
+        assert_eq!(calculate_momentum("1700000000", 1, created - 10), 1440.0);
+        assert_eq!(calculate_momentum("abc", 100, created), 0.0);
+    }
+
+    #[test]
+    fn list_cache_keeps_the_last_list() {
+        let cache = ThreadListCache::default();
+        assert!(cache.get().is_none());
+        let mut list = thread_list();
+        cache.set(&list);
+        // 並び替えたのは渡した側だけで、覚えている一覧は取得した順のまま
+        apply_thread_query(&mut list, &query(ThreadSortKey::CreatedAt, false)).unwrap();
+        assert_eq!(ids(&cache.get().unwrap()), ids(&thread_list()));
+    }
+
+    fn entry(thread_id: &str, title: &str, response_count: u32) -> SubjectEntry {
+        SubjectEntry {
+            thread_id: thread_id.to_string(),
+            title: title.to_string(),
+            response_count,
+            date: 1_700_100_000,
+        }
+    }
+
+    fn memory_store() -> LogStore {
+        LogStore::open(std::path::Path::new(":memory:")).unwrap()
+    }
+
+    #[test]
+    fn diffs_against_the_previous_refresh() {
+        let store = memory_store();
+        let first = build_thread_list(
+            vec![
+                entry("1700000000", "続いているスレ", 100),
+                entry("1700000001", "落ちるスレ", 980),
+                entry("1700000002", "もう一つ落ちるスレ", 50),
+                entry("1700000003", "変わらないスレ", 10),
+            ],
+            &store,
+            true,
+        );
+        // 初回は比べるものが無いので、すべて変化なし
+        assert!(first
+            .threads
+            .iter()
+            .all(|thread| thread.change == ThreadChange::Unchanged && !thread.is_new_thread));
+        assert!(first.dropped_threads.is_empty());
+
+        let refreshed = vec![
+            entry("1700000004", "新しいスレ", 1),
+            entry("1700000000", "続いているスレ", 105),
+            entry("1700000003", "変わらないスレ", 10),
+        ];
+        // update_snapshot が false なら比べるだけで、何度呼んでも同じ結果になる
+        for update_snapshot in [false, true] {
+            let second = build_thread_list(refreshed.clone(), &store, update_snapshot);
+            let changes: Vec<&ThreadChange> =
+                second.threads.iter().map(|thread| &thread.change).collect();
+            assert_eq!(
+                changes,
+                [
+                    &ThreadChange::New,
+                    &ThreadChange::Updated { new_responses: 5 },
+                    &ThreadChange::Unchanged,
+                ]
+            );
+            assert!(second.threads[0].is_new_thread);
+            // 消えたスレッドは新しい順で、最後に見たときのタイトルとレス数を返す
+            let dropped: Vec<(&str, &str, u32)> = second
+                .dropped_threads
+                .iter()
+                .map(|thread| {
+                    (
+                        thread.id.as_str(),
+                        thread.title.as_str(),
+                        thread.response_count,
+                    )
+                })
+                .collect();
+            assert_eq!(
+                dropped,
+                [
+                    ("1700000002", "もう一つ落ちるスレ", 50),
+                    ("1700000001", "落ちるスレ", 980),
+                ]
+            );
+        }
+
+        // 今回の一覧で置き換えたので、次は変化なし
+        let third = build_thread_list(refreshed, &store, true);
+        assert!(third
+            .threads
+            .iter()
+            .all(|thread| thread.change == ThreadChange::Unchanged));
+        assert!(third.dropped_threads.is_empty());
+    }
+
+    #[test]
+    fn thread_change_serializes_with_a_kind_tag() {
+        assert_eq!(
+            serde_json::to_value(ThreadChange::Updated { new_responses: 3 }).unwrap(),
+            serde_json::json!({ "kind": "updated", "new_responses": 3 })
+        );
+        assert_eq!(
+            serde_json::to_value(ThreadChange::New).unwrap(),
+            serde_json::json!({ "kind": "new" })
+        );
+    }
+
+    #[test]
+    fn unread_counts_come_from_read_positions() {
+        let store = memory_store();
+        store.mark_read(BOARD_ID, "1700000000", 40).unwrap();
+        store.mark_read(BOARD_ID, "1700000001", 60).unwrap();
+        // 取得しただけで読んでいないスレッドは既読位置 0
+        store.record_seen(BOARD_ID, "1700000002", 30).unwrap();
+        let list = build_thread_list(
+            vec![
+                entry("1700000000", "読みかけ", 50),
+                entry("1700000001", "読んだ後に減った", 50),
+                entry("1700000002", "取得だけ", 30),
+                entry("1700000003", "開いていない", 20),
+            ],
+            &store,
+            true,
+        );
+        let unread: Vec<Option<u32>> = list
+            .threads
+            .iter()
+            .map(|thread| thread.unread_count)
+            .collect();
+        assert_eq!(unread, [Some(10), Some(0), Some(30), None]);
+        assert!(list.threads[0].last_read_at.is_some());
+        assert!(list.threads[2].last_read_at.is_none());
+        assert!(list.threads.iter().all(|thread| !thread.is_new_thread));
+
+        // 新着スレッドの印は、保存してある前回の一覧に無いものに付く
+        let list = build_thread_list(
+            vec![
+                entry("1700000004", "新しいスレ", 1),
+                entry("1700000000", "読みかけ", 55),
+            ],
+            &store,
+            true,
+        );
+        assert!(list.threads[0].is_new_thread);
+        assert_eq!(list.threads[0].unread_count, None);
+        assert!(!list.threads[1].is_new_thread);
+        assert_eq!(list.threads[1].unread_count, Some(15));
+    }
+
+    #[test]
+    fn pins_come_first_and_title_ng_hides_threads() {
+        let store = memory_store();
+        let no_folds = NormalizeOptions::default();
+        store
+            .add_title_ng_rule(BOARD_ID, "荒らし", false, no_folds)
+            .unwrap();
+        store
+            .add_title_ng_rule(BOARD_ID, "^ワッチョイ", true, no_folds)
+            .unwrap();
+        // 別の板のスレタイNGは使わない
+        store
+            .add_title_ng_rule("other", "雑談", false, no_folds)
+            .unwrap();
+        store.pin_thread(BOARD_ID, "1700000004").unwrap();
+        store.pin_thread(BOARD_ID, "1700000002").unwrap();
+
+        let list = build_thread_list(
+            vec![
+                entry("1700000000", "雑談スレ", 10),
+                entry("1700000001", "荒らしスレ", 10),
+                entry("1700000002", "ピン留めした荒らしスレ", 10),
+                entry("1700000003", "ワッチョイありスレ", 10),
+                entry("1700000004", "ピン留めしたスレ", 10),
+                entry("1700000005", "質問スレ", 10),
+            ],
+            &store,
+            true,
+        );
+        // ピン留めはピン留めした順に先頭へ。NGに一致しても残す
+        assert_eq!(
+            ids(&list),
+            ["1700000004", "1700000002", "1700000000", "1700000005"]
+        );
+        assert_eq!(list.hidden_count, 2);
+        let pinned: Vec<bool> = list.threads.iter().map(|thread| thread.is_pinned).collect();
+        assert_eq!(pinned, [true, true, false, false]);
+
+        store.unpin_thread(BOARD_ID, "1700000002").unwrap();
+        let list = build_thread_list(
+            vec![
+                entry("1700000002", "ピン留めした荒らしスレ", 10),
+                entry("1700000004", "ピン留めしたスレ", 10),
+            ],
+            &store,
+            true,
+        );
+        assert_eq!(ids(&list), ["1700000004"]);
+        assert_eq!(list.hidden_count, 1);
+    }
 }
@@ -1,4 +1,4 @@
-const { invoke } = window.__TAURI__.core; // 以前のやり取りでこちらが機能した可能性
+const { invoke, Channel } = window.__TAURI__.core; // 以前のやり取りでこちらが機能した可能性
 
 // HTML要素の取得
 const threadListElement = document.getElementById("thread-list");
@@ -10,6 +10,11 @@ const currentThreadTitleElement = document.getElementById(
   "current-thread-title",
 );
 const responseListElement = document.getElementById("response-list");
+const favoritesListElement = document.getElementById("favorites-list");
+const favoritesStatusElement = document.getElementById("favorites-status");
+const currentThreadToolbarElement = document.getElementById("thread-toolbar");
+const replyFormElement = document.getElementById("reply-form");
+const newThreadFormElement = document.getElementById("new-thread-form");
 
 let isRefreshingThreads = false;
 
@@ -19,6 +24,7 @@ function addThreadToView(thread) {
   listItem.classList.add("thread-item");
   listItem.dataset.threadId = thread.id;
   listItem.dataset.threadTitle = thread.title; // タイトルもデータ属性として保持
+  listItem.dataset.responseCount = thread.response_count;
 
   const header = document.createElement("div");
   header.classList.add("thread-header");
@@ -36,8 +42,66 @@ function addThreadToView(thread) {
   responseCount.classList.add("thread-response-count");
   responseCount.textContent = `${thread.response_count}レス`;
 
+  const momentum = document.createElement("span");
+  momentum.classList.add("thread-momentum");
+  momentum.textContent = `勢い${Math.round(thread.momentum || 0)}`;
+
+  if (thread.is_new_thread) {
+    listItem.classList.add("new-thread");
+    const newBadge = document.createElement("span");
+    newBadge.classList.add("thread-new-badge");
+    newBadge.textContent = "新";
+    header.appendChild(newBadge);
+  } else if (thread.change && thread.change.kind === "updated") {
+    listItem.classList.add("updated-thread");
+    const updatedBadge = document.createElement("span");
+    updatedBadge.classList.add("thread-updated-badge");
+    updatedBadge.textContent = `+${thread.change.new_responses}`;
+    updatedBadge.title = "前回の更新からのレス数";
+    header.appendChild(updatedBadge);
+  }
+  if (thread.last_read_at) {
+    header.append(` (最終既読: ${thread.last_read_at})`);
+  }
+
   content.appendChild(title);
+  if (thread.unread_count !== null && thread.unread_count !== undefined) {
+    listItem.classList.add("opened-thread");
+    const unreadCount = document.createElement("span");
+    unreadCount.classList.add("thread-unread-count");
+    if (thread.unread_count > 0) {
+      unreadCount.classList.add("has-unread");
+    }
+    unreadCount.textContent = `未読${thread.unread_count}`;
+    content.appendChild(unreadCount);
+  }
   content.appendChild(responseCount);
+  content.appendChild(momentum);
+
+  // お気に入り登録ボタン
+  const favoriteButton = document.createElement("button");
+  favoriteButton.classList.add("thread-favorite-button");
+  favoriteButton.textContent = "☆";
+  favoriteButton.title = "お気に入りに追加";
+  favoriteButton.addEventListener("click", async (event) => {
+    event.stopPropagation(); // スレッドを開かない
+    await addFavorite(thread.id, thread.title);
+  });
+  header.appendChild(favoriteButton);
+
+  // ピン留めボタン (ピン留めしたスレッドは一覧の先頭に表示される)
+  if (thread.is_pinned) {
+    listItem.classList.add("pinned-thread");
+  }
+  const pinButton = document.createElement("button");
+  pinButton.classList.add("thread-pin-button");
+  pinButton.textContent = thread.is_pinned ? "📌" : "📍";
+  pinButton.title = thread.is_pinned ? "ピン留めを解除" : "ピン留め";
+  pinButton.addEventListener("click", async (event) => {
+    event.stopPropagation(); // スレッドを開かない
+    await togglePinnedThread(thread.id, thread.is_pinned);
+  });
+  header.appendChild(pinButton);
 
   listItem.appendChild(header);
   listItem.appendChild(content);
@@ -59,118 +123,407 @@ function addThreadToView(thread) {
   threadListElement.appendChild(listItem);
 }
 
-// 特定スレッドのレスポンスをメインコンテンツエリアに表示する関数
-async function displayThreadResponses(threadId, threadTitle) {
-  // ... (既存のレスポンスクリア処理、タイトル表示処理は変更なし) ...
-  if (!responseListElement || typeof invoke !== "function") {
-    /* ...エラー処理... */ return;
+// ID表示用の文字列を組み立てる関数
+function formatIdDisplayText(response) {
+  let idDisplayText = response.user_id_info || "";
+  // ★★★ IDカウンター表示の追加 ★★★
+  if (response.parsed_user_id && response.id_total_count > 1) {
+    // parsed_user_id があり、総投稿数が0より大きい場合
+    idDisplayText += ` [${response.id_occurrence_count}/${response.id_total_count}]`;
+  }
+  return idDisplayText.trim();
+}
+
+// レス1件分のDOM要素を作成する関数
+function createResponseElement(response) {
+  // response は拡張された ResponseItem 型
+  const resItem = document.createElement("li");
+  resItem.classList.add("response-item");
+  resItem.dataset.responseId = response.id;
+
+  const resHeader = document.createElement("div");
+  resHeader.classList.add("response-header");
+
+  const authorSpan = document.createElement("span");
+  authorSpan.classList.add("response-author");
+  authorSpan.textContent = response.author || "名無しさん";
+
+  if (response.mail) {
+    /* ...メールリンク作成... */
+  }
+
+  const dateSpan = document.createElement("span");
+  dateSpan.classList.add("response-created-at");
+  dateSpan.textContent = response.created_at;
+
+  const idInfoSpan = document.createElement("span");
+  idInfoSpan.classList.add("response-user-id"); // 必要ならCSSでスタイル調整
+  if (response.parsed_user_id) {
+    idInfoSpan.dataset.userId = response.parsed_user_id;
+    idInfoSpan.dataset.userIdInfo = response.user_id_info || "";
+    idInfoSpan.dataset.occurrence = response.id_occurrence_count;
+  }
+  const idDisplayText = formatIdDisplayText(response);
+  idInfoSpan.textContent = idDisplayText;
+  if (response.parsed_user_id) {
+    idInfoSpan.title = "右クリックでこのIDを24時間NGにします";
+    idInfoSpan.addEventListener("contextmenu", (event) => {
+      event.preventDefault();
+      addIdNgRule(response.parsed_user_id);
+    });
   }
-  responseListElement.innerHTML = "";
-  mainContentPlaceholder.style.display = "none";
-  currentThreadTitleElement.textContent = threadTitle;
-  currentThreadTitleElement.style.display = "block";
 
+  resHeader.appendChild(authorSpan);
+  // 自分の書き込み (書き込み履歴と照合できたレス)
+  if (response.is_mine) {
+    resItem.classList.add("my-response");
+    const mineBadge = document.createElement("span");
+    mineBadge.classList.add("my-response-badge");
+    mineBadge.textContent = "自分";
+    resHeader.appendChild(mineBadge);
+  }
+  resHeader.appendChild(dateSpan);
+  if (idDisplayText) {
+    // ID情報があれば表示
+    resHeader.appendChild(idInfoSpan);
+  }
+
+  const resContent = document.createElement("div");
+  resContent.classList.add("response-content");
+  resContent.innerHTML = response.content;
+
+  // NGに一致したレスは番号を残して折りたたみ、クリックで中身を表示する
+  if (response.ng) {
+    resItem.classList.add("response-muted");
+    const ngNotice = document.createElement("div");
+    ngNotice.classList.add("response-ng-notice");
+    const ngLabel = formatNgMatch(response.ng);
+    ngNotice.textContent = `${response.id} あぼーん (${ngLabel})`;
+    ngNotice.title = "クリックで表示/非表示を切り替えます";
+    ngNotice.addEventListener("click", () => {
+      resItem.classList.toggle("ng-revealed");
+    });
+    resItem.appendChild(ngNotice);
+  }
+
+  resItem.appendChild(resHeader);
+  resItem.appendChild(resContent);
+
+  loadImagesViaProxy(resContent);
+  return resItem;
+}
+
+const NG_TARGET_LABELS = {
+  word: "NGワード",
+  id: "NG ID",
+  name: "NGネーム",
+  trip: "NGトリップ",
+  regex: "NG正規表現",
+};
+
+function formatNgMatch(ng) {
+  if (ng.chain_from) {
+    return `連鎖NG: >>${ng.chain_from}`;
+  }
+  return `${NG_TARGET_LABELS[ng.target] || "NG"}: ${ng.pattern}`;
+}
+
+// IDを24時間NGにして、表示中のスレッドを読み込み直す
+async function addIdNgRule(userId) {
+  if (!confirm(`ID:${userId} を24時間NGにしますか？`)) {
+    return;
+  }
   try {
-    console.log(
-      `[JS] Invoking 'fetch_thread_content' for threadId: ${threadId}`,
+    await invoke("add_ng_rule", {
+      rule: {
+        target: "id",
+        pattern: userId,
+        scope: { type: "global" },
+        duration_secs: 24 * 60 * 60,
+      },
+    });
+  } catch (error) {
+    console.error("[JS] NGルールの追加に失敗しました:", error);
+    alert(`NGルールの追加に失敗しました: ${error}`);
+    return;
+  }
+  if (currentStreamingThreadId) {
+    displayThreadResponses(
+      currentStreamingThreadId,
+      currentThreadTitleElement.textContent,
     );
-    const responses = await invoke("fetch_thread_content", {
-      threadId: threadId,
+  }
+}
+
+// ★★★ resContent内のImgur画像をプロキシ経由で読み込む ★★★
+function loadImagesViaProxy(resContent) {
+  const imagesInPost = resContent.querySelectorAll("img");
+  imagesInPost.forEach(async (imgElement) => {
+    const originalSrc = imgElement.getAttribute("src");
+    // i.imgur.com の画像のみを対象とする (他のドメインは必要に応じて追加)
+    if (originalSrc && originalSrc.startsWith("https://i.imgur.com/")) {
+      console.log(
+        "[JS] Imgur画像を発見、プロキシ経由で取得試行: ",
+        originalSrc,
+      );
+
+      // 一時的にローディング画像などに差し替えるか、srcを空にする (任意)
+      // imgElement.src = "path/to/loading.gif";
+      const tempOriginalSrc = originalSrc; // エラー時に戻すためなどに保持
+
+      try {
+        const dataUrl = await invoke("fetch_image_as_base64", {
+          url: tempOriginalSrc,
+        });
+        imgElement.src = dataUrl; // Base64データURIに置き換え
+        console.log("[JS] 画像のプロキシ取得成功: ", tempOriginalSrc);
+      } catch (error) {
+        console.error(
+          "[JS] 画像のプロキシ取得失敗",
+          tempOriginalSrc,
+          ":",
+          error,
+        );
+        // エラーの場合、壊れた画像アイコンのままにするか、非表示にするか、
+        // あるいは元のsrcに戻してブラウザに再試行させるか (403が再度発生する可能性)
+        // imgElement.src = tempOriginalSrc; // ← これだと403が再発する可能性
+        imgElement.alt = `画像読み込み失敗: ${tempOriginalSrc}`; // altテキスト設定
+      }
+    }
+  });
+}
+
+// ストリーミング完了後、確定したIDの総投稿数で [n/m] 表示を更新する関数
+function updateIdTotalCounts(idTotalCounts) {
+  responseListElement
+    .querySelectorAll(".response-user-id[data-user-id]")
+    .forEach((idInfoSpan) => {
+      const response = {
+        user_id_info: idInfoSpan.dataset.userIdInfo,
+        parsed_user_id: idInfoSpan.dataset.userId,
+        id_occurrence_count: Number(idInfoSpan.dataset.occurrence),
+        id_total_count: idTotalCounts[idInfoSpan.dataset.userId] || 0,
+      };
+      idInfoSpan.textContent = formatIdDisplayText(response);
     });
-    console.log("[JS] Responses received from Rust:", responses); // responses に新しいフィールドが含まれる
+}
 
-    if (responses && responses.length > 0) {
-      responses.forEach((response) => {
-        // response は拡張された ResponseItem 型
-        const resItem = document.createElement("li");
-        resItem.classList.add("response-item");
+// 指定したレス番号までスクロールする関数
+function scrollToResponse(number) {
+  if (!number) {
+    return;
+  }
+  const resItem = responseListElement.querySelector(
+    `.response-item[data-response-id="${number}"]`,
+  );
+  if (resItem) {
+    resItem.scrollIntoView({ block: "start" });
+  }
+}
 
-        const resHeader = document.createElement("div");
-        resHeader.classList.add("response-header");
+// 読み込み進捗を表示する関数
+function showLoadProgress(receivedBytes, contentLength) {
+  let progressElement = document.getElementById("thread-load-progress");
+  if (!progressElement) {
+    progressElement = document.createElement("div");
+    progressElement.id = "thread-load-progress";
+    currentThreadTitleElement.after(progressElement);
+  }
+  const receivedKb = Math.round(receivedBytes / 1024);
+  if (contentLength) {
+    const percent = Math.floor((receivedBytes / contentLength) * 100);
+    progressElement.textContent = `読み込み中... ${receivedKb}KB (${percent}%)`;
+  } else {
+    progressElement.textContent = `読み込み中... ${receivedKb}KB`;
+  }
+  progressElement.style.display = "block";
+}
 
-        const authorSpan = document.createElement("span");
-        authorSpan.classList.add("response-author");
-        authorSpan.textContent = response.author || "名無しさん";
+function hideLoadProgress() {
+  const progressElement = document.getElementById("thread-load-progress");
+  if (progressElement) {
+    progressElement.style.display = "none";
+  }
+}
 
-        if (response.mail) {
-          /* ...メールリンク作成... */
-        }
+// --- 既読位置の記録 ---
+// 画面に表示された最大のレス番号を覚えておき、一定間隔で Rust 側に送る
+let readPositionObserver = null;
+let readPositionThreadId = null;
+let readPositionMax = 0;
+let readPositionReported = 0;
+let readPositionTimer = null;
 
-        const dateSpan = document.createElement("span");
-        dateSpan.classList.add("response-created-at");
-        dateSpan.textContent = response.created_at;
-
-        const idInfoSpan = document.createElement("span");
-        idInfoSpan.classList.add("response-user-id"); // 必要ならCSSでスタイル調整
-        let idDisplayText = response.user_id_info || "";
-        // ★★★ IDカウンター表示の追加 ★★★
-        if (response.parsed_user_id && response.id_total_count > 1) {
-          // parsed_user_id があり、総投稿数が0より大きい場合
-          idDisplayText += ` [${response.id_occurrence_count}/${response.id_total_count}]`;
+function startReadPositionTracking(threadId) {
+  stopReadPositionTracking();
+  readPositionThreadId = threadId;
+  readPositionMax = 0;
+  readPositionReported = 0;
+  readPositionObserver = new IntersectionObserver((entries) => {
+    entries.forEach((entry) => {
+      if (entry.isIntersecting) {
+        const number = Number(entry.target.dataset.responseId);
+        if (number > readPositionMax) {
+          readPositionMax = number;
         }
-        idInfoSpan.textContent = idDisplayText.trim();
+      }
+    });
+  });
+  readPositionTimer = setInterval(reportReadPosition, 2000);
+}
 
-        resHeader.appendChild(authorSpan);
-        resHeader.appendChild(dateSpan);
-        if (idDisplayText) {
-          // ID情報があれば表示
-          resHeader.appendChild(idInfoSpan);
-        }
+function stopReadPositionTracking() {
+  if (readPositionObserver) {
+    reportReadPosition();
+    readPositionObserver.disconnect();
+    readPositionObserver = null;
+  }
+  if (readPositionTimer) {
+    clearInterval(readPositionTimer);
+    readPositionTimer = null;
+  }
+}
 
-        const resContent = document.createElement("div");
-        resContent.classList.add("response-content");
-        resContent.innerHTML = response.content;
-
-        resItem.appendChild(resHeader);
-        resItem.appendChild(resContent);
-        responseListElement.appendChild(resItem);
-
-        // ★★★ ここから追加: resContent内のImgur画像をプロキシ経由で読み込む ★★★
-        const imagesInPost = resContent.querySelectorAll("img");
-        imagesInPost.forEach(async (imgElement) => {
-          const originalSrc = imgElement.getAttribute("src");
-          // i.imgur.com の画像のみを対象とする (他のドメインは必要に応じて追加)
-          if (originalSrc && originalSrc.startsWith("https://i.imgur.com/")) {
-            console.log(
-              "[JS] Imgur画像を発見、プロキシ経由で取得試行: ",
-              originalSrc,
-            );
+async function reportReadPosition() {
+  if (!readPositionThreadId || readPositionMax <= readPositionReported) {
+    return;
+  }
+  const threadId = readPositionThreadId;
+  const lastRead = readPositionMax;
+  readPositionReported = lastRead;
+  try {
+    await invoke("mark_thread_read", { threadId, lastRead });
+    updateThreadListUnread(threadId, lastRead);
+  } catch (error) {
+    console.error("[JS] 既読位置の記録に失敗しました:", error);
+  }
+}
 
-            // 一時的にローディング画像などに差し替えるか、srcを空にする (任意)
-            // imgElement.src = "path/to/loading.gif";
-            const tempOriginalSrc = originalSrc; // エラー時に戻すためなどに保持
-
-            try {
-              const dataUrl = await invoke("fetch_image_as_base64", {
-                url: tempOriginalSrc,
-              });
-              imgElement.src = dataUrl; // Base64データURIに置き換え
-              console.log("[JS] 画像のプロキシ取得成功: ", tempOriginalSrc);
-            } catch (error) {
-              console.error(
-                "[JS] 画像のプロキシ取得失敗",
-                tempOriginalSrc,
-                ":",
-                error,
-              );
-              // エラーの場合、壊れた画像アイコンのままにするか、非表示にするか、
-              // あるいは元のsrcに戻してブラウザに再試行させるか (403が再度発生する可能性)
-              // imgElement.src = tempOriginalSrc; // ← これだと403が再発する可能性
-              imgElement.alt = `画像読み込み失敗: ${tempOriginalSrc}`; // altテキスト設定
-            }
-          }
+// スレッド一覧の未読数表示をその場で更新する
+function updateThreadListUnread(threadId, lastRead) {
+  const listItem = threadListElement.querySelector(
+    `.thread-item[data-thread-id="${threadId}"]`,
+  );
+  if (!listItem) {
+    return;
+  }
+  const responseCount = Number(listItem.dataset.responseCount || 0);
+  const unread = Math.max(responseCount - lastRead, 0);
+  let unreadCount = listItem.querySelector(".thread-unread-count");
+  if (!unreadCount) {
+    unreadCount = document.createElement("span");
+    unreadCount.classList.add("thread-unread-count");
+    listItem
+      .querySelector(".thread-content")
+      .insertBefore(
+        unreadCount,
+        listItem.querySelector(".thread-response-count"),
+      );
+  }
+  unreadCount.classList.toggle("has-unread", unread > 0);
+  unreadCount.textContent = `未読${unread}`;
+}
+
+// 特定スレッドのレスポンスをメインコンテンツエリアに表示する関数
+// レスは受信しながら少しずつ届くので、届いた分から順に描画する
+let currentStreamingThreadId = null;
+
+// targetNumber を指定した場合は、読み込み後に既読位置ではなくそのレスへ移動する
+async function displayThreadResponses(threadId, threadTitle, targetNumber) {
+  // ... (既存のレスポンスクリア処理、タイトル表示処理は変更なし) ...
+  if (!responseListElement || typeof invoke !== "function") {
+    /* ...エラー処理... */ return;
+  }
+  responseListElement.innerHTML = "";
+  mainContentPlaceholder.style.display = "none";
+  currentThreadTitleElement.textContent = threadTitle;
+  currentThreadTitleElement.style.display = "block";
+  currentStreamingThreadId = threadId;
+  currentThreadToolbarElement.style.display = "flex";
+  replyFormElement.style.display = "flex";
+  newThreadFormElement.style.display = "none";
+  document.getElementById("reply-status").textContent = "";
+  hideReplyPreview();
+  restoreDraft(threadId);
+  startReadPositionTracking(threadId);
+
+  // 前回の既読位置 (読み込み完了後にそこまでスクロールする)
+  let lastRead = 0;
+  try {
+    const readState = await invoke("get_read_state", { threadId });
+    lastRead = readState ? readState.last_read : 0;
+  } catch (error) {
+    console.error("[JS] 既読情報の取得に失敗しました:", error);
+  }
+
+  const onEvent = new Channel();
+  onEvent.onmessage = (message) => {
+    // 別のスレッドに切り替わった後に届いたイベントは無視する
+    if (currentStreamingThreadId !== threadId) {
+      return;
+    }
+    switch (message.event) {
+      case "started":
+        showLoadProgress(0, message.data.content_length);
+        break;
+      case "progress":
+        showLoadProgress(
+          message.data.received_bytes,
+          message.data.content_length,
+        );
+        break;
+      case "responses": {
+        const fragment = document.createDocumentFragment();
+        message.data.responses.forEach((response) => {
+          const resItem = createResponseElement(response);
+          readPositionObserver.observe(resItem);
+          fragment.appendChild(resItem);
         });
-        // ★★★ 追加ここまで ★★★
-      });
-    } else {
-      /* ...レスなしの場合の処理... */
+        responseListElement.appendChild(fragment);
+        break;
+      }
+      case "finished":
+        console.log(
+          `[JS] ストリーミング完了: ${message.data.total_responses} レス`,
+        );
+        updateIdTotalCounts(message.data.id_total_counts);
+        hideLoadProgress();
+        scrollToResponse(targetNumber || lastRead);
+        break;
     }
+  };
+
+  try {
+    console.log(
+      `[JS] Invoking 'fetch_thread_content_stream' for threadId: ${threadId}`,
+    );
+    await invoke("fetch_thread_content_stream", {
+      threadId: threadId,
+      onEvent: onEvent,
+    });
   } catch (error) {
-    /* ...エラー処理... */
+    console.error("[JS] スレッド内容の取得に失敗しました:", error);
+    hideLoadProgress();
+    const errorItem = document.createElement("li");
+    errorItem.textContent = `スレッド内容の取得に失敗しました。エラー: ${error}`;
+    responseListElement.appendChild(errorItem);
   }
 }
 
-async function loadAndDisplayThreads() {
+// 並び替え・絞り込みの条件 (query_threads に渡す)
+function currentThreadQuery(refresh) {
+  const sortOrderButton = document.getElementById("thread-sort-order");
+  return {
+    sort: document.getElementById("thread-sort-select")?.value || "server",
+    descending: sortOrderButton?.dataset.descending === "true",
+    title: document.getElementById("thread-filter-input")?.value || null,
+    title_is_regex: !!document.getElementById("thread-filter-regex")?.checked,
+    refresh,
+  };
+}
+
+// refresh が false の場合は、前回取得した一覧を並び替え・絞り込みし直すだけ
+async function loadAndDisplayThreads(refresh = true) {
   console.log("[JS] loadAndDisplayThreads called");
   if (!threadListElement || typeof invoke !== "function") {
     console.error(
@@ -182,8 +535,11 @@ async function loadAndDisplayThreads() {
 
   try {
     console.log("[JS] Invoking 'fetch_threads' to get thread list from URL...");
-    const threads = await invoke("fetch_threads"); // ★★★ 引数を削除 ★★★
-    console.log("[JS] Threads received from Rust (fetch_threads):", threads);
+    const threadList = await invoke("query_threads", {
+      query: currentThreadQuery(refresh),
+    });
+    console.log("[JS] Threads received from Rust (fetch_threads):", threadList);
+    const threads = threadList.threads;
 
     if (threads && threads.length > 0) {
       threadListElement.innerHTML = ""; // 既存の項目をクリア
@@ -191,6 +547,17 @@ async function loadAndDisplayThreads() {
     } else {
       threadListElement.innerHTML = "<li>表示できるスレッドがありません。</li>";
     }
+    if (threadList.dropped_threads.length > 0) {
+      threadListElement.appendChild(
+        createDroppedThreadsElement(threadList.dropped_threads),
+      );
+    }
+    if (threadList.hidden_count > 0) {
+      const hiddenItem = document.createElement("li");
+      hiddenItem.classList.add("thread-hidden-count");
+      hiddenItem.textContent = `スレタイNGで ${threadList.hidden_count} 件を非表示`;
+      threadListElement.appendChild(hiddenItem);
+    }
   } catch (error) {
     console.error(
       "[JS] スレッドの読み込みに失敗しました (fetch_threads):",
@@ -200,6 +567,243 @@ async function loadAndDisplayThreads() {
   }
 }
 
+async function togglePinnedThread(threadId, isPinned) {
+  try {
+    await invoke(isPinned ? "unpin_thread" : "pin_thread", { threadId });
+    await loadAndDisplayThreads();
+  } catch (error) {
+    console.error("[JS] ピン留めの変更に失敗しました:", error);
+  }
+}
+
+// --- お気に入り ---
+async function addFavorite(threadId, title) {
+  try {
+    await invoke("add_favorite", { threadId, title, folderId: null });
+    await loadAndDisplayFavorites();
+  } catch (error) {
+    console.error("[JS] お気に入りの追加に失敗しました:", error);
+  }
+}
+
+async function loadAndDisplayFavorites() {
+  if (!favoritesListElement) {
+    return;
+  }
+  try {
+    const favoriteList = await invoke("list_favorites");
+    renderFavorites(favoriteList);
+  } catch (error) {
+    console.error("[JS] お気に入りの読み込みに失敗しました:", error);
+    favoritesListElement.innerHTML = `<li>お気に入りの読み込みに失敗しました。<br>エラー: ${error}</li>`;
+  }
+}
+
+function renderFavorites(favoriteList) {
+  favoritesListElement.innerHTML = "";
+
+  // フォルダに入っていないお気に入り
+  favoriteList.favorites
+    .filter((favorite) => favorite.folder_id === null)
+    .forEach((favorite) => {
+      favoritesListElement.appendChild(createFavoriteElement(favorite));
+    });
+
+  favoriteList.folders.forEach((folder) => {
+    const folderItem = document.createElement("li");
+    folderItem.classList.add("favorite-folder");
+
+    const folderHeader = document.createElement("div");
+    folderHeader.classList.add("favorite-folder-header");
+    folderHeader.textContent = `📁 ${folder.name}`;
+    folderHeader.draggable = true;
+    folderHeader.addEventListener("dragstart", (event) => {
+      event.dataTransfer.setData("text/favorite-folder-id", folder.id);
+    });
+    // フォルダ見出しへのドロップ: お気に入りはフォルダの末尾へ、フォルダはその位置へ
+    folderHeader.addEventListener("dragover", (event) =>
+      event.preventDefault(),
+    );
+    folderHeader.addEventListener("drop", async (event) => {
+      event.preventDefault();
+      const threadId = event.dataTransfer.getData("text/favorite-thread-id");
+      const draggedFolderId = event.dataTransfer.getData(
+        "text/favorite-folder-id",
+      );
+      if (threadId) {
+        await moveFavorite(threadId, folder.id, Number.MAX_SAFE_INTEGER);
+      } else if (draggedFolderId) {
+        await invoke("move_favorite_folder", {
+          folderId: Number(draggedFolderId),
+          position: folder.position,
+        });
+        await loadAndDisplayFavorites();
+      }
+    });
+
+    const renameButton = document.createElement("button");
+    renameButton.textContent = "名前変更";
+    renameButton.addEventListener("click", async () => {
+      const name = prompt("フォルダ名", folder.name);
+      if (name) {
+        await invoke("rename_favorite_folder", { folderId: folder.id, name });
+        await loadAndDisplayFavorites();
+      }
+    });
+    const deleteButton = document.createElement("button");
+    deleteButton.textContent = "削除";
+    deleteButton.addEventListener("click", async () => {
+      if (confirm(`フォルダ「${folder.name}」を削除しますか？`)) {
+        await invoke("delete_favorite_folder", { folderId: folder.id });
+        await loadAndDisplayFavorites();
+      }
+    });
+    folderHeader.appendChild(renameButton);
+    folderHeader.appendChild(deleteButton);
+    folderItem.appendChild(folderHeader);
+
+    const folderList = document.createElement("ul");
+    favoriteList.favorites
+      .filter((favorite) => favorite.folder_id === folder.id)
+      .forEach((favorite) => {
+        folderList.appendChild(createFavoriteElement(favorite));
+      });
+    folderItem.appendChild(folderList);
+    favoritesListElement.appendChild(folderItem);
+  });
+
+  if (
+    favoriteList.favorites.length === 0 &&
+    favoriteList.folders.length === 0
+  ) {
+    favoritesListElement.innerHTML =
+      '<li class="favorite-empty">お気に入りはありません。</li>';
+  }
+}
+
+function createFavoriteElement(favorite) {
+  const listItem = document.createElement("li");
+  listItem.classList.add("favorite-item");
+  if (favorite.is_dropped) {
+    listItem.classList.add("dropped");
+  }
+  listItem.draggable = true;
+
+  const title = document.createElement("span");
+  title.classList.add("favorite-title");
+  title.textContent = favorite.title;
+  title.title = favorite.title;
+  listItem.appendChild(title);
+
+  if (favorite.new_response_count > 0) {
+    const newCount = document.createElement("span");
+    newCount.classList.add("favorite-new-count");
+    newCount.textContent = `+${favorite.new_response_count}`;
+    listItem.appendChild(newCount);
+  }
+  if (favorite.is_dropped) {
+    const dropped = document.createElement("span");
+    dropped.classList.add("favorite-dropped");
+    dropped.textContent = "dat落ち";
+    listItem.appendChild(dropped);
+  }
+
+  const removeButton = document.createElement("button");
+  removeButton.textContent = "✕";
+  removeButton.title = "お気に入りから削除";
+  removeButton.addEventListener("click", async (event) => {
+    event.stopPropagation();
+    await invoke("remove_favorite", { threadId: favorite.thread_id });
+    await loadAndDisplayFavorites();
+  });
+  listItem.appendChild(removeButton);
+
+  listItem.addEventListener("click", async () => {
+    await displayThreadResponses(favorite.thread_id, favorite.title);
+    await loadAndDisplayFavorites(); // 新着数の表示を更新
+  });
+
+  // ドラッグ&ドロップで並び替え (ドロップ先の前に挿入する)
+  listItem.addEventListener("dragstart", (event) => {
+    event.dataTransfer.setData("text/favorite-thread-id", favorite.thread_id);
+  });
+  listItem.addEventListener("dragover", (event) => event.preventDefault());
+  listItem.addEventListener("drop", async (event) => {
+    event.preventDefault();
+    event.stopPropagation();
+    const threadId = event.dataTransfer.getData("text/favorite-thread-id");
+    if (threadId && threadId !== favorite.thread_id) {
+      await moveFavorite(threadId, favorite.folder_id, favorite.position);
+    }
+  });
+  return listItem;
+}
+
+async function moveFavorite(threadId, folderId, position) {
+  try {
+    await invoke("move_favorite", { threadId, folderId, position });
+  } catch (error) {
+    console.error("[JS] お気に入りの移動に失敗しました:", error);
+  }
+  await loadAndDisplayFavorites();
+}
+
+async function checkFavorites() {
+  const checkButton = document.getElementById("check-favorites-button");
+  checkButton.disabled = true;
+  favoritesStatusElement.textContent = "チェック中...";
+  try {
+    const results = await invoke("check_favorites");
+    const updated = results.filter(
+      (result) => result.status.status === "updated",
+    );
+    const newResponses = updated.reduce(
+      (sum, result) => sum + result.status.new_responses,
+      0,
+    );
+    favoritesStatusElement.textContent = `${updated.length}件更新 (+${newResponses})`;
+    await loadAndDisplayFavorites();
+  } catch (error) {
+    console.error("[JS] お気に入りの更新チェックに失敗しました:", error);
+    favoritesStatusElement.textContent = "チェック失敗";
+  } finally {
+    checkButton.disabled = false;
+  }
+}
+
+function setupFavoritesPanel() {
+  const checkButton = document.getElementById("check-favorites-button");
+  const addFolderButton = document.getElementById("add-favorite-folder-button");
+  if (!favoritesListElement || !checkButton || !addFolderButton) {
+    console.error("[JS] お気に入りパネルの要素が見つかりません。");
+    return;
+  }
+  checkButton.addEventListener("click", checkFavorites);
+  addFolderButton.addEventListener("click", async () => {
+    const name = prompt("新しいフォルダ名");
+    if (name) {
+      try {
+        await invoke("create_favorite_folder", { name });
+      } catch (error) {
+        alert(`フォルダの作成に失敗しました: ${error}`);
+      }
+      await loadAndDisplayFavorites();
+    }
+  });
+  // フォルダ外 (リスト末尾) へのドロップ
+  favoritesListElement.addEventListener("dragover", (event) =>
+    event.preventDefault(),
+  );
+  favoritesListElement.addEventListener("drop", async (event) => {
+    event.preventDefault();
+    const threadId = event.dataTransfer.getData("text/favorite-thread-id");
+    if (threadId) {
+      await moveFavorite(threadId, null, Number.MAX_SAFE_INTEGER);
+    }
+  });
+  loadAndDisplayFavorites();
+}
+
 // --- リサイズ機能のロジック ---
 function initializeResizablePanels() {
   const container = document.querySelector(".container");
@@ -360,9 +964,870 @@ function setupThreadListRefresh() {
 }
 
 // DOMが読み込まれたらスレッドを読み込む
+// 前回の一覧から消えたスレッド (dat落ち)。クリックで保存済みのログを開く
+function createDroppedThreadsElement(droppedThreads) {
+  const section = document.createElement("li");
+  section.classList.add("dropped-threads");
+  const details = document.createElement("details");
+  const summary = document.createElement("summary");
+  summary.textContent = `前回から消えたスレッド (${droppedThreads.length})`;
+  details.appendChild(summary);
+
+  const list = document.createElement("ul");
+  droppedThreads.forEach((thread) => {
+    const item = document.createElement("li");
+    item.classList.add("dropped-thread-item");
+    item.textContent = `${thread.title} (${thread.response_count})`;
+    item.title = thread.title;
+    item.addEventListener("click", () => {
+      displayThreadResponses(thread.id, thread.title);
+    });
+    list.appendChild(item);
+  });
+  details.appendChild(list);
+  section.appendChild(details);
+  return section;
+}
+
+function setupThreadListControls() {
+  const filterInput = document.getElementById("thread-filter-input");
+  const filterRegex = document.getElementById("thread-filter-regex");
+  const sortSelect = document.getElementById("thread-sort-select");
+  const sortOrderButton = document.getElementById("thread-sort-order");
+  if (!filterInput || !filterRegex || !sortSelect || !sortOrderButton) {
+    return;
+  }
+  let filterTimer = null;
+  filterInput.addEventListener("input", () => {
+    clearTimeout(filterTimer);
+    filterTimer = setTimeout(() => loadAndDisplayThreads(false), 300);
+  });
+  filterRegex.addEventListener("change", () => loadAndDisplayThreads(false));
+  sortSelect.addEventListener("change", () => loadAndDisplayThreads(false));
+  sortOrderButton.addEventListener("click", () => {
+    const descending = sortOrderButton.dataset.descending !== "true";
+    sortOrderButton.dataset.descending = String(descending);
+    sortOrderButton.textContent = descending ? "降順" : "昇順";
+    loadAndDisplayThreads(false);
+  });
+}
+
+// --- スレッドの書き出し ---
+const EXPORT_EXTENSIONS = {
+  html: "html",
+  markdown: "md",
+  json: "json",
+  dat: "dat",
+};
+
+async function exportCurrentThread() {
+  const threadId = currentStreamingThreadId;
+  if (!threadId) {
+    return;
+  }
+  const format = document.getElementById("export-format-select").value;
+  const applyNg = document.getElementById("export-apply-ng").checked;
+  const statusElement = document.getElementById("export-status");
+  const extension = EXPORT_EXTENSIONS[format];
+
+  const path = await window.__TAURI__.dialog.save({
+    defaultPath: `${threadId}.${extension}`,
+    filters: [{ name: format.toUpperCase(), extensions: [extension] }],
+  });
+  if (!path) {
+    return; // キャンセル
+  }
+  statusElement.textContent = "書き出し中...";
+  try {
+    await invoke("export_thread", { threadId, format, path, applyNg });
+    statusElement.textContent = `書き出しました: ${path}`;
+  } catch (error) {
+    console.error("[JS] スレッドの書き出しに失敗しました:", error);
+    statusElement.textContent = `書き出しに失敗しました: ${error}`;
+  }
+}
+
+// --- レスの書き込み ---
+// bbs.cgi の応答は Rust 側で判定し、status ごとに扱う
+async function postReply(confirmation = null) {
+  const threadId = currentStreamingThreadId;
+  if (!threadId) {
+    return;
+  }
+  const bodyElement = document.getElementById("reply-body");
+  const statusElement = document.getElementById("reply-status");
+  const submitButton = document.getElementById("reply-submit-button");
+  const form = {
+    thread_id: threadId,
+    name: document.getElementById("reply-name").value,
+    mail: document.getElementById("reply-mail").value,
+    body: bodyElement.value,
+    confirmation,
+  };
+
+  statusElement.textContent = "書き込み中...";
+  submitButton.disabled = true;
+  let result;
+  try {
+    result = await invoke("post_response", { form });
+  } catch (error) {
+    console.error("[JS] 書き込みに失敗しました:", error);
+    statusElement.textContent = `書き込みに失敗しました: ${error}`;
+    return;
+  } finally {
+    submitButton.disabled = false;
+  }
+
+  switch (result.status) {
+    case "ok":
+      statusElement.textContent = "書き込みました。";
+      bodyElement.value = "";
+      displayThreadResponses(threadId, currentThreadTitleElement.textContent);
+      break;
+    case "needs_confirmation":
+      // 書き込み確認 (Cookie の確認)。承諾したら確認ページの内容を付けて送り直す
+      if (confirm(`${result.message}\n\n書き込みますか？`)) {
+        postReply(result.fields);
+      } else {
+        statusElement.textContent = "書き込みを取りやめました。";
+      }
+      break;
+    case "rate_limited":
+      statusElement.textContent = result.retry_after_secs
+        ? `規制中です (${result.retry_after_secs}秒後に再試行): ${result.message}`
+        : `規制中です: ${result.message}`;
+      break;
+    default:
+      statusElement.textContent = `エラー: ${result.message}`;
+  }
+}
+
+// --- 書き込みのプレビュー ---
+// 本文は受信したレスと同じ形で組み立てられて返ってくるので、同じ createResponseElement で描画する
+async function previewReply() {
+  const threadId = currentStreamingThreadId;
+  if (!threadId) {
+    return;
+  }
+  const statusElement = document.getElementById("reply-status");
+  const form = {
+    thread_id: threadId,
+    name: document.getElementById("reply-name").value,
+    mail: document.getElementById("reply-mail").value,
+    body: document.getElementById("reply-body").value,
+  };
+  statusElement.textContent = "プレビューを作成中...";
+  let preview;
+  try {
+    preview = await invoke("preview_post", { form });
+  } catch (error) {
+    console.error("[JS] プレビューの作成に失敗しました:", error);
+    statusElement.textContent = `プレビューを作成できませんでした: ${error}`;
+    return;
+  }
+  if (currentStreamingThreadId !== threadId) {
+    return;
+  }
+  statusElement.textContent = "";
+
+  const previewElement = document.getElementById("reply-preview");
+  previewElement.innerHTML = "";
+  const list = document.createElement("ul");
+  list.classList.add("reply-preview-list");
+  list.appendChild(createResponseElement(preview.response));
+  previewElement.appendChild(list);
+
+  const limits = document.createElement("div");
+  limits.classList.add("reply-preview-limits");
+  const maxBytes = preview.setting.message_count;
+  const maxLines = preview.setting.max_message_lines;
+  limits.textContent = `本文 ${preview.body_bytes}${maxBytes ? ` / ${maxBytes}` : ""} バイト、${preview.body_lines}${maxLines ? ` / ${maxLines}` : ""} 行`;
+  previewElement.appendChild(limits);
+
+  const warnings = [
+    ...preview.limit_problems,
+    ...preview.anchor_warnings.map((warning) => warning.message),
+  ];
+  if (warnings.length > 0) {
+    const warningList = document.createElement("ul");
+    warningList.classList.add("reply-preview-warnings");
+    warnings.forEach((message) => {
+      const item = document.createElement("li");
+      item.textContent = message;
+      warningList.appendChild(item);
+    });
+    previewElement.appendChild(warningList);
+  }
+  previewElement.style.display = "block";
+}
+
+function hideReplyPreview() {
+  const previewElement = document.getElementById("reply-preview");
+  previewElement.innerHTML = "";
+  previewElement.style.display = "none";
+}
+
+// --- 画像のアップロード ---
+// Rust 側で EXIF を落として (設定によっては縮小して) からアップロードし、本文のカーソル位置に URL を入れる
+async function uploadReplyImage(source) {
+  const statusElement = document.getElementById("reply-status");
+  statusElement.textContent = "画像をアップロード中...";
+  let upload;
+  try {
+    upload = await invoke("upload_image", { source });
+  } catch (error) {
+    console.error("[JS] 画像のアップロードに失敗しました:", error);
+    statusElement.textContent = `画像をアップロードできませんでした: ${error}`;
+    return;
+  }
+  const bodyElement = document.getElementById("reply-body");
+  const start = bodyElement.selectionStart;
+  const end = bodyElement.selectionEnd;
+  const before = bodyElement.value.slice(0, start);
+  const separator = before === "" || before.endsWith("\n") ? "" : "\n";
+  const inserted = `${separator}${upload.url}\n`;
+  bodyElement.value = before + inserted + bodyElement.value.slice(end);
+  bodyElement.selectionStart = bodyElement.selectionEnd =
+    start + inserted.length;
+  statusElement.textContent = `画像をアップロードしました (${upload.width}x${upload.height})`;
+  saveDraft();
+}
+
+async function chooseReplyImage() {
+  const path = await window.__TAURI__.dialog.open({
+    filters: [
+      { name: "画像", extensions: ["png", "jpg", "jpeg", "gif", "webp"] },
+    ],
+  });
+  if (path) {
+    uploadReplyImage({ kind: "file", path });
+  }
+}
+
+// 本文に画像を貼り付けたらアップロードする (文字の貼り付けはそのまま)
+function handleReplyPaste(event) {
+  const file = Array.from(event.clipboardData?.files || []).find((item) =>
+    item.type.startsWith("image/"),
+  );
+  if (!file) {
+    return;
+  }
+  event.preventDefault();
+  const reader = new FileReader();
+  reader.onload = () => {
+    uploadReplyImage({ kind: "clipboard", data: reader.result });
+  };
+  reader.readAsDataURL(file);
+}
+
+async function showImageUploads() {
+  responseListElement.innerHTML = "";
+  mainContentPlaceholder.style.display = "none";
+  currentThreadTitleElement.textContent = "画像のアップロード履歴";
+  currentThreadTitleElement.style.display = "block";
+  currentStreamingThreadId = null;
+  currentThreadToolbarElement.style.display = "none";
+  replyFormElement.style.display = "none";
+  newThreadFormElement.style.display = "none";
+  stopReadPositionTracking();
+
+  let uploads;
+  try {
+    uploads = await invoke("list_image_uploads", { limit: null });
+  } catch (error) {
+    console.error("[JS] アップロード履歴の取得に失敗しました:", error);
+    responseListElement.innerHTML = `<li>アップロード履歴を取得できませんでした。<br>エラー: ${error}</li>`;
+    return;
+  }
+
+  currentThreadTitleElement.textContent = `画像のアップロード履歴 (${uploads.length}件)`;
+  if (uploads.length === 0) {
+    responseListElement.innerHTML = "<li>まだ画像をアップロードしていません。</li>";
+    return;
+  }
+  const fragment = document.createDocumentFragment();
+  uploads.forEach((upload) =>
+    fragment.appendChild(createImageUploadElement(upload)),
+  );
+  responseListElement.appendChild(fragment);
+}
+
+function createImageUploadElement(upload) {
+  const item = document.createElement("li");
+  item.classList.add("response-item", "search-hit");
+
+  const header = document.createElement("div");
+  header.classList.add("response-header");
+  const title = document.createElement("span");
+  title.classList.add("search-hit-title");
+  title.textContent = `${upload.file_name} (${upload.width}x${upload.height})`;
+  const meta = document.createElement("span");
+  meta.classList.add("response-created-at");
+  meta.textContent = new Date(upload.uploaded_at * 1000).toLocaleString();
+  const deleteButton = document.createElement("button");
+  deleteButton.type = "button";
+  deleteButton.textContent = "削除";
+  deleteButton.addEventListener("click", async () => {
+    if (!confirm(`アップロード先からも削除します。\n${upload.url}`)) {
+      return;
+    }
+    try {
+      await invoke("delete_image_upload", { id: upload.id });
+      item.remove();
+    } catch (error) {
+      console.error("[JS] 画像の削除に失敗しました:", error);
+      alert(`画像を削除できませんでした: ${error}`);
+    }
+  });
+  header.appendChild(title);
+  header.appendChild(meta);
+  header.appendChild(deleteButton);
+
+  const url = document.createElement("div");
+  url.classList.add("response-content");
+  url.textContent = upload.url;
+
+  item.appendChild(header);
+  item.appendChild(url);
+  return item;
+}
+
+// --- 下書き ---
+// 入力のたびに Rust 側へ送る (ログDBへの書き込みは Rust 側でまとめて遅らせる)
+function saveDraft() {
+  const threadId = currentStreamingThreadId;
+  if (!threadId) {
+    return;
+  }
+  const draft = {
+    thread_id: threadId,
+    name: document.getElementById("reply-name").value,
+    mail: document.getElementById("reply-mail").value,
+    body: document.getElementById("reply-body").value,
+  };
+  invoke("save_draft", { draft }).catch((error) => {
+    console.error("[JS] 下書きの保存に失敗しました:", error);
+  });
+}
+
+// スレッドを開いたときに下書きを戻す (下書きがなければ本文だけ空にして、名前とメール欄は残す)
+async function restoreDraft(threadId) {
+  const bodyElement = document.getElementById("reply-body");
+  bodyElement.value = "";
+  let draft;
+  try {
+    draft = await invoke("get_draft", { threadId });
+  } catch (error) {
+    console.error("[JS] 下書きの読み込みに失敗しました:", error);
+    return;
+  }
+  if (!draft || currentStreamingThreadId !== threadId) {
+    return;
+  }
+  document.getElementById("reply-name").value = draft.name;
+  document.getElementById("reply-mail").value = draft.mail;
+  bodyElement.value = draft.body;
+  document.getElementById("reply-status").textContent =
+    `下書きを戻しました (${new Date(draft.updated_at * 1000).toLocaleString()})`;
+}
+
+async function showDrafts() {
+  responseListElement.innerHTML = "";
+  mainContentPlaceholder.style.display = "none";
+  currentThreadTitleElement.textContent = "下書き";
+  currentThreadTitleElement.style.display = "block";
+  currentStreamingThreadId = null;
+  currentThreadToolbarElement.style.display = "none";
+  replyFormElement.style.display = "none";
+  newThreadFormElement.style.display = "none";
+  stopReadPositionTracking();
+
+  let drafts;
+  try {
+    drafts = await invoke("list_drafts");
+  } catch (error) {
+    console.error("[JS] 下書きの取得に失敗しました:", error);
+    responseListElement.innerHTML = `<li>下書きを取得できませんでした。<br>エラー: ${error}</li>`;
+    return;
+  }
+
+  currentThreadTitleElement.textContent = `下書き (${drafts.length}件)`;
+  if (drafts.length === 0) {
+    responseListElement.innerHTML = "<li>下書きはありません。</li>";
+    return;
+  }
+  const fragment = document.createDocumentFragment();
+  drafts.forEach((draft) => fragment.appendChild(createDraftElement(draft)));
+  responseListElement.appendChild(fragment);
+}
+
+function createDraftElement(draft) {
+  const item = document.createElement("li");
+  item.classList.add("response-item", "search-hit");
+
+  const header = document.createElement("div");
+  header.classList.add("response-header");
+  const title = document.createElement("span");
+  title.classList.add("search-hit-title");
+  title.textContent = draft.thread_title || draft.thread_id;
+  const meta = document.createElement("span");
+  meta.classList.add("response-created-at");
+  meta.textContent = new Date(draft.updated_at * 1000).toLocaleString();
+  const deleteButton = document.createElement("button");
+  deleteButton.type = "button";
+  deleteButton.textContent = "削除";
+  deleteButton.addEventListener("click", async (event) => {
+    event.stopPropagation();
+    try {
+      await invoke("delete_draft", { threadId: draft.thread_id });
+      item.remove();
+    } catch (error) {
+      console.error("[JS] 下書きの削除に失敗しました:", error);
+    }
+  });
+  header.appendChild(title);
+  header.appendChild(meta);
+  header.appendChild(deleteButton);
+
+  const body = document.createElement("div");
+  body.classList.add("response-content", "my-post-body");
+  body.textContent = draft.body;
+
+  item.appendChild(header);
+  item.appendChild(body);
+  item.addEventListener("click", () => {
+    displayThreadResponses(
+      draft.thread_id,
+      draft.thread_title || draft.thread_id,
+    );
+  });
+  return item;
+}
+
+// --- スレ立て ---
+function showNewThreadForm() {
+  responseListElement.innerHTML = "";
+  mainContentPlaceholder.style.display = "none";
+  currentThreadTitleElement.textContent = "新規スレッド作成";
+  currentThreadTitleElement.style.display = "block";
+  currentStreamingThreadId = null;
+  currentThreadToolbarElement.style.display = "none";
+  replyFormElement.style.display = "none";
+  newThreadFormElement.style.display = "flex";
+  document.getElementById("new-thread-status").textContent = "";
+  stopReadPositionTracking();
+  document.getElementById("new-thread-title").focus();
+}
+
+// タイトルと本文の長さは Rust 側で SETTING.TXT の制限と照らし合わせてから送信する
+async function createThread(confirmation = null) {
+  const titleElement = document.getElementById("new-thread-title");
+  const bodyElement = document.getElementById("new-thread-body");
+  const statusElement = document.getElementById("new-thread-status");
+  const submitButton = document.getElementById("new-thread-submit-button");
+  const form = {
+    title: titleElement.value,
+    name: document.getElementById("new-thread-name").value,
+    mail: document.getElementById("new-thread-mail").value,
+    body: bodyElement.value,
+    confirmation,
+  };
+
+  statusElement.textContent = "送信中...";
+  submitButton.disabled = true;
+  let result;
+  try {
+    result = await invoke("create_thread", { form });
+  } catch (error) {
+    console.error("[JS] スレ立てに失敗しました:", error);
+    statusElement.textContent = `スレ立てに失敗しました: ${error}`;
+    return;
+  } finally {
+    submitButton.disabled = false;
+  }
+
+  switch (result.status) {
+    case "created":
+      titleElement.value = "";
+      bodyElement.value = "";
+      await loadAndDisplayThreads();
+      if (result.thread_id) {
+        displayThreadResponses(result.thread_id, form.title);
+      } else {
+        statusElement.textContent =
+          "スレッドを立てました (スレッド一覧に表示されるまで時間がかかることがあります)";
+      }
+      break;
+    case "needs_confirmation":
+      if (confirm(`${result.message}\n\nスレッドを立てますか？`)) {
+        createThread(result.fields);
+      } else {
+        statusElement.textContent = "スレ立てを取りやめました。";
+      }
+      break;
+    case "rate_limited":
+      statusElement.textContent = `規制中です: ${result.message}`;
+      break;
+    default:
+      statusElement.textContent = `エラー: ${result.message}`;
+  }
+}
+
+// --- ローカルの dat ファイル ---
+// オンラインのスレッドと同じ createResponseElement で描画する。
+// ログDBには保存しないので、既読位置の記録や書き出しは行わない
+async function openLocalDat(path) {
+  responseListElement.innerHTML = "";
+  mainContentPlaceholder.style.display = "none";
+  currentThreadTitleElement.textContent = `読み込み中... ${path}`;
+  currentThreadTitleElement.style.display = "block";
+  currentStreamingThreadId = null;
+  currentThreadToolbarElement.style.display = "none";
+  replyFormElement.style.display = "none";
+  newThreadFormElement.style.display = "none";
+  stopReadPositionTracking();
+
+  let thread;
+  try {
+    thread = await invoke("open_local_dat", { path });
+  } catch (error) {
+    console.error("[JS] dat ファイルを開けませんでした:", error);
+    currentThreadTitleElement.textContent = path;
+    responseListElement.innerHTML = `<li>dat ファイルを開けませんでした。<br>エラー: ${error}</li>`;
+    return;
+  }
+
+  currentThreadTitleElement.textContent = `[ローカル] ${thread.title}`;
+  console.log(`[JS] dat ファイルを開きました: ${thread.path} (${thread.encoding})`);
+  const fragment = document.createDocumentFragment();
+  thread.responses.forEach((response) => {
+    fragment.appendChild(createResponseElement(response));
+  });
+  responseListElement.appendChild(fragment);
+}
+
+async function chooseLocalDat() {
+  const path = await window.__TAURI__.dialog.open({
+    filters: [{ name: "dat", extensions: ["dat"] }],
+  });
+  if (path) {
+    openLocalDat(path);
+  }
+}
+
+// ファイルメニュー、ドラッグ&ドロップ、起動時の引数から dat ファイルを開く
+async function setupLocalDatOpening() {
+  window.__TAURI__.event.listen("menu-open-dat", chooseLocalDat);
+  window.__TAURI__.webview.getCurrentWebview().onDragDropEvent((event) => {
+    if (event.payload.type !== "drop") {
+      return;
+    }
+    const datPath = event.payload.paths.find((path) =>
+      path.toLowerCase().endsWith(".dat"),
+    );
+    if (datPath) {
+      openLocalDat(datPath);
+    }
+  });
+
+  try {
+    const startupPath = await invoke("take_startup_dat");
+    if (startupPath) {
+      openLocalDat(startupPath);
+    }
+  } catch (error) {
+    console.error("[JS] 起動時の dat ファイルの確認に失敗しました:", error);
+  }
+}
+
+// --- ログ検索 ---
+async function searchLogs(query, fromDate, toDate) {
+  responseListElement.innerHTML = "";
+  mainContentPlaceholder.style.display = "none";
+  currentThreadTitleElement.textContent = `検索: ${query}`;
+  currentThreadTitleElement.style.display = "block";
+  currentStreamingThreadId = null;
+  currentThreadToolbarElement.style.display = "none";
+  replyFormElement.style.display = "none";
+  newThreadFormElement.style.display = "none";
+  stopReadPositionTracking();
+
+  let hits;
+  try {
+    hits = await invoke("search_logs", {
+      query: {
+        query,
+        from_date: fromDate || null,
+        to_date: toDate || null,
+      },
+    });
+  } catch (error) {
+    console.error("[JS] ログの検索に失敗しました:", error);
+    responseListElement.innerHTML = `<li>検索に失敗しました。<br>エラー: ${error}</li>`;
+    return;
+  }
+
+  currentThreadTitleElement.textContent = `検索: ${query} (${hits.length}件)`;
+  if (hits.length === 0) {
+    responseListElement.innerHTML = "<li>見つかりませんでした。</li>";
+    return;
+  }
+  const fragment = document.createDocumentFragment();
+  hits.forEach((hit) => fragment.appendChild(createSearchHitElement(hit)));
+  responseListElement.appendChild(fragment);
+}
+
+function createSearchHitElement(hit) {
+  const item = document.createElement("li");
+  item.classList.add("response-item", "search-hit");
+
+  const header = document.createElement("div");
+  header.classList.add("response-header");
+  const title = document.createElement("span");
+  title.classList.add("search-hit-title");
+  title.textContent = `${hit.thread_title || hit.thread_id} >>${hit.number}`;
+  const meta = document.createElement("span");
+  meta.classList.add("response-created-at");
+  meta.textContent = `${hit.author} ${hit.created_at}`;
+  header.appendChild(title);
+  header.appendChild(meta);
+
+  const snippet = document.createElement("div");
+  snippet.classList.add("response-content");
+  snippet.innerHTML = hit.snippet; // Rust 側でエスケープ済み (<mark> のみ)
+
+  item.appendChild(header);
+  item.appendChild(snippet);
+  item.addEventListener("click", () => {
+    displayThreadResponses(hit.thread_id, hit.thread_title, hit.number);
+  });
+  return item;
+}
+
+// --- 書き込み履歴 ---
+// レス番号は次に dat を取得したときに照合されるので、照合前のものは番号なしで表示する
+async function showMyPosts() {
+  responseListElement.innerHTML = "";
+  mainContentPlaceholder.style.display = "none";
+  currentThreadTitleElement.textContent = "書き込み履歴";
+  currentThreadTitleElement.style.display = "block";
+  currentStreamingThreadId = null;
+  currentThreadToolbarElement.style.display = "none";
+  replyFormElement.style.display = "none";
+  newThreadFormElement.style.display = "none";
+  stopReadPositionTracking();
+
+  let posts;
+  try {
+    posts = await invoke("get_my_posts", { limit: null });
+  } catch (error) {
+    console.error("[JS] 書き込み履歴の取得に失敗しました:", error);
+    responseListElement.innerHTML = `<li>書き込み履歴を取得できませんでした。<br>エラー: ${error}</li>`;
+    return;
+  }
+
+  currentThreadTitleElement.textContent = `書き込み履歴 (${posts.length}件)`;
+  if (posts.length === 0) {
+    responseListElement.innerHTML = "<li>まだ書き込んでいません。</li>";
+    return;
+  }
+  const fragment = document.createDocumentFragment();
+  posts.forEach((post) => fragment.appendChild(createMyPostElement(post)));
+  responseListElement.appendChild(fragment);
+}
+
+function createMyPostElement(post) {
+  const item = document.createElement("li");
+  item.classList.add("response-item", "search-hit");
+
+  const header = document.createElement("div");
+  header.classList.add("response-header");
+  const title = document.createElement("span");
+  title.classList.add("search-hit-title");
+  const number = post.number ? `>>${post.number}` : "(レス番号は未確認)";
+  title.textContent = `${post.thread_title || post.thread_id} ${number}`;
+  const meta = document.createElement("span");
+  meta.classList.add("response-created-at");
+  meta.textContent = new Date(post.posted_at * 1000).toLocaleString();
+  header.appendChild(title);
+  header.appendChild(meta);
+
+  const body = document.createElement("div");
+  body.classList.add("response-content", "my-post-body");
+  body.textContent = post.body;
+
+  item.appendChild(header);
+  item.appendChild(body);
+  item.addEventListener("click", () => {
+    displayThreadResponses(
+      post.thread_id,
+      post.thread_title || post.thread_id,
+      post.number,
+    );
+  });
+  return item;
+}
+
+// --- 自分の書き込みへの返信 ---
+// 受信箱を開いたら、その時点の未読を強調して表示し、すべて既読にする
+async function showReplyInbox() {
+  responseListElement.innerHTML = "";
+  mainContentPlaceholder.style.display = "none";
+  currentThreadTitleElement.textContent = "自分への返信";
+  currentThreadTitleElement.style.display = "block";
+  currentStreamingThreadId = null;
+  currentThreadToolbarElement.style.display = "none";
+  replyFormElement.style.display = "none";
+  newThreadFormElement.style.display = "none";
+  stopReadPositionTracking();
+
+  let replies;
+  try {
+    replies = await invoke("get_reply_inbox", { limit: null });
+  } catch (error) {
+    console.error("[JS] 受信箱の取得に失敗しました:", error);
+    responseListElement.innerHTML = `<li>受信箱を取得できませんでした。<br>エラー: ${error}</li>`;
+    return;
+  }
+
+  currentThreadTitleElement.textContent = `自分への返信 (${replies.length}件)`;
+  if (replies.length === 0) {
+    responseListElement.innerHTML = "<li>まだ返信はありません。</li>";
+  } else {
+    const fragment = document.createDocumentFragment();
+    replies.forEach((reply) =>
+      fragment.appendChild(createReplyElement(reply)),
+    );
+    responseListElement.appendChild(fragment);
+  }
+
+  if (replies.some((reply) => !reply.is_read)) {
+    try {
+      updateReplyBadge(await invoke("mark_replies_read", { ids: null }));
+    } catch (error) {
+      console.error("[JS] 返信を既読にできませんでした:", error);
+    }
+  }
+}
+
+function createReplyElement(reply) {
+  const item = document.createElement("li");
+  item.classList.add("response-item", "search-hit");
+  if (!reply.is_read) {
+    item.classList.add("unread-reply");
+  }
+
+  const header = document.createElement("div");
+  header.classList.add("response-header");
+  const title = document.createElement("span");
+  title.classList.add("search-hit-title");
+  title.textContent = `${reply.thread_title || reply.thread_id} ${reply.number} → >>${reply.my_number}`;
+  const meta = document.createElement("span");
+  meta.classList.add("response-created-at");
+  meta.textContent = `${reply.author} ${reply.created_at}`;
+  header.appendChild(title);
+  header.appendChild(meta);
+
+  const body = document.createElement("div");
+  body.classList.add("response-content", "my-post-body");
+  body.textContent = reply.content;
+
+  item.appendChild(header);
+  item.appendChild(body);
+  item.addEventListener("click", () => {
+    displayThreadResponses(
+      reply.thread_id,
+      reply.thread_title || reply.thread_id,
+      reply.number,
+    );
+  });
+  return item;
+}
+
+function updateReplyBadge(unreadCount) {
+  const badge = document.getElementById("reply-unread-badge");
+  if (!badge) {
+    return;
+  }
+  badge.textContent = unreadCount > 0 ? String(unreadCount) : "";
+  badge.classList.toggle("has-unread", unreadCount > 0);
+}
+
+// 未読数は起動時に取得し、以降はスレッドの更新やお気に入りの巡回のたびに Rust 側から届く
+async function setupReplyInbox() {
+  document
+    .getElementById("reply-inbox-button")
+    ?.addEventListener("click", showReplyInbox);
+  window.__TAURI__.event.listen("replies-updated", (event) => {
+    updateReplyBadge(event.payload);
+  });
+  try {
+    updateReplyBadge(await invoke("get_unread_reply_count"));
+  } catch (error) {
+    console.error("[JS] 返信の未読数を取得できませんでした:", error);
+  }
+}
+
+function setupSearchPanel() {
+  const searchForm = document.getElementById("search-panel");
+  if (!searchForm) {
+    return;
+  }
+  searchForm.addEventListener("submit", (event) => {
+    event.preventDefault();
+    const query = document.getElementById("search-input").value.trim();
+    if (!query) {
+      return;
+    }
+    searchLogs(
+      query,
+      document.getElementById("search-from-date").value,
+      document.getElementById("search-to-date").value,
+    );
+  });
+}
+
 document.addEventListener("DOMContentLoaded", () => {
   console.log("[JS] DOMContentLoaded event fired.");
   loadAndDisplayThreads();
+  setupSearchPanel();
+  setupThreadListControls();
+  document
+    .getElementById("export-thread-button")
+    ?.addEventListener("click", exportCurrentThread);
+  setupFavoritesPanel();
   initializeResizablePanels();
   setupThreadListRefresh();
+  setupLocalDatOpening();
+  replyFormElement?.addEventListener("submit", (event) => {
+    event.preventDefault();
+    postReply();
+  });
+  replyFormElement?.addEventListener("input", saveDraft);
+  document
+    .getElementById("reply-preview-button")
+    ?.addEventListener("click", previewReply);
+  document
+    .getElementById("reply-image-button")
+    ?.addEventListener("click", chooseReplyImage);
+  document
+    .getElementById("reply-body")
+    ?.addEventListener("paste", handleReplyPaste);
+  document
+    .getElementById("uploads-button")
+    ?.addEventListener("click", showImageUploads);
+  document
+    .getElementById("drafts-button")
+    ?.addEventListener("click", showDrafts);
+  newThreadFormElement?.addEventListener("submit", (event) => {
+    event.preventDefault();
+    createThread();
+  });
+  document
+    .getElementById("new-thread-button")
+    ?.addEventListener("click", showNewThreadForm);
+  document
+    .getElementById("my-posts-button")
+    ?.addEventListener("click", showMyPosts);
+  setupReplyInbox();
 });
@@ -4,6 +4,14 @@ const { getCurrentWindow } = window.__TAURI__.window; // 以前のやり取り
 
 const themeSelect = document.getElementById("theme-select");
 const fontSizeInput = document.getElementById("font-size-input");
+const profileInput = document.getElementById("profile-input");
+const imageHostUrlInput = document.getElementById("image-host-url-input");
+const imageHostClientIdInput = document.getElementById(
+  "image-host-client-id-input",
+);
+const imageMaxDimensionInput = document.getElementById(
+  "image-max-dimension-input",
+);
 const saveButton = document.getElementById("save-button");
 // HTML要素が正しく取得できているか確認
 console.log("settings.js: themeSelect 要素:", themeSelect);
@@ -24,6 +32,14 @@ async function loadSettings() {
       // 要素が存在するか確認
       fontSizeInput.value = settings.font_size || 14;
     }
+    if (profileInput && settings) {
+      profileInput.value = settings.profile || "default";
+    }
+    if (imageHostUrlInput && settings?.image_host) {
+      imageHostUrlInput.value = settings.image_host.api_url;
+      imageHostClientIdInput.value = settings.image_host.client_id;
+      imageMaxDimensionInput.value = settings.image_host.max_dimension ?? "";
+    }
   } catch (error) {
     console.error("settings.js: 設定の読み込みに失敗:", error);
     if (themeSelect) themeSelect.value = "light"; // フォールバック
@@ -44,6 +60,12 @@ async function saveSettings() {
   const newSettings = {
     theme: themeSelect.value,
     font_size: parseInt(fontSizeInput.value, 10),
+    profile: profileInput?.value.trim() || "default",
+    image_host: {
+      api_url: imageHostUrlInput?.value.trim() || "https://api.imgur.com/3",
+      client_id: imageHostClientIdInput?.value.trim() || "",
+      max_dimension: parseInt(imageMaxDimensionInput?.value, 10) || null,
+    },
   };
   console.log("settings.js: 保存する新しい設定:", newSettings);
 
@@ -75,8 +97,389 @@ if (saveButton) {
   );
 }
 
+// ===== NGルール =====
+// NGルールは保存ボタンを待たず、追加・削除した時点でログDBに反映される
+const NG_BOARD_ID = "tulipplantation";
+const NG_TARGET_LABELS = {
+  word: "ワード",
+  id: "ID",
+  name: "名前",
+  trip: "トリップ",
+  regex: "正規表現",
+};
+
+const ngTargetSelect = document.getElementById("ng-target-select");
+const ngPatternInput = document.getElementById("ng-pattern-input");
+const ngScopeSelect = document.getElementById("ng-scope-select");
+const ngDurationSelect = document.getElementById("ng-duration-select");
+const addNgRuleButton = document.getElementById("add-ng-rule-button");
+const ngRuleList = document.getElementById("ng-rule-list");
+const ngChainCheckbox = document.getElementById("ng-chain-checkbox");
+const ngChainDepthSelect = document.getElementById("ng-chain-depth-select");
+
+const FOLD_LABELS = {
+  nfkc: "全半角",
+  kana: "かな",
+  case: "大小",
+  zero_width: "ゼロ幅",
+};
+
+// 正規化のチェックボックスから { nfkc, kana, case, zero_width } を作る
+function readFolds(containerId) {
+  const folds = {};
+  document
+    .querySelectorAll(`#${containerId} input[data-fold]`)
+    .forEach((checkbox) => {
+      folds[checkbox.dataset.fold] = checkbox.checked;
+    });
+  return folds;
+}
+
+function formatFolds(folds) {
+  const labels = Object.keys(FOLD_LABELS)
+    .filter((key) => folds && folds[key])
+    .map((key) => FOLD_LABELS[key]);
+  return labels.length > 0 ? ` {${labels.join(",")}}` : "";
+}
+
+function formatNgScope(scope) {
+  switch (scope.type) {
+    case "board":
+      return `板: ${scope.board}`;
+    case "thread":
+      return `スレッド: ${scope.thread_id}`;
+    default:
+      return "全体";
+  }
+}
+
+async function loadNgRules() {
+  try {
+    const rules = await invoke("list_ng_rules");
+    ngRuleList.innerHTML = "";
+    rules.forEach((rule) => {
+      const item = document.createElement("li");
+      const text = document.createElement("span");
+      let label = `[${NG_TARGET_LABELS[rule.target]}] ${rule.pattern}`;
+      label += ` (${formatNgScope(rule.scope)}`;
+      if (rule.expires_at) {
+        const expires = new Date(rule.expires_at * 1000);
+        label += `, ${expires.toLocaleString()} まで`;
+      }
+      text.textContent = label + ")" + formatFolds(rule.folds);
+      const removeButton = document.createElement("button");
+      removeButton.textContent = "削除";
+      removeButton.addEventListener("click", () => removeNgRule(rule.id));
+      item.appendChild(text);
+      item.appendChild(removeButton);
+      ngRuleList.appendChild(item);
+    });
+  } catch (error) {
+    console.error("settings.js: NGルールの読み込みに失敗:", error);
+  }
+}
+
+async function addNgRule() {
+  const pattern = ngPatternInput.value.trim();
+  if (!pattern) {
+    return;
+  }
+  const scope =
+    ngScopeSelect.value === "board"
+      ? { type: "board", board: NG_BOARD_ID }
+      : { type: "global" };
+  const duration = ngDurationSelect.value;
+  try {
+    await invoke("add_ng_rule", {
+      rule: {
+        target: ngTargetSelect.value,
+        pattern,
+        scope,
+        duration_secs: duration ? parseInt(duration, 10) : null,
+        folds: readFolds("ng-folds"),
+      },
+    });
+    ngPatternInput.value = "";
+    await loadNgRules();
+  } catch (error) {
+    console.error("settings.js: NGルールの追加に失敗:", error);
+    alert("NGルールの追加に失敗しました: " + error);
+  }
+}
+
+async function removeNgRule(ruleId) {
+  try {
+    await invoke("remove_ng_rule", { ruleId });
+    await loadNgRules();
+  } catch (error) {
+    console.error("settings.js: NGルールの削除に失敗:", error);
+    alert("NGルールの削除に失敗しました: " + error);
+  }
+}
+
+if (addNgRuleButton) {
+  addNgRuleButton.addEventListener("click", addNgRule);
+}
+
+async function loadNgOptions() {
+  try {
+    const options = await invoke("get_ng_options");
+    ngChainCheckbox.checked = options.chain_enabled;
+    ngChainDepthSelect.value = String(options.chain_depth);
+  } catch (error) {
+    console.error("settings.js: NG設定の読み込みに失敗:", error);
+  }
+}
+
+async function saveNgOptions() {
+  try {
+    await invoke("set_ng_options", {
+      options: {
+        chain_enabled: ngChainCheckbox.checked,
+        chain_depth: parseInt(ngChainDepthSelect.value, 10),
+      },
+    });
+  } catch (error) {
+    console.error("settings.js: NG設定の保存に失敗:", error);
+    alert("NG設定の保存に失敗しました: " + error);
+  }
+}
+
+if (ngChainCheckbox && ngChainDepthSelect) {
+  ngChainCheckbox.addEventListener("change", saveNgOptions);
+  ngChainDepthSelect.addEventListener("change", saveNgOptions);
+}
+
+// ===== スレタイNG =====
+const titleNgPatternInput = document.getElementById("title-ng-pattern-input");
+const titleNgTypeSelect = document.getElementById("title-ng-type-select");
+const addTitleNgRuleButton = document.getElementById(
+  "add-title-ng-rule-button",
+);
+const titleNgRuleList = document.getElementById("title-ng-rule-list");
+
+async function loadTitleNgRules() {
+  try {
+    const rules = await invoke("list_title_ng_rules");
+    titleNgRuleList.innerHTML = "";
+    rules.forEach((rule) => {
+      const item = document.createElement("li");
+      const text = document.createElement("span");
+      const type = rule.is_regex ? "正規表現" : "ワード";
+      text.textContent =
+        `[${type}] ${rule.pattern}` + formatFolds(rule.folds);
+      const removeButton = document.createElement("button");
+      removeButton.textContent = "削除";
+      removeButton.addEventListener("click", () =>
+        removeTitleNgRule(rule.id),
+      );
+      item.appendChild(text);
+      item.appendChild(removeButton);
+      titleNgRuleList.appendChild(item);
+    });
+  } catch (error) {
+    console.error("settings.js: スレタイNGの読み込みに失敗:", error);
+  }
+}
+
+async function addTitleNgRule() {
+  const pattern = titleNgPatternInput.value.trim();
+  if (!pattern) {
+    return;
+  }
+  try {
+    await invoke("add_title_ng_rule", {
+      pattern,
+      isRegex: titleNgTypeSelect.value === "regex",
+      folds: readFolds("title-ng-folds"),
+    });
+    titleNgPatternInput.value = "";
+    await loadTitleNgRules();
+  } catch (error) {
+    console.error("settings.js: スレタイNGの追加に失敗:", error);
+    alert("スレタイNGの追加に失敗しました: " + error);
+  }
+}
+
+async function removeTitleNgRule(ruleId) {
+  try {
+    await invoke("remove_title_ng_rule", { ruleId });
+    await loadTitleNgRules();
+  } catch (error) {
+    console.error("settings.js: スレタイNGの削除に失敗:", error);
+    alert("スレタイNGの削除に失敗しました: " + error);
+  }
+}
+
+if (addTitleNgRuleButton) {
+  addTitleNgRuleButton.addEventListener("click", addTitleNgRule);
+}
+
+// ===== ログの取り込み =====
+const importPathInput = document.getElementById("import-path-input");
+const importBoardInput = document.getElementById("import-board-input");
+const importOverwriteCheckbox = document.getElementById(
+  "import-overwrite-checkbox",
+);
+const importReport = document.getElementById("import-report");
+
+const IMPORT_FORMAT_LABELS = {
+  plain_dat: "dat",
+  jane_style: "Jane",
+  two_ch_mate: "2chMate",
+};
+
+async function chooseImportFolder() {
+  const folder = await window.__TAURI__.dialog.open({ directory: true });
+  if (folder) {
+    importPathInput.value = folder;
+  }
+}
+
+function formatImportReport(report) {
+  const lines = [];
+  lines.push(
+    `${report.dry_run ? "[確認のみ] " : ""}スレッド ${report.threads.length} 件 / ` +
+      `お気に入り ${report.favorites.length} 件 / ` +
+      `スキップ ${report.skipped.length} 件`,
+  );
+  report.threads.forEach((thread) => {
+    const format = IMPORT_FORMAT_LABELS[thread.format] || thread.format;
+    let line = `  [${format}/${thread.encoding}] `;
+    line += `${thread.board}/${thread.thread_id}`;
+    line += ` ${thread.title} (${thread.response_count}レス`;
+    if (thread.last_read) {
+      line += `, ${thread.last_read}まで既読`;
+    }
+    line += thread.already_stored ? ", 上書き)" : ")";
+    lines.push(line);
+  });
+  report.favorites.forEach((favorite) => {
+    lines.push(
+      `  [お気に入り] ${favorite.board}/${favorite.thread_id} ${favorite.title}`,
+    );
+  });
+  report.skipped.forEach((skip) => {
+    lines.push(`  [スキップ] ${skip.path}: ${skip.reason}`);
+  });
+  return lines.join("\n");
+}
+
+async function importLogs(dryRun) {
+  if (!importPathInput.value) {
+    alert("取り込むフォルダを選択してください。");
+    return;
+  }
+  importReport.textContent = "読み込み中...";
+  try {
+    const report = await invoke("import_logs", {
+      path: importPathInput.value,
+      board: importBoardInput.value.trim() || null,
+      overwrite: importOverwriteCheckbox.checked,
+      dryRun,
+    });
+    importReport.textContent = formatImportReport(report);
+  } catch (error) {
+    console.error("settings.js: ログの取り込みに失敗:", error);
+    importReport.textContent = "ログの取り込みに失敗しました: " + error;
+  }
+}
+
+document
+  .getElementById("import-choose-button")
+  ?.addEventListener("click", chooseImportFolder);
+document
+  .getElementById("import-dry-run-button")
+  ?.addEventListener("click", () => importLogs(true));
+document
+  .getElementById("import-run-button")
+  ?.addEventListener("click", () => importLogs(false));
+
+// ===== Cookie =====
+// Cookie はプロファイルごとに保存され、書き込みのたびに更新される
+const cookieDomainList = document.getElementById("cookie-domain-list");
+const cookieImportReport = document.getElementById("cookie-import-report");
+
+function formatCookie(cookie) {
+  const expires = cookie.expires
+    ? `${new Date(cookie.expires * 1000).toLocaleString()} まで`
+    : "セッション";
+  return `${cookie.name} (${expires}${cookie.secure ? ", secure" : ""})`;
+}
+
+async function loadCookies() {
+  try {
+    const domains = await invoke("list_cookies");
+    cookieDomainList.innerHTML = "";
+    if (domains.length === 0) {
+      cookieDomainList.innerHTML = "<li><span>Cookie はありません</span></li>";
+      return;
+    }
+    domains.forEach((entry) => {
+      const item = document.createElement("li");
+      const text = document.createElement("span");
+      text.textContent = `${entry.domain}: ${entry.cookies
+        .map(formatCookie)
+        .join(", ")}`;
+      const clearButton = document.createElement("button");
+      clearButton.textContent = "削除";
+      clearButton.addEventListener("click", () => clearCookies(entry.domain));
+      item.appendChild(text);
+      item.appendChild(clearButton);
+      cookieDomainList.appendChild(item);
+    });
+  } catch (error) {
+    console.error("settings.js: Cookie の読み込みに失敗:", error);
+  }
+}
+
+// domain が null の場合はすべての Cookie を削除する
+async function clearCookies(domain) {
+  const target = domain ? `${domain} の Cookie` : "すべての Cookie";
+  if (!confirm(`${target}を削除しますか？`)) {
+    return;
+  }
+  try {
+    await invoke("clear_cookies", { domain });
+    await loadCookies();
+  } catch (error) {
+    console.error("settings.js: Cookie の削除に失敗:", error);
+    alert("Cookie の削除に失敗しました: " + error);
+  }
+}
+
+async function importCookieFile() {
+  const path = await window.__TAURI__.dialog.open({
+    filters: [{ name: "cookies.txt", extensions: ["txt"] }],
+  });
+  if (!path) {
+    return;
+  }
+  try {
+    const report = await invoke("import_cookie_file", { path });
+    const lines = [`${report.imported} 個の Cookie を取り込みました`];
+    report.skipped.forEach((reason) => lines.push(`  [スキップ] ${reason}`));
+    cookieImportReport.textContent = lines.join("\n");
+    await loadCookies();
+  } catch (error) {
+    console.error("settings.js: Cookie の取り込みに失敗:", error);
+    cookieImportReport.textContent = "Cookie の取り込みに失敗しました: " + error;
+  }
+}
+
+document
+  .getElementById("cookie-import-button")
+  ?.addEventListener("click", importCookieFile);
+document
+  .getElementById("cookie-clear-all-button")
+  ?.addEventListener("click", () => clearCookies(null));
+
 // DOMが読み込まれたら設定をロード
 document.addEventListener("DOMContentLoaded", () => {
   console.log("settings.js: DOMContentLoaded イベント発生");
   loadSettings();
+  loadNgRules();
+  loadNgOptions();
+  loadTitleNgRules();
+  loadCookies();
 });